@@ -0,0 +1,26 @@
+//! 构建时记录 git 提交，写入输出目录的 manifest.toml
+//!
+//! 不在 git 仓库中构建(如从发布包构建)或系统中没有 git 时不设置，运行时记为 unknown。
+
+use std::process::Command;
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/index");
+    println!("cargo:rerun-if-changed=.git/refs");
+    let Some(commit) = git(&["rev-parse", "HEAD"]).filter(|commit| !commit.is_empty()) else {
+        return;
+    };
+    // 工作区有未提交的修改时标记为 dirty
+    let dirty = git(&["status", "--porcelain", "--untracked-files=no"]).is_some_and(|status| !status.is_empty());
+    let commit = if dirty { format!("{}-dirty", commit) } else { commit };
+    println!("cargo:rustc-env=LDC_GIT_COMMIT={}", commit);
+}
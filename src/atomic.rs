@@ -0,0 +1,43 @@
+//! 原子地替换文件
+//!
+//! 先写入同目录下的临时文件并 fsync，再重命名覆盖目标文件，进程在写入途中退出时
+//! 目标文件保持原样，不会留下只写了一半的结果。
+
+use anyhow::{Context, Result};
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// 写入文件，完成前目标文件保持原有内容
+pub fn write(path: &Path, data: impl AsRef<[u8]>) -> Result<()> {
+    let tmp = temp_path(path);
+    let mut file = File::create(&tmp).with_context(|| format!("Failed to create {}", tmp.display()))?;
+    file.write_all(data.as_ref())
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    drop(file);
+    commit(&tmp, path)
+}
+
+/// 目标文件对应的临时文件
+pub fn temp_path(path: &Path) -> PathBuf {
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// 把写好的临时文件落盘并重命名为目标文件
+pub fn commit(tmp: &Path, path: &Path) -> Result<()> {
+    File::open(tmp)
+        .and_then(|file| file.sync_all())
+        .with_context(|| format!("Failed to sync {}", tmp.display()))?;
+    fs::rename(tmp, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    // 目录项也需要落盘，重命名才能在断电后保留
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        File::open(dir).and_then(|dir| dir.sync_all()).ok();
+    }
+    Ok(())
+}
@@ -0,0 +1,341 @@
+//! 自动调整并发数
+//!
+//! `--workers auto` 启动 [`MAX_WORKERS`] 个 worker，同时进行的查询数由这里的并发上限控制：
+//! 上限从 [`START_LIMIT`] 开始，每个观察窗口([`WINDOW`])结束时按窗口内的响应调整。没有限流、
+//! 错误率不超过 [`MAX_ERROR_RATE`]、平均延迟没有明显高于此前的水平，并且查询确实因上限而
+//! 等待过时，上限增加：第一次遇到问题之前加倍，之后每次加一；出现限流时减半，错误率过高时
+//! 减少四分之一。加性增、乘性减使并发数停留在查询服务能承受的水平附近。查询间隔仍由限速器控制，限速器是瓶颈时上限不再增加。
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::stats::ScanStats;
+
+/// 自动调整时启动的 worker 数，即并发上限的最大值
+pub const MAX_WORKERS: usize = 64;
+/// 初始并发上限
+pub const START_LIMIT: usize = 4;
+/// 观察窗口的长度
+const WINDOW: Duration = Duration::from_secs(5);
+/// 窗口内的查询少于该数时只根据限流和错误缩小上限，不增加
+const MIN_WINDOW_QUERIES: u64 = 8;
+/// 可以继续增加并发的最高错误率
+const MAX_ERROR_RATE: f64 = 0.05;
+/// 平均延迟超过基准的该倍数时视为延迟升高，不再增加并发
+const LATENCY_RISE: f64 = 1.5;
+
+/// --workers 参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Workers {
+    Fixed(usize),
+    /// 按观察到的限流和错误自动调整
+    Auto,
+}
+
+/// 解析 `--workers`：正整数或 auto
+pub fn parse_workers(value: &str) -> Result<Workers, String> {
+    if value.eq_ignore_ascii_case("auto") {
+        return Ok(Workers::Auto);
+    }
+    match value.parse::<usize>() {
+        Ok(workers) if workers > 0 => Ok(Workers::Fixed(workers)),
+        _ => Err(format!("expected a positive number or `auto`, got `{}`", value)),
+    }
+}
+
+/// 自动调整的并发上限
+pub struct Autoscaler {
+    gate: Semaphore,
+    /// 缩小上限时仍被占用、归还时收回的名额数
+    debt: AtomicUsize,
+    /// 本窗口内是否有查询因上限而等待
+    saturated: AtomicBool,
+    state: Mutex<ScalerState>,
+}
+
+struct ScalerState {
+    limit: usize,
+    /// 还没有遇到过限流或错误，上限按倍数增加
+    slow_start: bool,
+    window: Window,
+    /// 正常窗口的平均延迟(秒)的滑动平均
+    baseline: Option<f64>,
+}
+
+/// 一个观察窗口内的响应
+#[derive(Default)]
+struct Window {
+    queries: u64,
+    rate_limited: u64,
+    errors: u64,
+    latency: Duration,
+}
+
+/// 一次查询占用的名额，释放时归还
+pub struct Slot<'a> {
+    scaler: &'a Autoscaler,
+    permit: Option<SemaphorePermit<'a>>,
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        let Some(permit) = self.permit.take() else {
+            return;
+        };
+        let repaid = self
+            .scaler
+            .debt
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |debt| debt.checked_sub(1))
+            .is_ok();
+        if repaid {
+            permit.forget();
+        }
+    }
+}
+
+impl Autoscaler {
+    /// 创建并发上限并启动每个窗口调整一次的后台任务，调整结果记入 stats
+    pub fn start(stats: Arc<ScanStats>) -> Arc<Self> {
+        let scaler = Arc::new(Self::new());
+        stats.record_concurrency(START_LIMIT);
+        // 后台任务只持有弱引用，扫描器释放后随之结束
+        let weak = Arc::downgrade(&scaler);
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(WINDOW);
+            ticks.tick().await;
+            loop {
+                ticks.tick().await;
+                let Some(scaler) = weak.upgrade() else {
+                    break;
+                };
+                if let Some(limit) = scaler.adjust() {
+                    stats.record_concurrency(limit);
+                }
+            }
+        });
+        scaler
+    }
+
+    fn new() -> Self {
+        Self {
+            gate: Semaphore::new(START_LIMIT),
+            debt: AtomicUsize::new(0),
+            saturated: AtomicBool::new(false),
+            state: Mutex::new(ScalerState {
+                limit: START_LIMIT,
+                slow_start: true,
+                window: Window::default(),
+                baseline: None,
+            }),
+        }
+    }
+
+    /// 等待一个查询名额
+    pub async fn acquire(&self) -> Slot<'_> {
+        if self.gate.available_permits() == 0 {
+            self.saturated.store(true, Ordering::Relaxed);
+        }
+        Slot {
+            scaler: self,
+            permit: self.gate.acquire().await.ok(),
+        }
+    }
+
+    /// 记录一次查询的耗时和结果
+    pub fn record(&self, latency: Duration, rate_limited: bool, error: bool) {
+        let mut state = self.state.lock().unwrap();
+        let window = &mut state.window;
+        window.queries += 1;
+        window.latency += latency;
+        window.rate_limited += rate_limited as u64;
+        window.errors += error as u64;
+    }
+
+    /// 按刚结束的窗口调整上限，上限改变时返回新的上限
+    fn adjust(&self) -> Option<usize> {
+        let mut state = self.state.lock().unwrap();
+        let window = std::mem::take(&mut state.window);
+        let saturated = self.saturated.swap(false, Ordering::Relaxed);
+        let limit = state.limit;
+
+        let target = if window.rate_limited > 0 {
+            limit / 2
+        } else if window.queries > 0 && window.errors as f64 / window.queries as f64 > MAX_ERROR_RATE {
+            limit - limit.div_ceil(4)
+        } else if window.queries >= MIN_WINDOW_QUERIES {
+            let latency = window.latency.as_secs_f64() / window.queries as f64;
+            let baseline = *state.baseline.get_or_insert(latency);
+            state.baseline = Some(baseline * 0.8 + latency * 0.2);
+            match saturated && latency <= baseline * LATENCY_RISE {
+                true if state.slow_start => limit * 2,
+                true => limit + 1,
+                false => limit,
+            }
+        } else {
+            limit
+        };
+        if target < limit {
+            state.slow_start = false;
+        }
+        let target = target.clamp(1, MAX_WORKERS);
+        if target == limit {
+            return None;
+        }
+
+        if target > limit {
+            // 先抵消尚未收回的名额
+            let mut grow = target - limit;
+            while grow > 0
+                && self
+                    .debt
+                    .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |debt| debt.checked_sub(1))
+                    .is_ok()
+            {
+                grow -= 1;
+            }
+            self.gate.add_permits(grow);
+        } else {
+            // 空闲的名额立即收回，其余的在查询结束归还时收回
+            let shrink = limit - target;
+            let idle = self.gate.available_permits().min(shrink);
+            let taken = match self.gate.try_acquire_many(idle as u32) {
+                Ok(permit) => {
+                    permit.forget();
+                    idle
+                }
+                Err(_) => 0,
+            };
+            self.debt.fetch_add(shrink - taken, Ordering::Relaxed);
+        }
+        state.limit = target;
+        Some(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LATENCY: Duration = Duration::from_millis(10);
+
+    /// 记录一个窗口的查询，saturated 表示其间有查询因上限而等待
+    fn window(scaler: &Autoscaler, queries: u64, rate_limited: u64, errors: u64, latency: Duration, saturated: bool) {
+        for index in 0..queries {
+            scaler.record(latency, index < rate_limited, index < errors);
+        }
+        scaler.saturated.store(saturated, Ordering::Relaxed);
+    }
+
+    fn limit(scaler: &Autoscaler) -> usize {
+        scaler.state.lock().unwrap().limit
+    }
+
+    #[test]
+    fn limit_doubles_until_the_first_problem_then_grows_by_one() {
+        let scaler = Autoscaler::new();
+        // 慢启动：每个饱和的正常窗口加倍，最多 MAX_WORKERS
+        for expected in [8, 16, 32, 64] {
+            window(&scaler, 10, 0, 0, LATENCY, true);
+            assert_eq!(scaler.adjust(), Some(expected));
+            assert_eq!(scaler.gate.available_permits(), expected);
+        }
+        window(&scaler, 10, 0, 0, LATENCY, true);
+        assert_eq!(scaler.adjust(), None);
+        assert_eq!(limit(&scaler), MAX_WORKERS);
+
+        // 限流减半并结束慢启动，之后每次加一
+        window(&scaler, 10, 1, 0, LATENCY, true);
+        assert_eq!(scaler.adjust(), Some(32));
+        window(&scaler, 10, 0, 0, LATENCY, true);
+        assert_eq!(scaler.adjust(), Some(33));
+        assert_eq!(scaler.gate.available_permits(), 33);
+
+        // 没有因上限而等待、延迟明显升高或查询太少时不增加
+        window(&scaler, 10, 0, 0, LATENCY, false);
+        assert_eq!(scaler.adjust(), None);
+        window(&scaler, 10, 0, 0, LATENCY * 2, true);
+        assert_eq!(scaler.adjust(), None);
+        window(&scaler, MIN_WINDOW_QUERIES - 1, 0, 0, LATENCY, true);
+        assert_eq!(scaler.adjust(), None);
+
+        // 错误率过高时减少四分之一(向上取整)
+        window(&scaler, 20, 0, 1, LATENCY, true);
+        assert_eq!(scaler.adjust(), Some(34), "5% errors is still acceptable");
+        window(&scaler, 4, 0, 1, LATENCY, true);
+        assert_eq!(scaler.adjust(), Some(25));
+
+        // 反复限流时减到 1 为止
+        for expected in [12, 6, 3, 1] {
+            window(&scaler, 10, 1, 0, LATENCY, true);
+            assert_eq!(scaler.adjust(), Some(expected));
+            assert_eq!(scaler.gate.available_permits(), expected);
+        }
+        window(&scaler, 10, 1, 0, LATENCY, true);
+        assert_eq!(scaler.adjust(), None);
+        assert_eq!(limit(&scaler), 1);
+        assert_eq!(scaler.gate.available_permits(), 1);
+    }
+
+    #[tokio::test]
+    async fn busy_slots_are_reclaimed_when_released() {
+        let scaler = Autoscaler::new();
+        let mut slots = Vec::new();
+        for _ in 0..START_LIMIT {
+            slots.push(scaler.acquire().await);
+        }
+
+        // 名额都被占用，缩小的部分记为欠账
+        window(&scaler, 10, 1, 0, LATENCY, true);
+        assert_eq!(scaler.adjust(), Some(2));
+        assert_eq!(scaler.gate.available_permits(), 0);
+        assert_eq!(scaler.debt.load(Ordering::Relaxed), 2);
+
+        // 归还的名额先抵消欠账
+        drop(slots.pop());
+        assert_eq!(scaler.gate.available_permits(), 0);
+        assert_eq!(scaler.debt.load(Ordering::Relaxed), 1);
+
+        // 增加上限时先抵消欠账，不增加名额
+        window(&scaler, 10, 0, 0, LATENCY, true);
+        assert_eq!(scaler.adjust(), Some(3));
+        assert_eq!(scaler.debt.load(Ordering::Relaxed), 0);
+        assert_eq!(scaler.gate.available_permits(), 0);
+
+        // 三个仍被占用的名额全部归还后恰好等于上限
+        slots.clear();
+        assert_eq!(scaler.gate.available_permits(), 3);
+
+        // 空闲的名额在缩小时立即收回
+        window(&scaler, 10, 1, 0, LATENCY, true);
+        assert_eq!(scaler.adjust(), Some(1));
+        assert_eq!(scaler.debt.load(Ordering::Relaxed), 0);
+        assert_eq!(scaler.gate.available_permits(), 1);
+
+        // 占用一个、再缩小到 1 以下时仍保持 1
+        let slot = scaler.acquire().await;
+        window(&scaler, 10, 1, 0, LATENCY, true);
+        assert_eq!(scaler.adjust(), None);
+        drop(slot);
+        assert_eq!(scaler.gate.available_permits(), 1);
+
+        // 部分占用时空闲的立即收回，占用的归还时收回
+        window(&scaler, 10, 0, 0, LATENCY, true);
+        assert_eq!(scaler.adjust(), Some(2));
+        window(&scaler, 10, 0, 0, LATENCY, true);
+        assert_eq!(scaler.adjust(), Some(3));
+        let busy = [scaler.acquire().await, scaler.acquire().await];
+        window(&scaler, 10, 1, 0, LATENCY, true);
+        assert_eq!(scaler.adjust(), Some(1));
+        assert_eq!(scaler.gate.available_permits(), 0);
+        assert_eq!(scaler.debt.load(Ordering::Relaxed), 1);
+        drop(busy);
+        assert_eq!(scaler.gate.available_permits(), 1);
+        assert_eq!(scaler.debt.load(Ordering::Relaxed), 0);
+    }
+}
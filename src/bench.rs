@@ -0,0 +1,149 @@
+use anyhow::Result;
+use clap::Args as ClapArgs;
+use console::style;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::time::sleep;
+
+use crate::{
+    client::WhoisClient,
+    stats::ScanStats,
+    DomainStatus,
+};
+
+/// 基准测试使用的已知已注册域名
+const BENCH_DOMAINS: &[&str] = &[
+    "nic.li", "google.li", "post.li", "bank.li", "apple.li", "news.li", "shop.li", "mail.li",
+    "web.li", "info.li",
+];
+
+/// bench 子命令参数
+#[derive(ClapArgs, Debug)]
+pub struct BenchArgs {
+    /// 起始查询速率(次/秒)
+    #[arg(long, default_value = "1.0")]
+    start_qps: f64,
+
+    /// 最大查询速率(次/秒)
+    #[arg(long, default_value = "20.0")]
+    max_qps: f64,
+
+    /// 每档速率的增长倍数
+    #[arg(long, default_value = "1.5")]
+    step_factor: f64,
+
+    /// 每档速率发送的查询数
+    #[arg(long, default_value = "20")]
+    queries_per_step: usize,
+
+    /// 两档速率之间的冷却时间(秒)
+    #[arg(long, default_value = "10.0")]
+    cooldown: f64,
+}
+
+/// 单档速率的测试结果
+struct StepOutcome {
+    qps: f64,
+    rate_limited: u64,
+    errors: usize,
+    p95_ms: f64,
+}
+
+/// 以指定速率发送一轮查询
+async fn run_step<C: WhoisClient>(client: &Arc<C>, qps: f64, queries: usize) -> StepOutcome {
+    let stats = Arc::new(ScanStats::new());
+    let interval = Duration::from_secs_f64(1.0 / qps);
+    let mut tasks = Vec::with_capacity(queries);
+
+    for i in 0..queries {
+        let stats = stats.clone();
+        let client = client.clone();
+        let domain = BENCH_DOMAINS[i % BENCH_DOMAINS.len()];
+        tasks.push(tokio::spawn(async move {
+            let started = Instant::now();
+            let outcome = client.check_once(domain).await;
+            stats.record_query(0, started.elapsed());
+            match outcome {
+                Ok((code, _)) => {
+                    if DomainStatus::from_reply_code(code) == DomainStatus::RateLimited {
+                        stats.record_rate_limited(0);
+                    }
+                    false
+                }
+                Err(_) => {
+                    stats.record_error(0);
+                    true
+                }
+            }
+        }));
+        sleep(interval).await;
+    }
+
+    let mut errors = 0;
+    for task in futures::future::join_all(tasks).await {
+        if task.unwrap_or(true) {
+            errors += 1;
+        }
+    }
+
+    let summary = stats.summary();
+    StepOutcome {
+        qps,
+        rate_limited: summary.rate_limited,
+        errors,
+        p95_ms: summary.latency_p95_ms,
+    }
+}
+
+/// 运行基准测试
+pub async fn run<C: WhoisClient>(args: BenchArgs, client: C) -> Result<()> {
+    anyhow::ensure!(args.start_qps > 0.0, "start qps must be positive");
+    anyhow::ensure!(args.step_factor > 1.0, "step factor must be greater than 1");
+
+    let client = Arc::new(client);
+    println!(
+        "{}",
+        style(format!("基准测试 {}", client.endpoint())).bold()
+    );
+
+    let mut safe: Option<StepOutcome> = None;
+    let mut qps = args.start_qps;
+    while qps <= args.max_qps {
+        let outcome = run_step(&client, qps, args.queries_per_step).await;
+        let clean = outcome.rate_limited == 0 && outcome.errors == 0;
+        let line = format!(
+            "{:>6.2} 次/秒: 限流 {} 次, 错误 {} 次, p95 {:.0}ms",
+            outcome.qps, outcome.rate_limited, outcome.errors, outcome.p95_ms
+        );
+        if clean {
+            println!("{}", style(format!("✓ {}", line)).green());
+            safe = Some(outcome);
+        } else {
+            println!("{}", style(format!("✗ {}", line)).red());
+            break;
+        }
+
+        qps *= args.step_factor;
+        sleep(Duration::from_secs_f64(args.cooldown)).await;
+    }
+
+    match safe {
+        Some(outcome) => {
+            // 留出 20% 余量，并发数按 速率 × p95 延迟 估算
+            let qps = outcome.qps * 0.8;
+            let workers = ((qps * outcome.p95_ms / 1000.0).ceil() as usize).max(1) + 1;
+            println!("\n{}", style("推荐设置:").green().bold());
+            println!("  --workers {} --delay {:.2}", workers, 1.0 / qps);
+        }
+        None => {
+            println!(
+                "\n{}",
+                style("起始速率已触发限流或错误，请降低 --start-qps 后重试").yellow()
+            );
+        }
+    }
+
+    Ok(())
+}
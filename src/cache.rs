@@ -0,0 +1,129 @@
+//! 查询结果缓存
+//!
+//! 使用 `--cache-ttl <DURATION>` 时，得出可用或已注册的查询结果在进程内缓存该时长，期间再次
+//! 查询同一域名(监视列表中重叠的域名、品牌监测的下一轮、流式输入中重复的标签)直接使用缓存
+//! 的结果，不占用查询服务的速率预算。同时指定 `--cache-file <FILE>` 时缓存在启动时从文件读取、
+//! 结束时写回，多次运行之间共享；写回前合并其他进程在此期间写入的较新的记录。限流和出错的
+//! 结果不缓存。
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use crate::atomic;
+
+/// 一条缓存的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub domain: String,
+    pub reply_code: i32,
+    pub message: String,
+    /// 实际查询的时间
+    pub checked_at: chrono::DateTime<chrono::FixedOffset>,
+}
+
+/// 带有效期的结果缓存
+pub struct ResultCache {
+    ttl: chrono::Duration,
+    path: Option<PathBuf>,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl ResultCache {
+    /// 创建缓存，指定文件时读取其中尚未过期的记录，文件不存在时在保存时创建
+    pub fn open(ttl: Duration, path: Option<&Path>) -> Result<Self> {
+        let ttl = chrono::Duration::from_std(ttl).context("--cache-ttl is too long")?;
+        let cache = Self {
+            ttl,
+            path: path.map(Path::to_path_buf),
+            entries: Mutex::new(HashMap::new()),
+        };
+        if let Some(path) = path.filter(|path| path.exists()) {
+            let loaded = cache.read(path)?;
+            cache.entries.lock().unwrap().extend(loaded);
+        }
+        Ok(cache)
+    }
+
+    /// 读取缓存文件中尚未过期的记录，每行一条 JSON，写入中断的最后一行被忽略
+    fn read(&self, path: &Path) -> Result<HashMap<String, CacheEntry>> {
+        let content =
+            fs::read_to_string(path).with_context(|| format!("Failed to read cache file {}", path.display()))?;
+        let lines: Vec<&str> = content.lines().filter(|line| !line.trim().is_empty()).collect();
+        let mut entries = HashMap::new();
+        for (number, line) in lines.iter().enumerate() {
+            let entry: CacheEntry = match serde_json::from_str(line) {
+                Ok(entry) => entry,
+                Err(_) if number + 1 == lines.len() => break,
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("Invalid record on line {} of {}", number + 1, path.display()))
+                }
+            };
+            if self.is_fresh(&entry) {
+                entries.insert(entry.domain.clone(), entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    fn is_fresh(&self, entry: &CacheEntry) -> bool {
+        chrono::Utc::now().signed_duration_since(entry.checked_at) < self.ttl
+    }
+
+    /// 域名尚未过期的缓存结果
+    pub fn get(&self, domain: &str) -> Option<CacheEntry> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(domain)
+            .filter(|entry| self.is_fresh(entry))
+            .cloned()
+    }
+
+    /// 缓存一条查询结果
+    pub fn insert(&self, domain: &str, reply_code: i32, message: &str, checked_at: chrono::DateTime<chrono::FixedOffset>) {
+        self.entries.lock().unwrap().insert(
+            domain.to_string(),
+            CacheEntry {
+                domain: domain.to_string(),
+                reply_code,
+                message: message.to_string(),
+                checked_at,
+            },
+        );
+    }
+
+    /// 把尚未过期的记录写回缓存文件，同一域名保留查询时间较新的一条
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut merged = if path.exists() { self.read(path)? } else { HashMap::new() };
+        for (domain, entry) in self.entries.lock().unwrap().iter() {
+            if !self.is_fresh(entry) {
+                continue;
+            }
+            match merged.get(domain) {
+                Some(existing) if existing.checked_at >= entry.checked_at => {}
+                _ => {
+                    merged.insert(domain.clone(), entry.clone());
+                }
+            }
+        }
+        let mut entries: Vec<&CacheEntry> = merged.values().collect();
+        entries.sort_by(|a, b| a.domain.cmp(&b.domain));
+        let mut content = String::new();
+        for entry in entries {
+            content.push_str(&serde_json::to_string(entry).context("Failed to serialize cache entry")?);
+            content.push('\n');
+        }
+        atomic::write(path, content).with_context(|| format!("Failed to write cache file {}", path.display()))
+    }
+}
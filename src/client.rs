@@ -0,0 +1,379 @@
+//! 查询服务的传输层抽象
+//!
+//! 扫描器通过 [`WhoisClient`] 建立连接、发送查询和解析响应，既可以使用访问
+//! SWITCH 服务的 [`TcpWhoisClient`]，也可以使用按预设响应回答的
+//! [`MockWhoisClient`]，后者不访问网络，便于离线测试解析、重试和限流处理。
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+use crate::rng;
+
+/// 查询服务客户端
+pub trait WhoisClient: Send + Sync + 'static {
+    /// 一条可发送查询的连接
+    type Connection: Send + 'static;
+
+    /// 服务端标识，用于提示信息和错误信息
+    fn endpoint(&self) -> String;
+
+    /// 建立新连接
+    fn connect(&self) -> impl Future<Output = Result<Self::Connection>> + Send;
+
+    /// 在连接上发送一次查询(完整域名，如 `abc.li`)并返回原始响应
+    ///
+    /// 对端已关闭连接时返回 `Ok(None)`，调用方可以重新建立连接后重试。
+    fn query(
+        &self,
+        conn: &mut Self::Connection,
+        domain: &str,
+    ) -> impl Future<Output = Result<Option<String>>> + Send;
+
+    /// 在连接上依次发送多个查询，按顺序返回收到的原始响应
+    ///
+    /// 对端中途关闭连接时返回已收到的响应，少于查询数的部分由调用方重新查询。
+    fn query_batch(
+        &self,
+        conn: &mut Self::Connection,
+        domains: &[String],
+    ) -> impl Future<Output = Result<Vec<String>>> + Send {
+        async move {
+            let mut replies = Vec::with_capacity(domains.len());
+            for domain in domains {
+                match self.query(conn, domain).await? {
+                    Some(response) => replies.push(response),
+                    None => break,
+                }
+            }
+            Ok(replies)
+        }
+    }
+
+    /// 解析原始响应，返回响应码和消息
+    fn parse(&self, response: &str) -> (i32, String) {
+        parse_reply(response)
+    }
+
+    /// 空闲连接是否仍然可用
+    fn is_alive(&self, _conn: &Self::Connection) -> bool {
+        true
+    }
+
+    /// 新建连接发送一次查询并解析响应
+    fn check_once(&self, domain: &str) -> impl Future<Output = Result<(i32, String)>> + Send {
+        async move {
+            let mut conn = self.connect().await?;
+            let response = self
+                .query(&mut conn, domain)
+                .await?
+                .context("Whois server closed the connection without a reply")?;
+            Ok(self.parse(&response))
+        }
+    }
+}
+
+/// 解析 `code: message` 格式的响应
+pub fn parse_reply(response: &str) -> (i32, String) {
+    let response = response.trim();
+    match response.split_once(':') {
+        Some((code, msg)) => (code.parse().unwrap_or(-99), msg.trim().to_string()),
+        None => (-99, response.to_string()),
+    }
+}
+
+/// 通过 TCP 访问查询服务的客户端，每次查询读取一行响应
+pub struct TcpWhoisClient {
+    host: String,
+    port: u16,
+}
+
+impl TcpWhoisClient {
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+        }
+    }
+
+    /// 从 `HOST:PORT` 形式的地址创建客户端
+    pub fn from_endpoint(endpoint: &str) -> Result<Self> {
+        let (host, port) = endpoint
+            .rsplit_once(':')
+            .with_context(|| format!("Invalid server address `{}`, expected HOST:PORT", endpoint))?;
+        let port = port
+            .parse()
+            .with_context(|| format!("Invalid port in server address `{}`", endpoint))?;
+        Ok(Self::new(host.trim_start_matches('[').trim_end_matches(']'), port))
+    }
+}
+
+impl WhoisClient for TcpWhoisClient {
+    type Connection = BufReader<TcpStream>;
+
+    fn endpoint(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    async fn connect(&self) -> Result<Self::Connection> {
+        let stream = TcpStream::connect((self.host.as_str(), self.port))
+            .await
+            .context("Failed to connect to whois server")?;
+        stream.set_nodelay(true)?;
+        Ok(BufReader::new(stream))
+    }
+
+    async fn query(&self, conn: &mut Self::Connection, domain: &str) -> Result<Option<String>> {
+        let query = format!("{}\n", domain);
+        if conn.get_mut().write_all(query.as_bytes()).await.is_err() {
+            return Ok(None);
+        }
+
+        let mut line = String::new();
+        match conn.read_line(&mut line).await {
+            Ok(0) | Err(_) => Ok(None),
+            Ok(_) => Ok(Some(line)),
+        }
+    }
+
+    /// 一次写入全部查询行，再按顺序读取同样多的响应行
+    async fn query_batch(&self, conn: &mut Self::Connection, domains: &[String]) -> Result<Vec<String>> {
+        let query: String = domains.iter().map(|domain| format!("{}\n", domain)).collect();
+        let mut replies = Vec::with_capacity(domains.len());
+        if conn.get_mut().write_all(query.as_bytes()).await.is_err() {
+            return Ok(replies);
+        }
+        while replies.len() < domains.len() {
+            let mut line = String::new();
+            match conn.read_line(&mut line).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => replies.push(line),
+            }
+        }
+        Ok(replies)
+    }
+
+    /// 对端未关闭且没有未读的多余数据
+    fn is_alive(&self, conn: &Self::Connection) -> bool {
+        if !conn.buffer().is_empty() {
+            return false;
+        }
+        let mut buf = [0u8; 1];
+        matches!(
+            conn.get_ref().try_read(&mut buf),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock
+        )
+    }
+}
+
+/// 模拟的限流区间：从第 after 次查询起连续 length 次返回 -95，设置 every 时周期性重复，
+/// 设置 retry_after 时响应中附带要求等待的秒数
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitBurst {
+    pub after: usize,
+    #[serde(default = "default_burst_length")]
+    pub length: usize,
+    #[serde(default)]
+    pub every: Option<usize>,
+    #[serde(default)]
+    pub retry_after: Option<u64>,
+}
+
+fn default_burst_length() -> usize {
+    1
+}
+
+impl RateLimitBurst {
+    /// 第 count 次查询(从 1 开始)是否落在限流区间内
+    fn covers(&self, count: usize) -> bool {
+        let Some(mut offset) = count.checked_sub(self.after) else {
+            return false;
+        };
+        if let Some(every) = self.every.filter(|&every| every > 0) {
+            offset %= every;
+        }
+        offset < self.length
+    }
+}
+
+/// 内存中的模拟客户端，按预设的响应回答查询，不访问网络
+pub struct MockWhoisClient {
+    replies: HashMap<String, String>,
+    default_reply: String,
+    available_ratio: f64,
+    latency: Duration,
+    latency_jitter: Duration,
+    bursts: Vec<RateLimitBurst>,
+    queries: AtomicUsize,
+}
+
+impl MockWhoisClient {
+    /// 创建模拟客户端，未预设响应的域名统一返回 default_reply
+    pub fn new(default_reply: impl Into<String>) -> Self {
+        Self {
+            replies: HashMap::new(),
+            default_reply: default_reply.into(),
+            available_ratio: 0.0,
+            latency: Duration::ZERO,
+            latency_jitter: Duration::ZERO,
+            bursts: Vec::new(),
+            queries: AtomicUsize::new(0),
+        }
+    }
+
+    /// 为指定域名预设响应，不带 TLD 的标签匹配该标签的所有 TLD
+    pub fn with_reply(mut self, domain: impl Into<String>, reply: impl Into<String>) -> Self {
+        self.replies.insert(domain.into(), reply.into());
+        self
+    }
+
+    /// 未预设响应的域名按此比例随机返回可用
+    pub fn with_available_ratio(mut self, ratio: f64) -> Self {
+        self.available_ratio = ratio;
+        self
+    }
+
+    /// 每次响应前等待 latency 加上 [0, jitter) 内的随机时长
+    pub fn with_latency(mut self, latency: Duration, jitter: Duration) -> Self {
+        self.latency = latency;
+        self.latency_jitter = jitter;
+        self
+    }
+
+    /// 追加一个模拟的限流区间
+    pub fn with_burst(mut self, burst: RateLimitBurst) -> Self {
+        self.bursts.push(burst);
+        self
+    }
+
+    /// 每第 n 次查询返回限流响应(-95)
+    pub fn rate_limit_every(self, n: usize) -> Self {
+        self.with_burst(RateLimitBurst {
+            after: n,
+            length: 1,
+            every: Some(n),
+            retry_after: None,
+        })
+    }
+
+    /// 已收到的查询次数
+    pub fn queries(&self) -> usize {
+        self.queries.load(Ordering::Relaxed)
+    }
+
+    /// 计算下一次查询的响应
+    fn reply_for(&self, domain: &str) -> String {
+        let count = self.queries.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(burst) = self.bursts.iter().find(|burst| burst.covers(count)) {
+            return match burst.retry_after {
+                Some(seconds) => format!("-95: rate limit exceeded, retry after {} seconds", seconds),
+                None => "-95: rate limit exceeded".to_string(),
+            };
+        }
+        let label = domain.split('.').next().unwrap_or(domain);
+        if let Some(reply) = self.replies.get(domain).or_else(|| self.replies.get(label)) {
+            return reply.clone();
+        }
+        if self.available_ratio > 0.0 && rng::next_f64() < self.available_ratio {
+            return "1: available".to_string();
+        }
+        self.default_reply.clone()
+    }
+}
+
+impl WhoisClient for MockWhoisClient {
+    type Connection = ();
+
+    fn endpoint(&self) -> String {
+        "mock".to_string()
+    }
+
+    async fn connect(&self) -> Result<Self::Connection> {
+        Ok(())
+    }
+
+    async fn query(&self, _conn: &mut Self::Connection, domain: &str) -> Result<Option<String>> {
+        let mut delay = self.latency;
+        if !self.latency_jitter.is_zero() {
+            delay += self.latency_jitter.mul_f64(rng::next_f64());
+        }
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        Ok(Some(self.reply_for(domain)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reply_splits_code_and_message() {
+        assert_eq!(parse_reply("1: available\n"), (1, "available".to_string()));
+        assert_eq!(parse_reply("0: registered"), (0, "registered".to_string()));
+        assert_eq!(
+            parse_reply("-95: rate limit exceeded"),
+            (-95, "rate limit exceeded".to_string())
+        );
+        // 消息中的冒号属于消息
+        assert_eq!(parse_reply("-1: error: bad query"), (-1, "error: bad query".to_string()));
+    }
+
+    #[test]
+    fn parse_reply_rejects_malformed_responses() {
+        assert_eq!(parse_reply("garbage"), (-99, "garbage".to_string()));
+        assert_eq!(parse_reply("x: available"), (-99, "available".to_string()));
+        assert_eq!(parse_reply(""), (-99, String::new()));
+    }
+
+    #[tokio::test]
+    async fn mock_uses_preset_replies() {
+        let client = MockWhoisClient::new("0: registered")
+            .with_reply("ab.li", "1: available")
+            .with_reply("cd", "-1: invalid");
+        assert_eq!(client.check_once("ab.li").await.unwrap(), (1, "available".to_string()));
+        assert_eq!(client.check_once("ab.ch").await.unwrap(), (0, "registered".to_string()));
+        // 不带 TLD 的标签匹配所有 TLD
+        assert_eq!(client.check_once("cd.ch").await.unwrap(), (-1, "invalid".to_string()));
+        assert_eq!(client.queries(), 3);
+    }
+
+    #[tokio::test]
+    async fn mock_rate_limits_every_nth_query() {
+        let client = MockWhoisClient::new("0: registered").rate_limit_every(3);
+        let mut codes = Vec::new();
+        for _ in 0..7 {
+            codes.push(client.check_once("ab.li").await.unwrap().0);
+        }
+        assert_eq!(codes, [0, 0, -95, 0, 0, -95, 0]);
+    }
+
+    #[tokio::test]
+    async fn mock_burst_reports_retry_after() {
+        let client = MockWhoisClient::new("0: registered").with_burst(RateLimitBurst {
+            after: 2,
+            length: 2,
+            every: None,
+            retry_after: Some(30),
+        });
+        let mut replies = Vec::new();
+        for _ in 0..4 {
+            replies.push(client.check_once("ab.li").await.unwrap());
+        }
+        assert_eq!(replies[0].0, 0);
+        assert_eq!(replies[1], (-95, "rate limit exceeded, retry after 30 seconds".to_string()));
+        assert_eq!(replies[2].0, -95);
+        assert_eq!(replies[3].0, 0);
+    }
+}
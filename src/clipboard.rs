@@ -0,0 +1,48 @@
+use anyhow::{Context, Result};
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// 依次尝试的剪贴板命令，使用第一个可以运行的
+const CLIPBOARD_COMMANDS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+];
+
+/// 把文本复制到系统剪贴板，返回使用的命令
+pub fn copy(text: &str) -> Result<&'static str> {
+    for (program, args) in CLIPBOARD_COMMANDS {
+        let Ok(mut child) = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+
+        child
+            .stdin
+            .take()
+            .context("Failed to open clipboard command stdin")?
+            .write_all(text.as_bytes())
+            .with_context(|| format!("Failed to write to {}", program))?;
+        let status = child.wait()?;
+        if status.success() {
+            return Ok(program);
+        }
+    }
+    anyhow::bail!(
+        "No usable clipboard command found (tried {})",
+        CLIPBOARD_COMMANDS
+            .iter()
+            .map(|(program, _)| *program)
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
@@ -0,0 +1,118 @@
+//! 时间戳和显示时区
+//!
+//! 结果、清单和日志中的时间戳按机器的本地时区(或 `--utc` 时的 UTC)记录，带偏移量序列化。
+//! `--timezone` 只影响面向用户的显示：终端摘要、报告和结果文件头部的时间在格式化时转换到该时区，
+//! 不修改进程的 `TZ` 环境变量，因此不影响序列化的时间戳和启动的子进程。时区数据编译在程序中，
+//! 不依赖系统的 zoneinfo 目录。
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, FixedOffset, Local, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// `--timezone` 指定的显示时区
+#[derive(Clone, Copy, Debug)]
+enum DisplayZone {
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+/// 时间戳来源：默认使用本地时区，`--utc` 时统一使用 UTC
+///
+/// 两种模式序列化时都是带偏移量的 RFC 3339 时间，便于跨机器比对。
+#[derive(Clone, Copy, Debug)]
+pub struct Clock {
+    utc: bool,
+    zone: Option<DisplayZone>,
+}
+
+impl Clock {
+    pub fn new(utc: bool) -> Self {
+        Self { utc, zone: None }
+    }
+
+    /// 使用显示时区创建：接受 IANA 时区名(如 `Europe/Zurich`)、`UTC` 或固定偏移(如 `+05:30`)
+    pub fn with_timezone(utc: bool, timezone: Option<&str>) -> Result<Self> {
+        let zone = match timezone {
+            None => None,
+            Some(name) if name.eq_ignore_ascii_case("utc") => Some(DisplayZone::Named(Tz::UTC)),
+            Some(name) => Some(match parse_fixed_offset(name) {
+                Some(offset) => DisplayZone::Fixed(offset),
+                None => DisplayZone::Named(name.parse().map_err(|_| anyhow!("Unknown timezone '{}'", name))?),
+            }),
+        };
+        Ok(Self { utc, zone })
+    }
+
+    /// 当前时间
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        if self.utc {
+            Utc::now().fixed_offset()
+        } else {
+            Local::now().fixed_offset()
+        }
+    }
+
+    /// 结果文件头部使用的时间格式
+    pub fn header_timestamp(&self) -> String {
+        match self.zone {
+            _ if self.utc => Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            Some(zone) => format_in(zone, &Utc::now(), "%Y-%m-%d %H:%M:%S"),
+            None => Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+
+    /// 把时间转换到显示时区后格式化，用于终端摘要和报告
+    pub fn display(&self, time: &DateTime<FixedOffset>) -> String {
+        const FORMAT: &str = "%Y-%m-%d %H:%M:%S %:z";
+        match self.zone {
+            Some(zone) => format_in(zone, time, FORMAT),
+            None => time.with_timezone(&Local).format(FORMAT).to_string(),
+        }
+    }
+}
+
+/// 在显示时区中格式化时间
+fn format_in<T: TimeZone>(zone: DisplayZone, time: &DateTime<T>, format: &str) -> String {
+    match zone {
+        DisplayZone::Named(tz) => time.with_timezone(&tz).format(format).to_string(),
+        DisplayZone::Fixed(offset) => time.with_timezone(&offset).format(format).to_string(),
+    }
+}
+
+/// 解析 `+HH:MM` / `-HH:MM` 格式的固定偏移
+fn parse_fixed_offset(value: &str) -> Option<FixedOffset> {
+    let sign = match value.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let (hours, minutes) = value[1..].split_once(':').unwrap_or((&value[1..], "0"));
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 14 || minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_converts_to_the_requested_zone() {
+        let time = DateTime::parse_from_rfc3339("2024-07-01T10:00:00+00:00").unwrap();
+        let zurich = Clock::with_timezone(false, Some("Europe/Zurich")).unwrap();
+        assert_eq!(zurich.display(&time), "2024-07-01 12:00:00 +02:00");
+        let fixed = Clock::with_timezone(false, Some("-05:30")).unwrap();
+        assert_eq!(fixed.display(&time), "2024-07-01 04:30:00 -05:30");
+        let utc = Clock::with_timezone(false, Some("utc")).unwrap();
+        assert_eq!(utc.display(&time), "2024-07-01 10:00:00 +00:00");
+    }
+
+    #[test]
+    fn unknown_zones_are_rejected() {
+        assert!(Clock::with_timezone(false, Some("Mars/Olympus")).is_err());
+        assert!(Clock::with_timezone(false, Some("+15:00")).is_err());
+    }
+}
@@ -0,0 +1,421 @@
+use anyhow::{Context, Result};
+use clap::{Arg, ArgAction, Command, ValueEnum};
+use std::{fmt::Write as _, fs, path::Path};
+
+/// 支持生成补全脚本的 shell
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+/// completions 子命令参数
+#[derive(clap::Args, Debug)]
+pub struct CompletionsArgs {
+    /// 目标 shell
+    #[arg(value_enum)]
+    shell: Shell,
+
+    /// 写入该文件而不是标准输出
+    #[arg(long)]
+    output: Option<std::path::PathBuf>,
+}
+
+/// 一个命令(或子命令)中用于补全的信息
+struct CommandSpec {
+    name: String,
+    about: String,
+    flags: Vec<FlagSpec>,
+    /// 位置参数的固定取值(如 completions 的 shell 名)
+    values: Vec<String>,
+}
+
+struct FlagSpec {
+    long: Option<String>,
+    short: Option<char>,
+    help: String,
+    takes_value: bool,
+    values: Vec<String>,
+}
+
+impl CommandSpec {
+    fn from_command(cmd: &Command) -> Self {
+        let flags = cmd
+            .get_arguments()
+            .filter(|arg| !arg.is_positional() && !arg.is_hide_set())
+            .map(FlagSpec::from_arg)
+            .collect();
+        let values = cmd
+            .get_positionals()
+            .flat_map(|arg| arg.get_possible_values())
+            .map(|v| v.get_name().to_string())
+            .collect();
+        Self {
+            name: cmd.get_name().to_string(),
+            about: cmd.get_about().map(|s| s.to_string()).unwrap_or_default(),
+            flags,
+            values,
+        }
+    }
+}
+
+impl FlagSpec {
+    fn from_arg(arg: &Arg) -> Self {
+        Self {
+            long: arg.get_long().map(str::to_string),
+            short: arg.get_short(),
+            help: arg.get_help().map(|s| s.to_string()).unwrap_or_default(),
+            takes_value: matches!(arg.get_action(), ArgAction::Set | ArgAction::Append),
+            values: arg
+                .get_possible_values()
+                .iter()
+                .map(|v| v.get_name().to_string())
+                .collect(),
+        }
+    }
+
+    fn words(&self) -> Vec<String> {
+        let mut words = Vec::new();
+        if let Some(long) = &self.long {
+            words.push(format!("--{}", long));
+        }
+        if let Some(short) = self.short {
+            words.push(format!("-{}", short));
+        }
+        words
+    }
+}
+
+/// 顶层命令及其可见的子命令
+fn specs(cmd: &Command) -> (CommandSpec, Vec<CommandSpec>) {
+    let subcommands = cmd
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set())
+        .map(CommandSpec::from_command)
+        .collect();
+    (CommandSpec::from_command(cmd), subcommands)
+}
+
+/// 输出补全脚本
+pub fn run(args: CompletionsArgs, mut cmd: Command) -> Result<()> {
+    cmd.build();
+    let script = match args.shell {
+        Shell::Bash => bash(&cmd),
+        Shell::Zsh => zsh(&cmd),
+        Shell::Fish => fish(&cmd),
+        Shell::Powershell => powershell(&cmd),
+    };
+    emit(&script, args.output.as_deref())
+}
+
+/// 输出 man 页面，path 为 "-" 时写到标准输出
+pub fn generate_man(mut cmd: Command, path: &Path) -> Result<()> {
+    cmd.build();
+    let output = (path != Path::new("-")).then_some(path);
+    emit(&man(&cmd), output)
+}
+
+fn emit(content: &str, output: Option<&Path>) -> Result<()> {
+    match output {
+        Some(path) => fs::write(path, content)
+            .with_context(|| format!("Failed to write {}", path.display())),
+        None => {
+            print!("{}", content);
+            Ok(())
+        }
+    }
+}
+
+fn bash(cmd: &Command) -> String {
+    let (top, subs) = specs(cmd);
+    let func = format!("_{}", top.name.replace('-', "_"));
+    let mut out = String::new();
+
+    let _ = writeln!(out, "{}() {{", func);
+    let _ = writeln!(out, "    local cur prev cmd i");
+    let _ = writeln!(out, "    COMPREPLY=()");
+    let _ = writeln!(out, "    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"");
+    let _ = writeln!(out, "    prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"");
+    let _ = writeln!(out, "    cmd=\"\"");
+    let _ = writeln!(out, "    for ((i = 1; i < COMP_CWORD; i++)); do");
+    let _ = writeln!(out, "        case \"${{COMP_WORDS[i]}}\" in");
+    for sub in &subs {
+        let _ = writeln!(out, "            {}) cmd=\"{}\"; break ;;", sub.name, sub.name);
+    }
+    let _ = writeln!(out, "        esac");
+    let _ = writeln!(out, "    done");
+    let _ = writeln!(out);
+
+    // 带固定取值的选项
+    let _ = writeln!(out, "    case \"$prev\" in");
+    for spec in std::iter::once(&top).chain(&subs) {
+        for flag in spec.flags.iter().filter(|f| !f.values.is_empty()) {
+            let _ = writeln!(
+                out,
+                "        {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return 0 ;;",
+                flag.words().join("|"),
+                flag.values.join(" ")
+            );
+        }
+    }
+    let _ = writeln!(out, "    esac");
+    let _ = writeln!(out);
+
+    let _ = writeln!(out, "    case \"$cmd\" in");
+    for sub in &subs {
+        let mut words: Vec<_> = sub.flags.iter().flat_map(FlagSpec::words).collect();
+        words.extend(sub.values.iter().cloned());
+        let _ = writeln!(
+            out,
+            "        {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")) ;;",
+            sub.name,
+            words.join(" ")
+        );
+    }
+    let mut words: Vec<_> = top.flags.iter().flat_map(FlagSpec::words).collect();
+    words.extend(subs.iter().map(|sub| sub.name.clone()));
+    let _ = writeln!(
+        out,
+        "        *) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")) ;;",
+        words.join(" ")
+    );
+    let _ = writeln!(out, "    esac");
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(out, "complete -F {} -o default {}", func, top.name);
+    out
+}
+
+fn zsh_escape(text: &str) -> String {
+    text.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh_flag_specs(flags: &[FlagSpec]) -> Vec<String> {
+    flags
+        .iter()
+        .flat_map(|flag| {
+            let value = if !flag.values.is_empty() {
+                format!(":value:({})", flag.values.join(" "))
+            } else if flag.takes_value {
+                ":value:_default".to_string()
+            } else {
+                String::new()
+            };
+            flag.words().into_iter().map(move |word| {
+                format!("'{}[{}]{}'", word, zsh_escape(&flag.help), value)
+            })
+        })
+        .collect()
+}
+
+fn zsh(cmd: &Command) -> String {
+    let (top, subs) = specs(cmd);
+    let func = format!("_{}", top.name.replace('-', "_"));
+    let mut out = String::new();
+
+    let _ = writeln!(out, "#compdef {}", top.name);
+    let _ = writeln!(out);
+    let _ = writeln!(out, "{}() {{", func);
+    let _ = writeln!(out, "    local line state");
+    let _ = writeln!(out, "    _arguments -C \\");
+    for spec in zsh_flag_specs(&top.flags) {
+        let _ = writeln!(out, "        {} \\", spec);
+    }
+    let _ = writeln!(out, "        '1: :->command' \\");
+    let _ = writeln!(out, "        '*:: :->args'");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    case $state in");
+    let _ = writeln!(out, "        command)");
+    let _ = writeln!(out, "            local -a commands");
+    let _ = writeln!(out, "            commands=(");
+    for sub in &subs {
+        let _ = writeln!(out, "                '{}:{}'", sub.name, zsh_escape(&sub.about));
+    }
+    let _ = writeln!(out, "            )");
+    let _ = writeln!(out, "            _describe 'command' commands");
+    let _ = writeln!(out, "            ;;");
+    let _ = writeln!(out, "        args)");
+    let _ = writeln!(out, "            case $line[1] in");
+    for sub in &subs {
+        let mut specs = zsh_flag_specs(&sub.flags);
+        if !sub.values.is_empty() {
+            specs.push(format!("'1:value:({})'", sub.values.join(" ")));
+        }
+        let _ = writeln!(out, "                {})", sub.name);
+        let _ = writeln!(out, "                    _arguments {}", specs.join(" "));
+        let _ = writeln!(out, "                    ;;");
+    }
+    let _ = writeln!(out, "            esac");
+    let _ = writeln!(out, "            ;;");
+    let _ = writeln!(out, "    esac");
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "{} \"$@\"", func);
+    out
+}
+
+fn fish_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn fish(cmd: &Command) -> String {
+    let (top, subs) = specs(cmd);
+    let names: Vec<_> = subs.iter().map(|sub| sub.name.as_str()).collect();
+    let mut out = String::new();
+
+    let write_flags = |out: &mut String, condition: &str, flags: &[FlagSpec]| {
+        for flag in flags {
+            let mut line = format!("complete -c {} -n '{}'", top.name, condition);
+            if let Some(long) = &flag.long {
+                let _ = write!(line, " -l {}", long);
+            }
+            if let Some(short) = flag.short {
+                let _ = write!(line, " -s {}", short);
+            }
+            if !flag.values.is_empty() {
+                let _ = write!(line, " -x -a '{}'", flag.values.join(" "));
+            } else if flag.takes_value {
+                line.push_str(" -r");
+            }
+            let _ = writeln!(out, "{} -d '{}'", line, fish_escape(&flag.help));
+        }
+    };
+
+    let top_condition = format!("not __fish_seen_subcommand_from {}", names.join(" "));
+    write_flags(&mut out, &top_condition, &top.flags);
+    for sub in &subs {
+        let _ = writeln!(
+            out,
+            "complete -c {} -n '{}' -f -a {} -d '{}'",
+            top.name,
+            top_condition,
+            sub.name,
+            fish_escape(&sub.about)
+        );
+    }
+    for sub in &subs {
+        let condition = format!("__fish_seen_subcommand_from {}", sub.name);
+        write_flags(&mut out, &condition, &sub.flags);
+        if !sub.values.is_empty() {
+            let _ = writeln!(
+                out,
+                "complete -c {} -n '{}' -f -a '{}'",
+                top.name,
+                condition,
+                sub.values.join(" ")
+            );
+        }
+    }
+    out
+}
+
+fn powershell(cmd: &Command) -> String {
+    let (top, subs) = specs(cmd);
+    let mut out = String::new();
+
+    let quote = |text: &str| format!("'{}'", text.replace('\'', "''"));
+    let list = |spec: &CommandSpec, with_subcommands: bool| {
+        let mut items: Vec<_> = spec
+            .flags
+            .iter()
+            .flat_map(|flag| {
+                flag.words()
+                    .into_iter()
+                    .map(|word| format!("@({}, {})", quote(&word), quote(&flag.help)))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        items.extend(
+            spec.values
+                .iter()
+                .map(|value| format!("@({}, {})", quote(value), quote(value))),
+        );
+        if with_subcommands {
+            items.extend(
+                subs.iter()
+                    .map(|sub| format!("@({}, {})", quote(&sub.name), quote(&sub.about))),
+            );
+        }
+        items.join(", ")
+    };
+
+    let _ = writeln!(
+        out,
+        "Register-ArgumentCompleter -Native -CommandName {} -ScriptBlock {{",
+        quote(&top.name)
+    );
+    let _ = writeln!(out, "    param($wordToComplete, $commandAst, $cursorPosition)");
+    let _ = writeln!(out, "    $words = $commandAst.CommandElements | ForEach-Object {{ $_.ToString() }}");
+    let _ = writeln!(out, "    $candidates = @({})", list(&top, true));
+    for sub in &subs {
+        let _ = writeln!(out, "    if ($words -contains {}) {{", quote(&sub.name));
+        let _ = writeln!(out, "        $candidates = @({})", list(sub, false));
+        let _ = writeln!(out, "    }}");
+    }
+    let _ = writeln!(out, "    $candidates | Where-Object {{ $_[0] -like \"$wordToComplete*\" }} | ForEach-Object {{");
+    let _ = writeln!(
+        out,
+        "        [System.Management.Automation.CompletionResult]::new($_[0], $_[0], 'ParameterValue', $_[1])"
+    );
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out, "}}");
+    out
+}
+
+fn roff_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('-', "\\-")
+}
+
+fn man_options(out: &mut String, flags: &[FlagSpec]) {
+    for flag in flags {
+        let mut words: Vec<_> = flag
+            .words()
+            .iter()
+            .rev()
+            .map(|w| format!("\\fB{}\\fR", roff_escape(w)))
+            .collect();
+        if flag.takes_value {
+            if let Some(last) = words.last_mut() {
+                last.push_str(" \\fIVALUE\\fR");
+            }
+        }
+        let _ = writeln!(out, ".TP");
+        let _ = writeln!(out, "{}", words.join(", "));
+        let _ = writeln!(out, "{}", roff_escape(&flag.help));
+        if !flag.values.is_empty() {
+            let _ = writeln!(out, ".br");
+            let _ = writeln!(out, "[{}]", roff_escape(&flag.values.join(", ")));
+        }
+    }
+}
+
+fn man(cmd: &Command) -> String {
+    let (top, subs) = specs(cmd);
+    let version = cmd.get_version().unwrap_or_default();
+    let mut out = String::new();
+
+    let _ = writeln!(out, ".TH {} 1 \"\" \"{} {}\"", top.name.to_uppercase(), top.name, version);
+    let _ = writeln!(out, ".SH NAME");
+    let _ = writeln!(out, "{} \\- {}", roff_escape(&top.name), roff_escape(&top.about));
+    let _ = writeln!(out, ".SH SYNOPSIS");
+    let _ = writeln!(out, "\\fB{}\\fR [OPTIONS] [COMMAND]", roff_escape(&top.name));
+    let _ = writeln!(out, ".SH OPTIONS");
+    man_options(&mut out, &top.flags);
+
+    if !subs.is_empty() {
+        let _ = writeln!(out, ".SH COMMANDS");
+        for sub in &subs {
+            let _ = writeln!(out, ".SS {}", roff_escape(&sub.name));
+            let _ = writeln!(out, "{}", roff_escape(&sub.about));
+            man_options(&mut out, &sub.flags);
+        }
+    }
+
+    let _ = writeln!(out, ".SH VERSION");
+    let _ = writeln!(out, "v{}", version);
+    out
+}
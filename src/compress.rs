@@ -0,0 +1,142 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::{
+    fs,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+};
+
+use crate::atomic;
+
+/// 结果文件的压缩格式，通过系统中的 gzip / zstd 命令完成压缩和解压
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// 压缩文件的扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Zstd => "zst",
+        }
+    }
+
+    fn program(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// 根据扩展名判断文件的压缩格式
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "gz" => Some(Self::Gzip),
+            "zst" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// 确认系统中可以调用对应的压缩命令，避免扫描结束后才发现无法保存
+pub fn ensure_available(compression: Compression) -> Result<()> {
+    let available = Command::new(compression.program())
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    anyhow::ensure!(
+        available,
+        "--compress {} requires the `{}` command to be installed",
+        compression.program(),
+        compression.program()
+    );
+    Ok(())
+}
+
+/// 边压缩边写入文件的写入端，调用 [`CompressedWriter::finish`] 后才替换目标文件
+pub struct CompressedWriter {
+    child: Child,
+    stdin: BufWriter<ChildStdin>,
+    compression: Compression,
+    tmp: PathBuf,
+    path: PathBuf,
+}
+
+/// 开始压缩写入文件，完成前目标文件保持原有内容
+pub fn create(path: &Path, compression: Compression) -> Result<CompressedWriter> {
+    let tmp = atomic::temp_path(path);
+    let file = fs::File::create(&tmp)
+        .with_context(|| format!("Failed to create {}", tmp.display()))?;
+    let mut child = Command::new(compression.program())
+        .args(["-c", "-q"])
+        .stdin(Stdio::piped())
+        .stdout(file)
+        .spawn()
+        .with_context(|| format!("Failed to run {}, is it installed?", compression.program()))?;
+    let stdin = child.stdin.take().context("Failed to open compressor stdin")?;
+    Ok(CompressedWriter {
+        child,
+        stdin: BufWriter::new(stdin),
+        compression,
+        tmp,
+        path: path.to_path_buf(),
+    })
+}
+
+impl Write for CompressedWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.stdin.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stdin.flush()
+    }
+}
+
+impl CompressedWriter {
+    /// 等待压缩完成并替换目标文件
+    pub fn finish(self) -> Result<()> {
+        let Self {
+            mut child,
+            stdin,
+            compression,
+            tmp,
+            path,
+        } = self;
+        stdin
+            .into_inner()
+            .map_err(|e| e.into_error())
+            .context("Failed to write to compressor")?;
+        let status = child.wait()?;
+        anyhow::ensure!(status.success(), "{} exited with {}", compression.program(), status);
+        atomic::commit(&tmp, &path)
+    }
+}
+
+/// 读取文件内容，按扩展名透明地解压 .gz / .zst 文件
+pub fn read_to_string(path: &Path) -> Result<String> {
+    let Some(compression) = Compression::from_path(path) else {
+        return fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()));
+    };
+
+    let output = Command::new(compression.program())
+        .args(["-d", "-c", "-q"])
+        .arg(path)
+        .output()
+        .with_context(|| format!("Failed to run {}, is it installed?", compression.program()))?;
+    anyhow::ensure!(
+        output.status.success(),
+        "Failed to decompress {}: {}",
+        path.display(),
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    String::from_utf8(output.stdout)
+        .with_context(|| format!("{} is not valid UTF-8", path.display()))
+}
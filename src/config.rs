@@ -0,0 +1,70 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::{collections::BTreeMap, path::Path};
+
+use crate::toml;
+
+/// 配置文件(TOML)
+///
+/// ```toml
+/// [tlds]
+/// li = "whois.nic.ch:4343"
+///
+/// [servers."whois.nic.ch:4343"]
+/// qps = 5.0
+/// concurrency = 10
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// 各 TLD 使用的查询服务地址，未列出的 TLD 使用 --server 或默认服务
+    pub tlds: BTreeMap<String, String>,
+    /// 按查询服务地址配置的速率预算
+    pub servers: BTreeMap<String, ServerBudget>,
+    /// `--protocol epp` 使用的 EPP 服务和凭据
+    pub epp: Option<crate::epp::EppConfig>,
+    /// 可用域名的价格表
+    pub pricing: Option<crate::pricing::PricingConfig>,
+    /// 按 TLD 覆盖内置的标签规则
+    pub label_rules: BTreeMap<String, crate::rules::RulesOverride>,
+    /// 自动注册优先列表中的可用域名
+    #[cfg(feature = "registrar")]
+    pub registrar: Option<crate::registrar::RegistrarConfig>,
+}
+
+/// 单个查询服务的速率预算，未设置的项沿用命令行参数
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerBudget {
+    /// 查询速率上限(次/秒)
+    pub qps: Option<f64>,
+    /// 查询间隔(秒)
+    pub delay: Option<f64>,
+    /// 同时进行的查询数上限
+    pub concurrency: Option<usize>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut config: Self = toml::from_file(path)?;
+        config.pricing = config.pricing.map(|pricing| pricing.validate()).transpose()?;
+        for (endpoint, budget) in &config.servers {
+            anyhow::ensure!(
+                budget.qps.is_none_or(|qps| qps > 0.0),
+                "servers.\"{}\".qps must be positive",
+                endpoint
+            );
+            anyhow::ensure!(
+                budget.delay.is_none_or(|delay| delay >= 0.0),
+                "servers.\"{}\".delay must not be negative",
+                endpoint
+            );
+            anyhow::ensure!(
+                budget.concurrency != Some(0),
+                "servers.\"{}\".concurrency must be at least 1",
+                endpoint
+            );
+        }
+        Ok(config)
+    }
+}
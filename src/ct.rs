@@ -0,0 +1,141 @@
+//! 证书透明度(CT)日志交叉检查
+//!
+//! 查询服务报告可用的域名如果在 CT 日志中有历史证书，通常说明它刚过期不久或正处于
+//! 赎回期，实际能否注册、何时能注册都不确定。通过 crt.sh 的 JSON 接口查询每个可用
+//! 域名的证书记录并附在结果中，便于据此调整优先级。crt.sh 负载较高，查询按固定间隔
+//! 进行，收到 429 / 5xx 响应时加大间隔并重试。
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, time::Duration};
+
+use crate::{
+    http::{self, CurlConfig},
+    limiter::RateLimiter,
+};
+
+/// 默认的 CT 日志查询服务
+pub const DEFAULT_CT_URL: &str = "https://crt.sh";
+
+/// 两次查询之间的基础间隔
+const LOOKUP_INTERVAL: Duration = Duration::from_secs(1);
+/// crt.sh 的响应可能很慢
+const LOOKUP_TIMEOUT_SECS: u32 = 60;
+const MAX_ATTEMPTS: u32 = 3;
+
+/// 结果中附带的证书历史
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CtHistory {
+    /// 日志中包含该域名的证书数
+    pub certificates: usize,
+    /// 最早的证书生效时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_issued: Option<String>,
+    /// 最晚的证书到期时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_expiry: Option<String>,
+}
+
+/// crt.sh 返回的一条证书记录，只取用到的字段
+#[derive(Deserialize)]
+struct Entry {
+    id: u64,
+    #[serde(default)]
+    not_before: Option<String>,
+    #[serde(default)]
+    not_after: Option<String>,
+}
+
+/// CT 日志查询
+pub struct CtChecker {
+    base_url: String,
+    limiter: RateLimiter,
+}
+
+impl CtChecker {
+    pub fn new(base_url: &str) -> Result<Self> {
+        anyhow::ensure!(
+            base_url.starts_with("https://") || base_url.starts_with("http://"),
+            "--ct-url must be an http:// or https:// URL"
+        );
+        anyhow::ensure!(http::curl_available(), "--ct-check requires the `curl` command to be installed");
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            limiter: RateLimiter::new(LOOKUP_INTERVAL, 0.0),
+        })
+    }
+
+    /// 查询域名的证书历史
+    pub async fn lookup(&self, domain: &str) -> Result<CtHistory> {
+        let url = format!("{}/?q={}&output=json", self.base_url, domain);
+        let mut attempt = 1;
+        let body = loop {
+            self.limiter.wait().await;
+            let mut request = CurlConfig::new(&url, LOOKUP_TIMEOUT_SECS);
+            request.option("header", "Accept: application/json");
+            let (status, body) = request.send().await?;
+            match status {
+                200 => {
+                    self.limiter.on_success();
+                    break body;
+                }
+                429 | 500..=599 if attempt < MAX_ATTEMPTS => {
+                    self.limiter.on_rate_limited();
+                    attempt += 1;
+                }
+                _ => anyhow::bail!("CT log lookup for {} failed with HTTP {}", domain, status),
+            }
+        };
+        summarize(&body).with_context(|| format!("Invalid CT log response for {}", domain))
+    }
+}
+
+/// 汇总证书记录，同一证书只计一次
+fn summarize(body: &str) -> Result<CtHistory> {
+    let body = body.trim();
+    // 没有记录时 crt.sh 可能返回空响应而不是 []
+    let entries: Vec<Entry> = if body.is_empty() {
+        Vec::new()
+    } else {
+        serde_json::from_str(body)?
+    };
+
+    let mut seen = HashSet::new();
+    let mut history = CtHistory {
+        certificates: 0,
+        first_issued: None,
+        last_expiry: None,
+    };
+    for entry in entries {
+        if !seen.insert(entry.id) {
+            continue;
+        }
+        history.certificates += 1;
+        // crt.sh 的时间为不带时区的 ISO 8601(UTC)，可以直接按字符串比较
+        if let Some(not_before) = entry.not_before {
+            if history.first_issued.as_ref().is_none_or(|first| not_before < *first) {
+                history.first_issued = Some(not_before);
+            }
+        }
+        if let Some(not_after) = entry.not_after {
+            if history.last_expiry.as_ref().is_none_or(|last| not_after > *last) {
+                history.last_expiry = Some(not_after);
+            }
+        }
+    }
+    Ok(history)
+}
+
+impl CtHistory {
+    /// 终端中显示的说明，没有历史证书时为 `None`
+    pub fn display(&self) -> Option<String> {
+        if self.certificates == 0 {
+            return None;
+        }
+        let mut text = format!("{} 张历史证书", self.certificates);
+        if let Some(last_expiry) = &self.last_expiry {
+            text.push_str(&format!("，最晚到期 {}", last_expiry.get(..10).unwrap_or(last_expiry)));
+        }
+        Some(text)
+    }
+}
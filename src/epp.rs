@@ -0,0 +1,383 @@
+//! EPP 查询服务
+//!
+//! 持有注册商资质的用户可以通过 EPP(RFC 5730/5731)直接向注册局查询和注册域名，
+//! 查询结果具有权威性，限流也远比公共查询服务宽松。TLS 连接由 rustls 建立(证书配置与
+//! `--tls` 相同，另可提供客户端证书)，报文格式为 4 字节大端长度头加 XML。每个连接在建立时
+//! 登录一次，之后在同一会话上连续查询。
+
+use anyhow::{Context, Result};
+use rustls::pki_types::ServerName;
+use serde::Deserialize;
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+use tokio_rustls::{client::TlsStream, TlsConnector};
+
+use crate::{client::WhoisClient, tls};
+
+/// 默认的注册局 EPP 服务
+const EPP_HOST: &str = "epp.nic.ch";
+const EPP_PORT: u16 = 700;
+/// 未在配置文件中设置 password 时读取的环境变量
+pub const PASSWORD_ENV: &str = "EPP_PASSWORD";
+/// 建立连接、完成握手并收到问候报文的超时时间
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// 单个报文的长度上限，防止错误的长度头导致分配过多内存
+const MAX_FRAME_LEN: usize = 1 << 20;
+
+const EPP_NS: &str = "urn:ietf:params:xml:ns:epp-1.0";
+const DOMAIN_NS: &str = "urn:ietf:params:xml:ns:domain-1.0";
+const CONTACT_NS: &str = "urn:ietf:params:xml:ns:contact-1.0";
+
+/// 命令执行成功
+const RESULT_OK: u32 = 1000;
+/// 会话超出服务器的限制，按限流处理
+const RESULT_SESSION_LIMIT: u32 = 2502;
+
+/// 配置文件中的 [epp] 部分
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EppConfig {
+    /// EPP 服务地址(HOST:PORT)
+    #[serde(default = "default_server")]
+    pub server: String,
+    /// 注册商在注册局的客户端标识
+    client_id: String,
+    /// 登录密码，未设置时读取环境变量 EPP_PASSWORD
+    #[serde(default)]
+    password: Option<String>,
+    /// 客户端证书和私钥(PEM)，注册局要求双向认证时设置
+    #[serde(default)]
+    cert: Option<PathBuf>,
+    #[serde(default)]
+    key: Option<PathBuf>,
+    /// 校验服务器证书使用的 CA 文件，默认使用系统证书
+    #[serde(default)]
+    ca_file: Option<PathBuf>,
+}
+
+fn default_server() -> String {
+    format!("{}:{}", EPP_HOST, EPP_PORT)
+}
+
+/// 已登录的 EPP 会话
+pub struct EppSession {
+    stream: BufReader<TlsStream<TcpStream>>,
+}
+
+impl EppSession {
+    async fn send(&mut self, xml: &str) -> std::io::Result<()> {
+        let len = u32::try_from(xml.len() + 4).map_err(std::io::Error::other)?;
+        let mut frame = Vec::with_capacity(xml.len() + 4);
+        frame.extend_from_slice(&len.to_be_bytes());
+        frame.extend_from_slice(xml.as_bytes());
+        self.stream.write_all(&frame).await?;
+        self.stream.flush().await
+    }
+
+    async fn receive(&mut self) -> std::io::Result<String> {
+        let mut header = [0u8; 4];
+        self.stream.read_exact(&mut header).await?;
+        let len = (u32::from_be_bytes(header) as usize).saturating_sub(4);
+        if len > MAX_FRAME_LEN {
+            return Err(std::io::Error::other(format!("EPP frame of {} bytes is too large", len)));
+        }
+        let mut body = vec![0u8; len];
+        self.stream.read_exact(&mut body).await?;
+        String::from_utf8(body).map_err(std::io::Error::other)
+    }
+
+    /// 发送命令并读取响应，连接已关闭时返回 `None`
+    async fn command(&mut self, xml: &str) -> Option<String> {
+        self.send(xml).await.ok()?;
+        self.receive().await.ok()
+    }
+}
+
+/// 通过 EPP 查询域名的客户端
+pub struct EppClient {
+    host: String,
+    port: u16,
+    client_id: String,
+    password: String,
+    server_name: ServerName<'static>,
+    connector: TlsConnector,
+    transactions: AtomicU64,
+}
+
+impl EppClient {
+    /// 使用 [epp] 中的凭据创建访问指定地址的客户端
+    pub fn new(endpoint: &str, config: &EppConfig) -> Result<Self> {
+        let (host, port) = endpoint
+            .rsplit_once(':')
+            .with_context(|| format!("Invalid EPP server address `{}`, expected HOST:PORT", endpoint))?;
+        let port = port
+            .parse()
+            .with_context(|| format!("Invalid port in EPP server address `{}`", endpoint))?;
+        let password = config
+            .password
+            .clone()
+            .or_else(|| std::env::var(PASSWORD_ENV).ok())
+            .unwrap_or_default();
+        anyhow::ensure!(
+            !password.is_empty(),
+            "epp.password is not set and {} is empty",
+            PASSWORD_ENV
+        );
+        anyhow::ensure!(
+            config.cert.is_some() == config.key.is_some(),
+            "epp.cert and epp.key must be set together"
+        );
+        let client_auth = config.cert.as_deref().zip(config.key.as_deref());
+        let connector = tls::connector(config.ca_file.as_deref(), client_auth).map_err(|e| match &config.ca_file {
+            None => e.context("Set epp.ca_file to specify the CA certificates"),
+            Some(_) => e,
+        })?;
+        let host = host.trim_start_matches('[').trim_end_matches(']').to_string();
+
+        Ok(Self {
+            server_name: tls::server_name(&host)?,
+            host,
+            port,
+            client_id: config.client_id.clone(),
+            password,
+            connector,
+            transactions: AtomicU64::new(0),
+        })
+    }
+
+    /// 客户端事务标识，便于在注册局日志中定位请求
+    fn transaction_id(&self) -> String {
+        format!(
+            "ldc-{}-{}",
+            std::process::id(),
+            self.transactions.fetch_add(1, Ordering::Relaxed)
+        )
+    }
+
+    /// 包装为完整的 EPP 命令报文
+    fn command_xml(&self, body: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?><epp xmlns="{}"><command>{}<clTRID>{}</clTRID></command></epp>"#,
+            EPP_NS,
+            body,
+            self.transaction_id()
+        )
+    }
+
+    /// 建立 TLS 连接并读取问候报文
+    async fn open(&self) -> Result<EppSession> {
+        let endpoint = self.endpoint();
+        let handshake = async {
+            let tcp = TcpStream::connect((self.host.as_str(), self.port)).await?;
+            tcp.set_nodelay(true)?;
+            let mut session = EppSession {
+                stream: BufReader::new(self.connector.connect(self.server_name.clone(), tcp).await?),
+            };
+            // 连接成功后服务器先发送问候报文
+            session.receive().await?;
+            std::io::Result::Ok(session)
+        };
+        match tokio::time::timeout(CONNECT_TIMEOUT, handshake).await {
+            Ok(Ok(session)) => Ok(session),
+            Ok(Err(e)) => anyhow::bail!("Failed to connect to EPP server {}: {}", endpoint, e),
+            Err(_) => anyhow::bail!(
+                "EPP server {} did not send a greeting within {}s",
+                endpoint,
+                CONNECT_TIMEOUT.as_secs()
+            ),
+        }
+    }
+
+    /// 在新会话上创建(注册)域名，返回注册局的响应消息
+    #[cfg(feature = "registrar")]
+    pub async fn create(
+        &self,
+        domain: &str,
+        years: u32,
+        registrant: &str,
+        admin: Option<&str>,
+        tech: Option<&str>,
+    ) -> Result<String> {
+        let mut session = self.connect().await?;
+        let mut contacts = String::new();
+        for (kind, id) in [("admin", admin), ("tech", tech)] {
+            if let Some(id) = id {
+                contacts.push_str(&format!(
+                    r#"<domain:contact type="{}">{}</domain:contact>"#,
+                    kind,
+                    escape(id)
+                ));
+            }
+        }
+        // 注册局要求提供转移授权码，使用随机值，需要时可在注册商处重置
+        let auth_info = format!("Ldc-{:016x}!", crate::rng::next_u64());
+        let body = format!(
+            r#"<create><domain:create xmlns:domain="{}"><domain:name>{}</domain:name><domain:period unit="y">{}</domain:period><domain:registrant>{}</domain:registrant>{}<domain:authInfo><domain:pw>{}</domain:pw></domain:authInfo></domain:create></create>"#,
+            DOMAIN_NS,
+            escape(domain),
+            years,
+            escape(registrant),
+            contacts,
+            auth_info
+        );
+        let response = session
+            .command(&self.command_xml(&body))
+            .await
+            .context("EPP server closed the connection during create")?;
+        let (code, message) = result(&response).context("Unexpected EPP response to create")?;
+        anyhow::ensure!(code == RESULT_OK || code == 1001, "EPP {}: {}", code, message);
+        Ok(message)
+    }
+}
+
+impl WhoisClient for EppClient {
+    type Connection = EppSession;
+
+    fn endpoint(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    /// 建立 TLS 连接并登录
+    async fn connect(&self) -> Result<Self::Connection> {
+        let mut session = self.open().await?;
+        let login = format!(
+            r#"<login><clID>{}</clID><pw>{}</pw><options><version>1.0</version><lang>en</lang></options><svcs><objURI>{}</objURI><objURI>{}</objURI></svcs></login>"#,
+            escape(&self.client_id),
+            escape(&self.password),
+            DOMAIN_NS,
+            CONTACT_NS
+        );
+        let response = session
+            .command(&self.command_xml(&login))
+            .await
+            .context("EPP server closed the connection during login")?;
+        let (code, message) = result(&response).context("Unexpected EPP response to login")?;
+        anyhow::ensure!(code == RESULT_OK, "EPP login failed ({}): {}", code, message);
+        Ok(session)
+    }
+
+    async fn query(&self, conn: &mut Self::Connection, domain: &str) -> Result<Option<String>> {
+        let body = format!(
+            r#"<check><domain:check xmlns:domain="{}"><domain:name>{}</domain:name></domain:check></check>"#,
+            DOMAIN_NS,
+            escape(domain)
+        );
+        Ok(conn.command(&self.command_xml(&body)).await)
+    }
+
+    /// 把 check 响应转换为与查询服务相同的响应码
+    fn parse(&self, response: &str) -> (i32, String) {
+        parse_check(response)
+    }
+}
+
+/// 把 check 响应转换为与查询服务相同的响应码：1 可用、0 已注册、-95 限流，
+/// 其他 EPP 错误保留原始结果码
+pub fn parse_check(response: &str) -> (i32, String) {
+    let Some((code, message)) = result(response) else {
+        return (-99, "Malformed EPP response".to_string());
+    };
+    match code {
+        RESULT_OK => {
+            let Some((attrs, _)) = element(response, "name") else {
+                return (-99, "EPP check response lists no domain".to_string());
+            };
+            let reason = element(response, "reason").map(|(_, reason)| unescape(reason));
+            if matches!(attribute(attrs, "avail"), Some("1" | "true")) {
+                (1, reason.unwrap_or_else(|| "available".to_string()))
+            } else {
+                (0, reason.unwrap_or_else(|| "not available".to_string()))
+            }
+        }
+        RESULT_SESSION_LIMIT => (-95, message),
+        _ => (code as i32, format!("EPP {}: {}", code, message)),
+    }
+}
+
+/// 响应中的结果码和消息
+fn result(xml: &str) -> Option<(u32, String)> {
+    let (attrs, inner) = element(xml, "result")?;
+    let code = attribute(attrs, "code")?.parse().ok()?;
+    let message = element(inner, "msg")
+        .map(|(_, msg)| unescape(msg))
+        .unwrap_or_default();
+    Some((code, message))
+}
+
+/// 查找第一个本地名为 name 的元素(忽略命名空间前缀)，返回其属性部分和内容
+fn element<'a>(xml: &'a str, name: &str) -> Option<(&'a str, &'a str)> {
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        let tag = &rest[start + 1..];
+        let end = tag.find('>')?;
+        let head = &tag[..end];
+        rest = &tag[end + 1..];
+        if head.starts_with(['/', '?', '!']) {
+            continue;
+        }
+
+        let qname_len = head
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(head.len());
+        let qname = &head[..qname_len];
+        let local = qname.rsplit(':').next().unwrap_or(qname);
+        if local != name {
+            continue;
+        }
+
+        let attrs = &head[qname_len..];
+        if let Some(attrs) = attrs.strip_suffix('/') {
+            return Some((attrs, ""));
+        }
+        let close = rest.find(&format!("</{}>", qname))?;
+        return Some((attrs, &rest[..close]));
+    }
+    None
+}
+
+/// 读取属性值
+fn attribute<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attrs;
+    while let Some(pos) = rest.find(name) {
+        let preceded = rest[..pos].ends_with(char::is_whitespace);
+        let after = rest[pos + name.len()..].trim_start();
+        rest = &rest[pos + name.len()..];
+        let Some(value) = after.strip_prefix('=').map(str::trim_start) else {
+            continue;
+        };
+        if !preceded {
+            continue;
+        }
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            continue;
+        }
+        let value = &value[1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+    None
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn unescape(text: &str) -> String {
+    text.trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
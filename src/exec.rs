@@ -0,0 +1,152 @@
+//! `--exec`：对每个可用结果运行外部命令
+//!
+//! 命令模板中的 `{domain}`、`{status}`、`{score}` 替换为结果的 A-label 域名、状态名和评分后
+//! 经 `sh -c`(Windows 上为 `cmd /C`)执行，其他花括号原样保留。替换的值只含小写字母、数字、
+//! 点和连字符，不需要另行转义。命令在后台运行，同时运行的数量受 `--exec-concurrency` 限制，
+//! 不会拖慢扫描；超过 `--exec-timeout` 的命令被终止。命令的标准输出被丢弃(流式模式中标准
+//! 输出为结果)，失败(非零退出、超时或无法启动)时把退出状态和标准错误的最后一行追加到输出
+//! 目录的 exec_failures.log，运行结束时汇总成功和失败的次数。
+
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{process::Command, sync::Semaphore, task::JoinHandle, time::timeout};
+
+use crate::{clock::Clock, filter::status_name, score, DomainResult};
+
+/// 失败记录的文件名
+pub const FAILURES_FILE: &str = "exec_failures.log";
+/// 运行命令的 shell 及其参数
+#[cfg(not(windows))]
+const SHELL: (&str, &str) = ("sh", "-c");
+#[cfg(windows)]
+const SHELL: (&str, &str) = ("cmd", "/C");
+/// 日志中保留的标准错误的最大长度(字符)
+const STDERR_LIMIT: usize = 200;
+
+/// 命令运行的统计
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ExecReport {
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+/// --exec 的命令模板和正在运行的命令
+pub struct ExecHook {
+    template: String,
+    permits: Arc<Semaphore>,
+    timeout: Duration,
+    log_path: PathBuf,
+    /// 失败记录的时间戳来源，与结果相同
+    clock: Clock,
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+    report: Arc<Mutex<ExecReport>>,
+}
+
+impl ExecHook {
+    pub fn new(template: &str, concurrency: usize, timeout: Duration, output_dir: PathBuf, clock: Clock) -> Self {
+        Self {
+            template: template.to_string(),
+            permits: Arc::new(Semaphore::new(concurrency)),
+            timeout,
+            log_path: output_dir.join(FAILURES_FILE),
+            clock,
+            tasks: Mutex::new(Vec::new()),
+            report: Arc::new(Mutex::new(ExecReport::default())),
+        }
+    }
+
+    /// 替换模板中的占位符
+    pub fn expand(&self, result: &DomainResult) -> String {
+        self.template
+            .replace("{domain}", &result.domain)
+            .replace("{status}", status_name(&result.status))
+            .replace("{score}", &score::score(&result.domain).to_string())
+    }
+
+    /// 在后台为该结果运行命令，不等待命令结束
+    pub fn spawn(&self, result: &DomainResult) {
+        let command = self.expand(result);
+        let domain = result.domain.clone();
+        let (permits, limit, log_path, clock, report) =
+            (self.permits.clone(), self.timeout, self.log_path.clone(), self.clock, self.report.clone());
+        let task = tokio::spawn(async move {
+            let Ok(_permit) = permits.acquire_owned().await else {
+                return;
+            };
+            let outcome = run(&command, limit).await;
+            let mut report = report.lock().unwrap();
+            match outcome {
+                Ok(()) => report.succeeded += 1,
+                Err(reason) => {
+                    report.failed += 1;
+                    log_failure(&log_path, clock, &domain, &command, &reason);
+                }
+            }
+        });
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.retain(|task| !task.is_finished());
+        tasks.push(task);
+    }
+
+    /// 等待所有命令结束，返回统计
+    pub async fn finish(&self) -> ExecReport {
+        let tasks: Vec<JoinHandle<()>> = std::mem::take(&mut *self.tasks.lock().unwrap());
+        futures::future::join_all(tasks).await;
+        *self.report.lock().unwrap()
+    }
+
+    pub fn log_path(&self) -> &Path {
+        &self.log_path
+    }
+}
+
+/// 运行一条命令，失败时返回原因
+async fn run(command: &str, limit: Duration) -> Result<(), String> {
+    let (shell, flag) = SHELL;
+    let child = Command::new(shell)
+        .args([flag, command])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("failed to start: {}", e))?;
+    let output = match timeout(limit, child.wait_with_output()).await {
+        Ok(output) => output.map_err(|e| format!("failed to wait: {}", e))?,
+        Err(_) => return Err(format!("timed out after {:.0}s", limit.as_secs_f64())),
+    };
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let last = stderr.lines().map(str::trim).rfind(|line| !line.is_empty()).unwrap_or("");
+    match last {
+        "" => Err(output.status.to_string()),
+        line => Err(format!("{}: {}", output.status, line.chars().take(STDERR_LIMIT).collect::<String>())),
+    }
+}
+
+/// 追加一行失败记录：时间、域名、原因和命令，以制表符分隔
+fn log_failure(path: &Path, clock: Clock, domain: &str, command: &str, reason: &str) {
+    let line = format!(
+        "{}\t{}\t{}\t{}\n",
+        clock.now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        domain,
+        reason,
+        command
+    );
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()));
+    if let Err(e) = written {
+        eprintln!("无法写入 {}: {}", path.display(), e);
+    }
+}
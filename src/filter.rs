@@ -0,0 +1,812 @@
+//! 结果过滤表达式
+//!
+//! 类 SQL 的条件表达式，例如 `status = 'available' AND length(domain) <= 6 ORDER BY domain`。
+//! 支持 `AND` / `OR` / `NOT`、括号、比较运算符 `= != <> < <= > >=`、`LIKE`(`%` 和 `_` 通配符)、
+//! `length(...)` 函数，以及结尾的 `ORDER BY 字段 [ASC|DESC], ...` 和 `LIMIT n`。
+//! 字符串比较不区分大小写。
+//!
+//! 也接受更简短的写法，例如 `status==available && len<=5 && !contains("-")`：`&&` / `||` / `!`
+//! 等同于 `AND` / `OR` / `NOT`，`==` 等同于 `=`，状态名可以不加引号，`len` 为标签长度，
+//! `contains(...)` / `starts_with(...)` / `ends_with(...)` 只给一个参数时检查标签。
+
+use anyhow::{bail, Context, Result};
+use std::cmp::Ordering;
+
+use crate::{score, DomainResult, DomainStatus};
+
+/// 可在表达式中引用的结果字段
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Domain,
+    /// 域名的 Unicode 形式，不是 IDN 时与 domain 相同
+    Unicode,
+    /// 第一个点之前的标签
+    Label,
+    /// 标签的字符数
+    Len,
+    Tld,
+    /// available / registered / rate_limited / error
+    Status,
+    ReplyCode,
+    Message,
+    Timestamp,
+    RunId,
+    /// 见 [`score::score`]
+    Score,
+    /// 注册价格，没有价格的结果不满足任何比较
+    Price,
+    RenewPrice,
+    /// 溢价域名为 1，否则为 0
+    Premium,
+    /// CT 日志中的历史证书数，未经 --ct-check 查询的结果不满足任何比较
+    CtCerts,
+    /// --probe-registered 得到的状态码，未探测或无法访问的结果不满足任何比较
+    HttpStatus,
+    /// 探测到停放或待售页面为 1，否则为 0
+    Parked,
+    /// 探测到的页面标题，没有时为空字符串
+    Title,
+    /// --whois-details 得到的注册商，没有时为空字符串
+    Registrar,
+    /// 首次注册日期(YYYY-MM-DD)，没有时为空字符串
+    RegisteredOn,
+    /// whois 公开的持有人名称，没有时为空字符串
+    Holder,
+    /// --verify-available 的复核结论(confirmed、inconsistent、unverified)，未复核时为空字符串
+    Verification,
+    /// 以分号连接的标记，没有时为空字符串，可用 LIKE '%brand%' 匹配
+    Tags,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "domain" => Self::Domain,
+            "unicode" => Self::Unicode,
+            "label" => Self::Label,
+            "len" => Self::Len,
+            "tld" => Self::Tld,
+            "status" => Self::Status,
+            "reply_code" | "code" => Self::ReplyCode,
+            "message" => Self::Message,
+            "timestamp" => Self::Timestamp,
+            "run_id" => Self::RunId,
+            "score" => Self::Score,
+            "price" => Self::Price,
+            "renew_price" => Self::RenewPrice,
+            "premium" => Self::Premium,
+            "ct_certs" => Self::CtCerts,
+            "http_status" => Self::HttpStatus,
+            "parked" => Self::Parked,
+            "title" => Self::Title,
+            "registrar" => Self::Registrar,
+            "registered_on" => Self::RegisteredOn,
+            "holder" => Self::Holder,
+            "verification" => Self::Verification,
+            "tags" | "tag" => Self::Tags,
+            _ => return None,
+        })
+    }
+
+    fn value(self, result: &DomainResult) -> Value {
+        let (label, tld) = result
+            .domain
+            .split_once('.')
+            .unwrap_or((&result.domain, ""));
+        match self {
+            Self::Domain => Value::Str(result.domain.clone()),
+            Self::Unicode => Value::Str(result.unicode.clone().unwrap_or_else(|| result.domain.clone())),
+            Self::Label => Value::Str(label.to_string()),
+            Self::Len => Value::Num(label.chars().count() as f64),
+            Self::Tld => Value::Str(tld.to_string()),
+            Self::Status => Value::Str(status_name(&result.status).to_string()),
+            Self::ReplyCode => Value::Num(result.reply_code as f64),
+            Self::Message => Value::Str(result.message.clone()),
+            Self::Timestamp => Value::Str(result.timestamp.to_rfc3339()),
+            Self::RunId => Value::Str(result.run_id.clone()),
+            Self::Score => Value::Num(score::score(&result.domain) as f64),
+            Self::Price => Value::Num(result.price.as_ref().map_or(f64::NAN, |price| price.register)),
+            Self::RenewPrice => Value::Num(result.price.as_ref().map_or(f64::NAN, |price| price.renew)),
+            Self::Premium => Value::Num(result.price.as_ref().is_some_and(|price| price.premium) as u8 as f64),
+            Self::CtCerts => Value::Num(result.ct.as_ref().map_or(f64::NAN, |ct| ct.certificates as f64)),
+            Self::HttpStatus => Value::Num(
+                result
+                    .http
+                    .as_ref()
+                    .and_then(|probe| probe.status)
+                    .map_or(f64::NAN, f64::from),
+            ),
+            Self::Parked => Value::Num(result.http.as_ref().is_some_and(|probe| probe.parked) as u8 as f64),
+            Self::Title => Value::Str(
+                result
+                    .http
+                    .as_ref()
+                    .and_then(|probe| probe.title.clone())
+                    .unwrap_or_default(),
+            ),
+            Self::Registrar => Value::Str(
+                result
+                    .whois
+                    .as_ref()
+                    .and_then(|whois| whois.registrar.clone())
+                    .unwrap_or_default(),
+            ),
+            Self::RegisteredOn => Value::Str(
+                result
+                    .whois
+                    .as_ref()
+                    .and_then(|whois| whois.registered.clone())
+                    .unwrap_or_default(),
+            ),
+            Self::Holder => Value::Str(
+                result
+                    .whois
+                    .as_ref()
+                    .and_then(|whois| whois.holder.clone())
+                    .unwrap_or_default(),
+            ),
+            Self::Verification => Value::Str(
+                result
+                    .verification
+                    .as_ref()
+                    .map_or("", |verification| verification.outcome.name())
+                    .to_string(),
+            ),
+            Self::Tags => Value::Str(result.tags.join(";")),
+        }
+    }
+}
+
+/// 可以不加引号的状态名
+const STATUS_NAMES: &[&str] = &["available", "registered", "rate_limited", "error"];
+
+/// 表达式中使用的状态名
+pub fn status_name(status: &DomainStatus) -> &'static str {
+    match status {
+        DomainStatus::Available => "available",
+        DomainStatus::Registered => "registered",
+        DomainStatus::RateLimited => "rate_limited",
+        DomainStatus::Error => "error",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Str(String),
+    Num(f64),
+}
+
+impl Value {
+    fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Num(a), Value::Num(b)) => a.partial_cmp(b),
+            _ => Some(self.text().to_lowercase().cmp(&other.text().to_lowercase())),
+        }
+    }
+
+    fn is_missing(&self) -> bool {
+        matches!(self, Value::Num(n) if n.is_nan())
+    }
+
+    fn text(&self) -> String {
+        match self {
+            Value::Str(s) => s.clone(),
+            Value::Num(n) => n.to_string(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Operand {
+    Field(Field),
+    Length(Box<Operand>),
+    Literal(Value),
+}
+
+impl Operand {
+    fn eval(&self, result: &DomainResult) -> Value {
+        match self {
+            Operand::Field(field) => field.value(result),
+            Operand::Length(inner) => Value::Num(inner.eval(result).text().chars().count() as f64),
+            Operand::Literal(value) => value.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TextOp {
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+impl TextOp {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "contains" => Self::Contains,
+            "starts_with" => Self::StartsWith,
+            "ends_with" => Self::EndsWith,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// 过滤条件
+#[derive(Debug)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, CompareOp, Operand),
+    Like(Operand, Operand),
+    /// 字符串包含、以...开头或结尾
+    Text(TextOp, Operand, Operand),
+}
+
+impl Expr {
+    /// 结果是否满足条件
+    fn matches(&self, result: &DomainResult) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(result) && b.matches(result),
+            Expr::Or(a, b) => a.matches(result) || b.matches(result),
+            Expr::Not(inner) => !inner.matches(result),
+            Expr::Compare(left, op, right) => {
+                let Some(ordering) = left.eval(result).compare(&right.eval(result)) else {
+                    return false;
+                };
+                match op {
+                    CompareOp::Eq => ordering == Ordering::Equal,
+                    CompareOp::Ne => ordering != Ordering::Equal,
+                    CompareOp::Lt => ordering == Ordering::Less,
+                    CompareOp::Le => ordering != Ordering::Greater,
+                    CompareOp::Gt => ordering == Ordering::Greater,
+                    CompareOp::Ge => ordering != Ordering::Less,
+                }
+            }
+            Expr::Like(value, pattern) => {
+                let value = value.eval(result).text().to_lowercase();
+                let pattern = pattern.eval(result).text().to_lowercase();
+                let pattern: Vec<char> = pattern.chars().collect();
+                let value: Vec<char> = value.chars().collect();
+                like_match(&pattern, &value)
+            }
+            Expr::Text(op, value, needle) => {
+                let value = value.eval(result).text().to_lowercase();
+                let needle = needle.eval(result).text().to_lowercase();
+                match op {
+                    TextOp::Contains => value.contains(&needle),
+                    TextOp::StartsWith => value.starts_with(&needle),
+                    TextOp::EndsWith => value.ends_with(&needle),
+                }
+            }
+        }
+    }
+}
+
+/// 条件加排序和数量限制
+#[derive(Debug, Default)]
+pub struct Query {
+    filter: Option<Expr>,
+    order: Vec<(Field, bool)>,
+    pub limit: Option<usize>,
+}
+
+impl Query {
+    /// 解析查询，条件部分可以省略
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            pos: 0,
+        };
+        let mut query = Query::default();
+        if !parser.at_end() && !parser.peek_keyword("ORDER") && !parser.peek_keyword("LIMIT") {
+            query.filter = Some(parser.or_expr()?);
+        }
+        if parser.eat_keyword("ORDER") {
+            anyhow::ensure!(parser.eat_keyword("BY"), "Expected BY after ORDER");
+            loop {
+                let field = parser.field()?;
+                let descending = if parser.eat_keyword("DESC") {
+                    true
+                } else {
+                    parser.eat_keyword("ASC");
+                    false
+                };
+                query.order.push((field, descending));
+                if !parser.eat(&Token::Comma) {
+                    break;
+                }
+            }
+        }
+        if parser.eat_keyword("LIMIT") {
+            match parser.next() {
+                Some(Token::Num(n)) if n >= 0.0 && n.fract() == 0.0 => query.limit = Some(n as usize),
+                _ => bail!("LIMIT expects a non-negative integer"),
+            }
+        }
+        if let Some(token) = parser.next() {
+            bail!("Unexpected {} in query", describe(Some(token)));
+        }
+        Ok(query)
+    }
+
+    /// 解析只有条件、没有 ORDER BY 和 LIMIT 的表达式
+    pub fn parse_condition(input: &str) -> Result<Self> {
+        let query = Self::parse(input)?;
+        anyhow::ensure!(
+            query.order.is_empty() && query.limit.is_none(),
+            "ORDER BY and LIMIT are not supported here"
+        );
+        Ok(query)
+    }
+
+    /// 结果是否满足条件
+    pub fn matches(&self, result: &DomainResult) -> bool {
+        self.filter.as_ref().is_none_or(|filter| filter.matches(result))
+    }
+
+    /// 按 ORDER BY 排序
+    pub fn sort(&self, results: &mut [&DomainResult]) {
+        if self.order.is_empty() {
+            return;
+        }
+        results.sort_by(|a, b| {
+            for (field, descending) in &self.order {
+                let (a, b) = (field.value(a), field.value(b));
+                // 没有值(如没有价格)的结果无论升序降序都排在最后
+                let ordering = match (a.is_missing(), b.is_missing()) {
+                    (false, false) => {
+                        let ordering = a.compare(&b).unwrap_or(Ordering::Equal);
+                        if *descending { ordering.reverse() } else { ordering }
+                    }
+                    (missing_a, missing_b) => missing_a.cmp(&missing_b),
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            Ordering::Equal
+        });
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        match c {
+            '(' => tokens.push(Token::LParen),
+            ')' => tokens.push(Token::RParen),
+            ',' => tokens.push(Token::Comma),
+            '\'' | '"' => {
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        // 引号内连续两个引号表示引号本身
+                        Some(&q) if q == c && chars.get(i + 1) == Some(&c) => {
+                            value.push(c);
+                            i += 1;
+                        }
+                        Some(&q) if q == c => break,
+                        Some(&other) => value.push(other),
+                        None => bail!("Unterminated string in query"),
+                    }
+                    i += 1;
+                }
+                tokens.push(Token::Str(value));
+            }
+            '=' | '!' | '<' | '>' | '&' | '|' => {
+                let (op, len) = match (c, chars.get(i + 1)) {
+                    ('<', Some('=')) => ("<=", 2),
+                    ('>', Some('=')) => (">=", 2),
+                    ('<', Some('>')) | ('!', Some('=')) => ("!=", 2),
+                    ('=', Some('=')) => ("=", 2),
+                    ('&', Some('&')) => ("&&", 2),
+                    ('|', Some('|')) => ("||", 2),
+                    ('=', _) => ("=", 1),
+                    ('<', _) => ("<", 1),
+                    ('>', _) => (">", 1),
+                    ('!', _) => ("!", 1),
+                    _ => bail!("Unexpected `{}` in query", c),
+                };
+                tokens.push(Token::Op(op));
+                i += len;
+                continue;
+            }
+            c if c.is_ascii_digit() || (c == '-' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) => {
+                let start = i;
+                i += 1;
+                while chars.get(i).is_some_and(|d| d.is_ascii_digit() || *d == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                tokens.push(Token::Num(text.parse().with_context(|| format!("Invalid number `{}`", text))?));
+                continue;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while chars.get(i).is_some_and(|d| d.is_alphanumeric() || *d == '_') {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+                continue;
+            }
+            _ => bail!("Unexpected `{}` in query", c),
+        }
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.tokens.get(self.pos) == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Ident(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn field(&mut self) -> Result<Field> {
+        match self.next() {
+            Some(Token::Ident(name)) => Field::parse(&name).with_context(|| format!("Unknown field `{}`", name)),
+            other => bail!("Expected a field name, found {}", describe(other)),
+        }
+    }
+
+    fn or_expr(&mut self) -> Result<Expr> {
+        let mut left = self.and_expr()?;
+        while self.eat_keyword("OR") || self.eat(&Token::Op("||")) {
+            left = Expr::Or(Box::new(left), Box::new(self.and_expr()?));
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr> {
+        let mut left = self.unary()?;
+        while self.eat_keyword("AND") || self.eat(&Token::Op("&&")) {
+            left = Expr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat_keyword("NOT") || self.eat(&Token::Op("!")) {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat(&Token::LParen) {
+            let inner = self.or_expr()?;
+            anyhow::ensure!(self.eat(&Token::RParen), "Expected `)`");
+            return Ok(inner);
+        }
+        if let Some(op) = self.text_op() {
+            self.pos += 2;
+            let first = self.operand()?;
+            let (value, needle) = if self.eat(&Token::Comma) {
+                (first, self.operand()?)
+            } else {
+                (Operand::Field(Field::Label), first)
+            };
+            anyhow::ensure!(self.eat(&Token::RParen), "Expected `)`");
+            return Ok(Expr::Text(op, value, needle));
+        }
+        self.comparison()
+    }
+
+    /// 当前位置是否为 contains( / starts_with( / ends_with(
+    fn text_op(&self) -> Option<TextOp> {
+        match self.tokens.get(self.pos..self.pos + 2) {
+            Some([Token::Ident(name), Token::LParen]) => TextOp::parse(name),
+            _ => None,
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        let left = self.operand()?;
+        let negated = self.eat_keyword("NOT");
+        if self.eat_keyword("LIKE") {
+            let like = Expr::Like(left, self.operand()?);
+            return Ok(if negated { Expr::Not(Box::new(like)) } else { like });
+        }
+        anyhow::ensure!(!negated, "Expected LIKE after NOT");
+        let op = match self.next() {
+            Some(Token::Op("=")) => CompareOp::Eq,
+            Some(Token::Op("!=")) => CompareOp::Ne,
+            Some(Token::Op("<")) => CompareOp::Lt,
+            Some(Token::Op("<=")) => CompareOp::Le,
+            Some(Token::Op(">")) => CompareOp::Gt,
+            Some(Token::Op(">=")) => CompareOp::Ge,
+            other => bail!("Expected a comparison operator, found {}", describe(other)),
+        };
+        Ok(Expr::Compare(left, op, self.operand()?))
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        match self.next() {
+            Some(Token::Str(s)) => Ok(Operand::Literal(Value::Str(s))),
+            Some(Token::Num(n)) => Ok(Operand::Literal(Value::Num(n))),
+            Some(Token::Ident(name))
+                if (name.eq_ignore_ascii_case("length") || name.eq_ignore_ascii_case("len"))
+                    && self.tokens.get(self.pos) == Some(&Token::LParen) =>
+            {
+                self.pos += 1;
+                let inner = self.operand()?;
+                anyhow::ensure!(self.eat(&Token::RParen), "Expected `)`");
+                Ok(Operand::Length(Box::new(inner)))
+            }
+            Some(Token::Ident(name)) => match Field::parse(&name) {
+                Some(field) => Ok(Operand::Field(field)),
+                // 不加引号的状态名
+                None if STATUS_NAMES.contains(&name.to_ascii_lowercase().as_str()) => {
+                    Ok(Operand::Literal(Value::Str(name)))
+                }
+                None => bail!("Unknown field `{}`", name),
+            },
+            other => bail!("Expected a field or value, found {}", describe(other)),
+        }
+    }
+}
+
+/// 用于错误信息的记号描述
+fn describe(token: Option<Token>) -> String {
+    match token {
+        None => "end of query".to_string(),
+        Some(Token::Ident(name)) => format!("`{}`", name),
+        Some(Token::Str(s)) => format!("'{}'", s),
+        Some(Token::Num(n)) => n.to_string(),
+        Some(Token::Op(op)) => format!("`{}`", op),
+        Some(Token::LParen) => "`(`".to_string(),
+        Some(Token::RParen) => "`)`".to_string(),
+        Some(Token::Comma) => "`,`".to_string(),
+    }
+}
+
+/// SQL LIKE 匹配：`%` 匹配任意长度，`_` 匹配单个字符(Unicode 字符，不是字节)
+fn like_match(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('%') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '_' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '%')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(domain: &str, status: &str, extra: serde_json::Value) -> DomainResult {
+        let mut value = serde_json::json!({
+            "domain": domain,
+            "status": status,
+            "reply_code": 0,
+            "message": "",
+            "timestamp": "2024-01-01T00:00:00Z",
+        });
+        if let serde_json::Value::Object(extra) = extra {
+            value.as_object_mut().unwrap().extend(extra);
+        }
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn sample() -> Vec<DomainResult> {
+        let price = |register: f64| {
+            serde_json::json!({
+                "price": {"currency": "CHF", "register": register, "renew": register, "premium": false},
+            })
+        };
+        vec![
+            result("ab.li", "Available", price(10.0)),
+            result("zz9.li", "Available", price(30.0)),
+            result("qq.ch", "Registered", serde_json::json!({})),
+            result("a-b.ch", "Error", serde_json::json!({"message": "Timeout"})),
+            result("xyzw.li", "Registered", serde_json::json!({"tags": ["brand", "short"]})),
+        ]
+    }
+
+    /// 满足条件的域名，按 sample 中的顺序
+    fn select(query: &str) -> Vec<String> {
+        let query = Query::parse(query).unwrap_or_else(|e| panic!("{}: {:#}", query, e));
+        sample()
+            .iter()
+            .filter(|result| query.matches(result))
+            .map(|result| result.domain.clone())
+            .collect()
+    }
+
+    #[test]
+    fn conditions_select_results() {
+        let cases: &[(&str, &[&str])] = &[
+            ("", &["ab.li", "zz9.li", "qq.ch", "a-b.ch", "xyzw.li"]),
+            // AND 比 OR 优先，NOT 比 AND 优先
+            ("status = 'available' OR tld = 'ch' AND len = 2", &["ab.li", "zz9.li", "qq.ch"]),
+            ("(status = 'available' OR tld = 'ch') AND len = 2", &["ab.li", "qq.ch"]),
+            ("NOT status = 'available' AND tld = 'li'", &["xyzw.li"]),
+            ("NOT (status = 'available' AND tld = 'li')", &["qq.ch", "a-b.ch", "xyzw.li"]),
+            ("not tld = 'ch' or len > 3", &["ab.li", "zz9.li", "xyzw.li"]),
+            // 简短写法
+            ("status==available && len<=2", &["ab.li"]),
+            ("status == error || tags LIKE '%brand%'", &["a-b.ch", "xyzw.li"]),
+            ("!contains(\"-\") && tld == 'ch'", &["qq.ch"]),
+            ("!(tld == 'li' || len == 3)", &["qq.ch"]),
+            ("status <> registered AND tld = 'ch'", &["a-b.ch"]),
+            ("status != registered AND tld = 'ch'", &["a-b.ch"]),
+            // 不加引号的状态名，不区分大小写
+            ("status = registered", &["qq.ch", "xyzw.li"]),
+            ("STATUS = Registered", &["qq.ch", "xyzw.li"]),
+            ("status = rate_limited", &[]),
+            // LIKE 和 NOT LIKE
+            ("domain LIKE '%.li'", &["ab.li", "zz9.li", "xyzw.li"]),
+            ("domain NOT LIKE '%.li'", &["qq.ch", "a-b.ch"]),
+            ("label like '_b'", &["ab.li"]),
+            ("message LIKE 'time%'", &["a-b.ch"]),
+            // length() 和 len
+            ("length(domain) = 6", &["zz9.li", "a-b.ch"]),
+            ("len(label) > 3", &["xyzw.li"]),
+            ("length(tld) < len", &["zz9.li", "a-b.ch", "xyzw.li"]),
+            // 文本函数
+            ("starts_with('a')", &["ab.li", "a-b.ch"]),
+            ("ends_with(domain, '.ch')", &["qq.ch", "a-b.ch"]),
+            ("contains(tags, 'short')", &["xyzw.li"]),
+            // 没有价格的结果不满足任何比较
+            ("price >= 10", &["ab.li", "zz9.li"]),
+            ("price != 10", &["zz9.li"]),
+            ("NOT price < 20", &["zz9.li", "qq.ch", "a-b.ch", "xyzw.li"]),
+            ("premium = 0", &["ab.li", "zz9.li", "qq.ch", "a-b.ch", "xyzw.li"]),
+            // 引号内连续两个引号表示引号本身
+            ("message = 'it''s'", &[]),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(select(query), *expected, "{}", query);
+        }
+    }
+
+    #[test]
+    fn order_by_and_limit_sort_results() {
+        let results = sample();
+        let cases: &[(&str, &[&str], Option<usize>)] = &[
+            ("ORDER BY domain", &["a-b.ch", "ab.li", "qq.ch", "xyzw.li", "zz9.li"], None),
+            ("order by domain desc", &["zz9.li", "xyzw.li", "qq.ch", "ab.li", "a-b.ch"], None),
+            ("ORDER BY len DESC, domain ASC", &["xyzw.li", "a-b.ch", "zz9.li", "ab.li", "qq.ch"], None),
+            // 没有价格的结果无论升序降序都排在最后，其余保持原来的顺序
+            ("ORDER BY price DESC", &["zz9.li", "ab.li", "qq.ch", "a-b.ch", "xyzw.li"], None),
+            ("ORDER BY price", &["ab.li", "zz9.li", "qq.ch", "a-b.ch", "xyzw.li"], None),
+            ("status = registered ORDER BY domain DESC LIMIT 1", &["xyzw.li", "qq.ch"], Some(1)),
+            ("LIMIT 0", &["ab.li", "zz9.li", "qq.ch", "a-b.ch", "xyzw.li"], Some(0)),
+        ];
+        for (text, expected, limit) in cases {
+            let query = Query::parse(text).unwrap_or_else(|e| panic!("{}: {:#}", text, e));
+            let mut selected: Vec<&DomainResult> = results.iter().filter(|result| query.matches(result)).collect();
+            query.sort(&mut selected);
+            let domains: Vec<&str> = selected.iter().map(|result| result.domain.as_str()).collect();
+            assert_eq!(domains, *expected, "{}", text);
+            assert_eq!(query.limit, *limit, "{}", text);
+        }
+    }
+
+    #[test]
+    fn invalid_queries_are_rejected() {
+        let cases = [
+            ("domain = 'ab", "Unterminated string"),
+            ("len = 2 & tld = 'li'", "Unexpected `&`"),
+            ("domain = #", "Unexpected `#`"),
+            ("len = 1.2.3", "Invalid number `1.2.3`"),
+            ("colour = 'red'", "Unknown field `colour`"),
+            ("available", "Expected a comparison operator, found end of query"),
+            ("domain 'ab.li'", "Expected a comparison operator, found 'ab.li'"),
+            ("domain =", "Expected a field or value, found end of query"),
+            ("domain = (", "Expected a field or value, found `(`"),
+            ("(status = available", "Expected `)`"),
+            ("length(domain = 3", "Expected `)`"),
+            ("contains('a' = 1", "Expected `)`"),
+            ("domain NOT = 'ab.li'", "Expected LIKE after NOT"),
+            ("ORDER domain", "Expected BY after ORDER"),
+            ("ORDER BY 3", "Expected a field name, found 3"),
+            ("ORDER BY colour", "Unknown field `colour`"),
+            ("LIMIT", "LIMIT expects a non-negative integer"),
+            ("LIMIT -1", "LIMIT expects a non-negative integer"),
+            ("LIMIT 1.5", "LIMIT expects a non-negative integer"),
+            ("status = available available", "Unexpected `available` in query"),
+            ("LIMIT 1 ORDER BY domain", "Unexpected `ORDER` in query"),
+        ];
+        for (query, message) in cases {
+            let error = format!("{:#}", Query::parse(query).expect_err(query));
+            assert!(error.contains(message), "{}: {}", query, error);
+        }
+
+        for query in ["ORDER BY domain", "len = 2 LIMIT 3"] {
+            let error = format!("{:#}", Query::parse_condition(query).expect_err(query));
+            assert!(error.contains("ORDER BY and LIMIT are not supported here"), "{}: {}", query, error);
+        }
+        assert!(Query::parse_condition("len = 2").is_ok());
+    }
+
+    fn like(pattern: &str, text: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let text: Vec<char> = text.chars().collect();
+        like_match(&pattern, &text)
+    }
+
+    #[test]
+    fn like_matches_characters() {
+        assert!(like("z_rich.li", "zürich.li"));
+        assert!(like("%ü%", "zürich.li"));
+        assert!(like("___.li", "abc.li"));
+        assert!(!like("__.li", "ü.li"));
+        assert!(!like("a%c", "abcd"));
+        assert!(like("a%", "a"));
+    }
+}
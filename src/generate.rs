@@ -0,0 +1,773 @@
+//! 候选域名生成
+//!
+//! 各生成器都是惰性的 `Iterator<Item = String>`，逐个产生标签而不预先构造全部候选，
+//! 可以直接与 `filter` 等适配器组合，需要异步流时用 `futures::stream::iter` 包装。
+//! [`WithTlds`] 为每个标签加上所有 TLD，同一标签的各个 TLD 相邻，以分散各查询服务的负载。
+//!
+//! 生成有意保持单线程：扫描按查询服务允许的速率逐个取用候选，单线程枚举的速度比查询快几个
+//! 数量级，并行预先生成全部候选只会把整个枚举空间留在内存中。
+
+use anyhow::{Context, Result};
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufRead, BufReader, Lines},
+    path::Path,
+};
+
+const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const LETTERS_HYPHEN: &[u8] = b"abcdefghijklmnopqrstuvwxyz-";
+const ALPHANUMERIC: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+const ALPHANUMERIC_HYPHEN: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789-";
+
+/// 标签的最大长度
+const MAX_LABEL_LENGTH: usize = 63;
+
+/// 指定长度的全部标签，按字符表顺序(字母、数字、连字符)排列
+///
+/// 标签由字母(和数字)组成，中间可以有连字符，但不以连字符开头或结尾，也不含连续的
+/// 连字符。在复用的下标数组上按进位方式递增，每个标签只分配一次。
+#[derive(Debug, Clone)]
+pub struct BruteForceGen {
+    /// 首尾位置可用的字符
+    edge: &'static [u8],
+    /// 中间位置可用的字符
+    inner: &'static [u8],
+    /// 下一个候选各位置的字符下标，全部枚举完后为 `None`
+    digits: Option<Vec<usize>>,
+    /// 剩余的标签数，超出 usize 范围时为 `None`
+    remaining: Option<usize>,
+}
+
+impl BruteForceGen {
+    pub fn new(length: usize, letters_only: bool) -> Self {
+        let (edge, inner) = if letters_only {
+            (LETTERS, LETTERS_HYPHEN)
+        } else {
+            (ALPHANUMERIC, ALPHANUMERIC_HYPHEN)
+        };
+        let valid = (1..=MAX_LABEL_LENGTH).contains(&length);
+        Self {
+            edge,
+            inner,
+            digits: valid.then(|| vec![0; length]),
+            remaining: if valid { count_labels(length, edge.len()) } else { Some(0) },
+        }
+    }
+
+    fn alphabet(&self, position: usize, length: usize) -> &'static [u8] {
+        if position == 0 || position == length - 1 {
+            self.edge
+        } else {
+            self.inner
+        }
+    }
+}
+
+/// 首尾取自 edge 个字符、中间还可以是连字符且没有连续连字符的标签数
+fn count_labels(length: usize, edge: usize) -> Option<usize> {
+    if length == 1 {
+        return Some(edge);
+    }
+    // 分别统计以连字符和以其他字符结尾的前缀
+    let (mut plain, mut hyphen) = (edge, 0usize);
+    for _ in 1..length - 1 {
+        (plain, hyphen) = (plain.checked_add(hyphen)?.checked_mul(edge)?, plain);
+    }
+    plain.checked_add(hyphen)?.checked_mul(edge)
+}
+
+impl Iterator for BruteForceGen {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            let length = self.digits.as_ref()?.len();
+            let mut label = Vec::with_capacity(length);
+            for (position, &digit) in self.digits.as_ref()?.iter().enumerate() {
+                label.push(self.alphabet(position, length)[digit]);
+            }
+
+            // 从最后一位开始进位，第一位也溢出时枚举结束
+            let mut position = length;
+            let exhausted = loop {
+                if position == 0 {
+                    break true;
+                }
+                position -= 1;
+                let radix = self.alphabet(position, length).len();
+                let digits = self.digits.as_mut()?;
+                digits[position] += 1;
+                if digits[position] < radix {
+                    break false;
+                }
+                digits[position] = 0;
+            };
+            if exhausted {
+                self.digits = None;
+            }
+
+            if !label.windows(2).any(|pair| pair == b"--") {
+                self.remaining = self.remaining.map(|remaining| remaining.saturating_sub(1));
+                // 字符表只含 ASCII 字符
+                return Some(String::from_utf8(label).expect("labels are ASCII"));
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.remaining {
+            Some(remaining) => (remaining, Some(remaining)),
+            None => (usize::MAX, None),
+        }
+    }
+}
+
+/// 两个字符组成的模式，0 为第一个字符，1 为第二个字符
+const THREE_ONE: &[[usize; 4]] = &[[0, 0, 0, 1], [0, 0, 1, 0], [0, 1, 0, 0], [1, 0, 0, 0]];
+const TWO_TWO: &[[usize; 4]] = &[[0, 0, 1, 1], [0, 1, 1, 0], [0, 1, 0, 1]];
+
+/// 四字符的重复模式
+///
+/// 依次为 aaaa，aaab / aaba / abaa / baaa，aabb / abba / abab，a、b 为不同的字符。
+#[derive(Debug, Clone)]
+pub struct PatternGen {
+    chars: &'static [u8],
+    /// 在所有 (模式组, 第一个字符, 第二个字符, 模式) 组合中的位置，包括两个字符相同的组合
+    index: usize,
+    remaining: usize,
+}
+
+impl PatternGen {
+    pub fn new(letters_only: bool) -> Self {
+        let chars = if letters_only { LETTERS } else { ALPHANUMERIC };
+        let n = chars.len();
+        Self {
+            chars,
+            index: 0,
+            remaining: n + n * (n - 1) * (THREE_ONE.len() + TWO_TWO.len()),
+        }
+    }
+}
+
+impl Iterator for PatternGen {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let n = self.chars.len();
+        loop {
+            let index = self.index;
+            if index < n {
+                self.index += 1;
+                self.remaining -= 1;
+                return Some(char::from(self.chars[index]).to_string().repeat(4));
+            }
+            let (templates, offset) = if index < n + n * n * THREE_ONE.len() {
+                (THREE_ONE, index - n)
+            } else if index < n + n * n * (THREE_ONE.len() + TWO_TWO.len()) {
+                (TWO_TWO, index - n - n * n * THREE_ONE.len())
+            } else {
+                return None;
+            };
+            self.index += 1;
+
+            let first = offset / (n * templates.len());
+            let second = offset / templates.len() % n;
+            if first == second {
+                continue;
+            }
+            let pair = [self.chars[first], self.chars[second]];
+            let label = templates[offset % templates.len()].map(|which| pair[which]);
+            self.remaining -= 1;
+            return Some(String::from_utf8(label.to_vec()).expect("labels are ASCII"));
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// 词表中的标签，每行一个
+///
+/// 忽略空行和 `#` 开头的注释以及标签后逗号之后的标记，按 [`normalize_label`] 转换，跳过
+/// 不合法的标签和重复的行。
+pub struct WordlistGen<R> {
+    lines: Lines<R>,
+    seen: HashSet<String>,
+}
+
+impl WordlistGen<BufReader<File>> {
+    /// 读取词表文件
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Failed to open wordlist {}", path.display()))?;
+        Ok(Self::new(BufReader::new(file)))
+    }
+}
+
+impl<R: BufRead> WordlistGen<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            seen: HashSet::new(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for WordlistGen<R> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => {
+                    eprintln!("警告: 读取词表出错，之后的内容被忽略: {}", e);
+                    return None;
+                }
+            };
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+            // 逗号之后的标记由 TagIndex 另行读取
+            let (name, _) = crate::tags::split_line(line);
+            let Some(label) = normalize_label(name) else {
+                continue;
+            };
+            if self.seen.insert(label.clone()) {
+                return Some(label);
+            }
+        }
+    }
+}
+
+/// 生成首字母缩写时可以省略的虚词(德、法、意、英)
+const STOPWORDS: &[&str] = &[
+    "der", "die", "das", "des", "dem", "den", "und", "für", "fur", "von", "vom", "zum", "zur", "im", "in", "am", "an",
+    "auf", "mit", "le", "la", "les", "de", "du", "et", "pour", "en", "il", "lo", "gli", "di", "del", "della",
+    "e", "per", "the", "of", "and", "for",
+];
+
+/// 短语文件中每个短语(或名称)的首字母缩写，每行一个
+///
+/// 单词以空白、连字符和标点分隔，"zentrale informatik dienste" 依次产生 zid 和带连字符的
+/// z-i-d；含有虚词时再产生省略虚词的缩写，如 "Bundesamt für Informatik und Telekommunikation"
+/// 还产生 bit。忽略空行和 `#` 开头的注释，跳过不合法的标签和重复的缩写。
+pub struct AcronymGen<R> {
+    lines: Lines<R>,
+    pending: std::vec::IntoIter<String>,
+    seen: HashSet<String>,
+}
+
+impl AcronymGen<BufReader<File>> {
+    /// 读取短语文件
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Failed to open phrase file {}", path.display()))?;
+        Ok(Self::new(BufReader::new(file)))
+    }
+}
+
+impl<R: BufRead> AcronymGen<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            pending: Vec::new().into_iter(),
+            seen: HashSet::new(),
+        }
+    }
+}
+
+/// 一个短语的各种缩写，未经校验
+fn acronyms(phrase: &str) -> Vec<String> {
+    let words: Vec<String> = phrase
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let content: Vec<&String> = words.iter().filter(|word| !STOPWORDS.contains(&word.as_str())).collect();
+
+    let mut initials = vec![words.iter().filter_map(|word| word.chars().next()).collect::<Vec<char>>()];
+    if !content.is_empty() && content.len() < words.len() {
+        initials.push(content.iter().filter_map(|word| word.chars().next()).collect());
+    }
+    let mut variants = Vec::new();
+    for letters in initials.iter().filter(|letters| letters.len() >= 2) {
+        variants.push(letters.iter().collect());
+        variants.push(letters.iter().map(char::to_string).collect::<Vec<_>>().join("-"));
+    }
+    variants
+}
+
+impl<R: BufRead> Iterator for AcronymGen<R> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            for candidate in self.pending.by_ref() {
+                let Some(label) = normalize_label(&candidate) else {
+                    continue;
+                };
+                if self.seen.insert(label.clone()) {
+                    return Some(label);
+                }
+            }
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => {
+                    eprintln!("警告: 读取短语文件出错，之后的内容被忽略: {}", e);
+                    return None;
+                }
+            };
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+            self.pending = acronyms(line).into_iter();
+        }
+    }
+}
+
+/// 外形相近的字符(串)，左边可以被替换为右边的任意一项
+///
+/// 前几项是 ASCII 中的组合和字母数字混淆，其余为外形与拉丁字母相同的西里尔、希腊字母，
+/// 替换后成为 IDN。
+const CONFUSABLES: &[(&str, &[&str])] = &[
+    ("m", &["rn", "nn"]),
+    ("rn", &["m"]),
+    ("w", &["vv"]),
+    ("vv", &["w"]),
+    ("d", &["cl"]),
+    ("cl", &["d"]),
+    ("l", &["1", "i", "\u{04CF}"]),
+    ("1", &["l", "i"]),
+    ("i", &["1", "l", "\u{0456}"]),
+    ("0", &["o"]),
+    ("o", &["0", "\u{043E}", "\u{03BF}"]),
+    ("a", &["\u{0430}"]),
+    ("c", &["\u{0441}"]),
+    ("e", &["\u{0435}"]),
+    ("h", &["\u{04BB}"]),
+    ("j", &["\u{0458}"]),
+    ("p", &["\u{0440}"]),
+    ("s", &["\u{0455}"]),
+    ("x", &["\u{0445}"]),
+    ("y", &["\u{0443}"]),
+];
+
+/// 名称的近形变体，用于发现仿冒域名
+///
+/// 在名称上做一处或两处不重叠的 [`CONFUSABLES`] 替换，按替换位置的顺序排列。变体转换为
+/// A-label，跳过不合法的变体、重复的变体和名称本身。
+#[derive(Debug)]
+pub struct ConfusableGen {
+    variants: std::vec::IntoIter<String>,
+}
+
+impl ConfusableGen {
+    /// 名称可以是 Unicode 形式或 A-label
+    pub fn new(name: &str) -> Result<Self> {
+        let label = normalize_label(name).with_context(|| format!("`{}` is not a valid domain label", name))?;
+        let unicode = crate::idna::to_unicode(&label).unwrap_or_else(|| label.clone());
+        let chars: Vec<char> = unicode.chars().collect();
+
+        // 每处可能的替换：(起始位置, 结束位置, 替换内容)
+        let mut edits = Vec::new();
+        for start in 0..chars.len() {
+            for (from, replacements) in CONFUSABLES {
+                let end = start + from.chars().count();
+                if end <= chars.len() && chars[start..end].iter().copied().eq(from.chars()) {
+                    edits.extend(replacements.iter().map(|to| (start, end, *to)));
+                }
+            }
+        }
+
+        let apply = |applied: &[&(usize, usize, &str)]| {
+            let mut variant = String::new();
+            let mut position = 0;
+            for (start, end, to) in applied {
+                variant.extend(&chars[position..*start]);
+                variant.push_str(to);
+                position = *end;
+            }
+            variant.extend(&chars[position..]);
+            variant
+        };
+        let mut candidates = Vec::new();
+        for (index, first) in edits.iter().enumerate() {
+            candidates.push(apply(&[first]));
+            for second in &edits[index + 1..] {
+                if second.0 >= first.1 {
+                    candidates.push(apply(&[first, second]));
+                }
+            }
+        }
+
+        let mut seen = HashSet::from([label]);
+        let variants: Vec<String> = candidates
+            .iter()
+            .filter_map(|candidate| normalize_label(candidate))
+            .filter(|variant| seen.insert(variant.clone()))
+            .collect();
+        Ok(Self {
+            variants: variants.into_iter(),
+        })
+    }
+}
+
+impl Iterator for ConfusableGen {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.variants.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.variants.size_hint()
+    }
+}
+
+/// QWERTY 键盘上每个键相邻的键
+const KEYBOARD_NEIGHBOURS: &[(char, &str)] = &[
+    ('1', "2q"),
+    ('2', "13qw"),
+    ('3', "24we"),
+    ('4', "35er"),
+    ('5', "46rt"),
+    ('6', "57ty"),
+    ('7', "68yu"),
+    ('8', "79ui"),
+    ('9', "80io"),
+    ('0', "9op"),
+    ('q', "12wa"),
+    ('w', "23qeas"),
+    ('e', "34wrsd"),
+    ('r', "45etdf"),
+    ('t', "56ryfg"),
+    ('y', "67tugh"),
+    ('u', "78yihj"),
+    ('i', "89uojk"),
+    ('o', "90ipkl"),
+    ('p', "0ol"),
+    ('a', "qwsz"),
+    ('s', "weadzx"),
+    ('d', "ersfxc"),
+    ('f', "rtdgcv"),
+    ('g', "tyfhvb"),
+    ('h', "yugjbn"),
+    ('j', "uihknm"),
+    ('k', "iojlm"),
+    ('l', "opk"),
+    ('z', "asx"),
+    ('x', "sdzc"),
+    ('c', "dfxv"),
+    ('v', "fgcb"),
+    ('b', "ghvn"),
+    ('n', "hjbm"),
+    ('m', "jkn"),
+];
+
+/// 名称的常见拼写错误变体，用于发现抢注的仿冒域名
+///
+/// 依次为漏掉一个字符、重复一个字符、交换相邻字符、按到键盘上相邻的键、多按一个相邻的键
+/// 和插入连字符。名称可以是 Unicode 形式或 A-label，变体转换为 A-label，跳过不合法的变体、
+/// 重复的变体和名称本身。
+#[derive(Debug)]
+pub struct TyposquatGen {
+    variants: std::vec::IntoIter<String>,
+}
+
+impl TyposquatGen {
+    pub fn new(name: &str) -> Result<Self> {
+        let label = normalize_label(name).with_context(|| format!("`{}` is not a valid domain label", name))?;
+        let unicode = crate::idna::to_unicode(&label).unwrap_or_else(|| label.clone());
+        let chars: Vec<char> = unicode.chars().collect();
+        let neighbours = |c: char| {
+            KEYBOARD_NEIGHBOURS
+                .iter()
+                .find(|(key, _)| *key == c)
+                .map_or("", |(_, neighbours)| neighbours)
+        };
+        let with = |position: usize, skip: usize, insert: &str| {
+            let mut variant: String = chars[..position].iter().collect();
+            variant.push_str(insert);
+            variant.extend(&chars[position + skip..]);
+            variant
+        };
+
+        let mut candidates = Vec::new();
+        for position in 0..chars.len() {
+            candidates.push(with(position, 1, ""));
+        }
+        for (position, c) in chars.iter().enumerate() {
+            candidates.push(with(position, 0, &c.to_string()));
+        }
+        for position in 0..chars.len().saturating_sub(1) {
+            let swapped: String = [chars[position + 1], chars[position]].iter().collect();
+            candidates.push(with(position, 2, &swapped));
+        }
+        for (position, &c) in chars.iter().enumerate() {
+            for neighbour in neighbours(c).chars() {
+                candidates.push(with(position, 1, &neighbour.to_string()));
+            }
+        }
+        for (position, &c) in chars.iter().enumerate() {
+            for neighbour in neighbours(c).chars() {
+                candidates.push(with(position, 0, &neighbour.to_string()));
+                candidates.push(with(position + 1, 0, &neighbour.to_string()));
+            }
+        }
+        for position in 1..chars.len() {
+            candidates.push(with(position, 0, "-"));
+        }
+
+        let mut seen = HashSet::from([label]);
+        let variants: Vec<String> = candidates
+            .into_iter()
+            .filter(|candidate| !candidate.contains("--"))
+            .filter_map(|candidate| normalize_label(&candidate))
+            .filter(|variant| seen.insert(variant.clone()))
+            .collect();
+        Ok(Self {
+            variants: variants.into_iter(),
+        })
+    }
+}
+
+impl Iterator for TyposquatGen {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.variants.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.variants.size_hint()
+    }
+}
+
+const VOWELS: &[u8] = b"aeiou";
+
+/// 生成时对标签形状的限制，各项可以组合，未设置的项不限制
+///
+/// 只看 ASCII 字母，元音为 a、e、i、o、u，y 和其他字母算作辅音。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LabelShape {
+    /// 至少含有的元音数
+    pub min_vowels: usize,
+    /// 不含相邻的两个相同字符
+    pub no_double_letters: bool,
+    /// 只由字母组成，元音和辅音交替出现
+    pub alternating_cv: bool,
+}
+
+impl LabelShape {
+    /// 是否设置了任何限制
+    pub fn is_active(&self) -> bool {
+        self.min_vowels > 0 || self.no_double_letters || self.alternating_cv
+    }
+
+    /// 标签是否满足全部限制
+    pub fn allows(&self, label: &str) -> bool {
+        let bytes = label.as_bytes();
+        if bytes.iter().filter(|b| VOWELS.contains(b)).count() < self.min_vowels {
+            return false;
+        }
+        if self.no_double_letters && bytes.windows(2).any(|pair| pair[0] == pair[1]) {
+            return false;
+        }
+        if self.alternating_cv {
+            let all_letters = bytes.iter().all(u8::is_ascii_lowercase);
+            let alternates = bytes
+                .windows(2)
+                .all(|pair| VOWELS.contains(&pair[0]) != VOWELS.contains(&pair[1]));
+            if !(all_letters && alternates) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// 标签是否合法：1 到 63 个小写字母、数字或连字符，不以连字符开头或结尾
+pub fn is_valid_label(label: &str) -> bool {
+    (1..=MAX_LABEL_LENGTH).contains(&label.len())
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+/// 把输入的标签转换为小写的 A-label(见 [`crate::idna`])，不合法时为 `None`
+pub fn normalize_label(label: &str) -> Option<String> {
+    crate::idna::to_ascii(label).ok().filter(|label| is_valid_label(label))
+}
+
+/// 为每个标签依次加上所有 TLD
+#[derive(Debug, Clone)]
+pub struct WithTlds<I> {
+    labels: I,
+    tlds: Vec<String>,
+    label: Option<String>,
+    next_tld: usize,
+}
+
+impl<I: Iterator<Item = String>> WithTlds<I> {
+    pub fn new(labels: I, tlds: Vec<String>) -> Self {
+        Self {
+            labels,
+            tlds,
+            label: None,
+            next_tld: 0,
+        }
+    }
+}
+
+impl<I: Iterator<Item = String>> Iterator for WithTlds<I> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            if let (Some(label), Some(tld)) = (&self.label, self.tlds.get(self.next_tld)) {
+                self.next_tld += 1;
+                let mut domain = String::with_capacity(label.len() + 1 + tld.len());
+                domain.push_str(label);
+                domain.push('.');
+                domain.push_str(tld);
+                return Some(domain);
+            }
+            self.label = Some(self.labels.next()?);
+            self.next_tld = 0;
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = match self.label {
+            Some(_) => self.tlds.len() - self.next_tld,
+            None => 0,
+        };
+        let (lower, upper) = self.labels.size_hint();
+        let domains = |labels: usize| labels.checked_mul(self.tlds.len())?.checked_add(pending);
+        (domains(lower).unwrap_or(usize::MAX), upper.and_then(domains))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn brute_force_counts_follow_the_hyphen_rules() {
+        // 首尾为字母(数字)，中间还可以是连字符，但不能有两个相邻的连字符
+        let cases = [
+            (1, true, 26),
+            (2, true, 26 * 26),
+            (3, true, 26 * 27 * 26),
+            (4, true, 26 * (27 * 27 - 1) * 26),
+            (1, false, 36),
+            (2, false, 36 * 36),
+            (3, false, 36 * 37 * 36),
+            (4, false, 36 * (37 * 37 - 1) * 36),
+            (0, false, 0),
+            (64, true, 0),
+        ];
+        for (length, letters_only, expected) in cases {
+            let generator = BruteForceGen::new(length, letters_only);
+            assert_eq!(generator.size_hint(), (expected, Some(expected)), "length {}", length);
+            if length > 3 {
+                assert_eq!(generator.count(), expected, "length {}", length);
+                continue;
+            }
+            let labels: Vec<String> = generator.collect();
+            assert_eq!(labels.len(), expected, "length {}", length);
+            assert!(labels.iter().all(|label| label.len() == length && is_valid_label(label) && !label.contains("--")));
+            assert_eq!(labels.iter().collect::<HashSet<_>>().len(), expected);
+        }
+
+        // 标签数超出 usize 时 size_hint 没有上界
+        assert_eq!(BruteForceGen::new(63, false).size_hint(), (usize::MAX, None));
+    }
+
+    #[test]
+    fn brute_force_enumerates_in_alphabet_order() {
+        let labels: Vec<String> = BruteForceGen::new(3, true).collect();
+        assert_eq!(&labels[..3], ["aaa", "aab", "aac"]);
+        assert_eq!(labels[26], "aba");
+        assert_eq!(labels[26 * 26], "a-a");
+        assert_eq!(labels.last().unwrap(), "z-z");
+
+        // 字母之后是数字，连字符在最后
+        let labels: Vec<String> = BruteForceGen::new(2, false).collect();
+        assert_eq!(labels[25], "az");
+        assert_eq!(labels[26], "a0");
+        assert_eq!(labels.last().unwrap(), "99");
+
+        let mut generator = BruteForceGen::new(2, true);
+        generator.nth(9);
+        assert_eq!(generator.size_hint(), (666, Some(666)));
+    }
+
+    #[test]
+    fn patterns_repeat_two_characters() {
+        let labels: Vec<String> = PatternGen::new(true).collect();
+        assert_eq!(labels.len(), 26 + 26 * 25 * 7);
+        assert_eq!(PatternGen::new(false).count(), 36 + 36 * 35 * 7);
+        assert_eq!(labels.iter().collect::<HashSet<_>>().len(), labels.len());
+
+        assert_eq!(&labels[..2], ["aaaa", "bbbb"]);
+        assert_eq!(labels[25], "zzzz");
+        assert_eq!(&labels[26..30], ["aaab", "aaba", "abaa", "baaa"]);
+        assert_eq!(&labels[30..34], ["aaac", "aaca", "acaa", "caaa"]);
+        let two_two = 26 + 26 * 25 * 4;
+        assert_eq!(&labels[two_two..two_two + 3], ["aabb", "abba", "abab"]);
+        assert_eq!(&labels[labels.len() - 3..], ["zzyy", "zyyz", "zyzy"]);
+    }
+
+    #[test]
+    fn wordlist_skips_comments_duplicates_and_invalid_labels() {
+        let input = "# places\n\nFoo\nfoo\nbar, brand;short\n-bad\nZürich\n  baz  \nfoo.li\n";
+        let labels: Vec<String> = WordlistGen::new(Cursor::new(input)).collect();
+        assert_eq!(labels, ["foo", "bar", "xn--zrich-kva", "baz"]);
+    }
+
+    #[test]
+    fn acronyms_include_hyphenated_and_stopword_free_forms() {
+        let input = "zentrale informatik dienste\n\
+                     # comment\n\
+                     Bundesamt für Informatik und Telekommunikation\n\
+                     Solo\n\
+                     Zentrale Informatik-Dienste\n";
+        let labels: Vec<String> = AcronymGen::new(Cursor::new(input)).collect();
+        assert_eq!(labels, ["zid", "z-i-d", "bfiut", "b-f-i-u-t", "bit", "b-i-t"]);
+    }
+
+    #[test]
+    fn confusables_apply_one_or_two_replacements_in_order() {
+        // 1 → l / i，0 → o
+        let variants: Vec<String> = ConfusableGen::new("10").unwrap().collect();
+        assert_eq!(variants, ["l0", "lo", "i0", "io", "1o"]);
+
+        // 西里尔字母变体转换为 A-label，名称本身不出现
+        let variants: Vec<String> = ConfusableGen::new("ab").unwrap().collect();
+        assert_eq!(variants, [normalize_label("\u{0430}b").unwrap()]);
+        assert!(variants[0].starts_with("xn--"));
+
+        assert!(ConfusableGen::new("-ab").is_err());
+    }
+
+    #[test]
+    fn tlds_are_added_to_each_label_in_turn() {
+        let labels = vec!["ab".to_string(), "cd".to_string()];
+        let tlds = vec!["li".to_string(), "ch".to_string()];
+        let mut domains = WithTlds::new(labels.into_iter(), tlds.clone());
+        assert_eq!(domains.size_hint(), (4, Some(4)));
+        assert_eq!(domains.next().as_deref(), Some("ab.li"));
+        assert_eq!(domains.size_hint(), (3, Some(3)));
+        assert_eq!(domains.collect::<Vec<_>>(), ["ab.ch", "cd.li", "cd.ch"]);
+
+        let domains = WithTlds::new(BruteForceGen::new(2, true), tlds);
+        assert_eq!(domains.size_hint(), (676 * 2, Some(676 * 2)));
+        assert_eq!(domains.count(), 676 * 2);
+        assert_eq!(WithTlds::new(BruteForceGen::new(2, true), Vec::new()).count(), 0);
+    }
+}
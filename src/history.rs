@@ -0,0 +1,305 @@
+//! 长期历史记录
+//!
+//! 使用 `--history <FILE>` 时，每次运行结束后把全部结果追加到 SQLite 数据库的 `history` 表，
+//! 每个域名每次检查一行，已有的记录不修改也不删除，追加模式合并的已写入过的旧结果被忽略。
+//! `report trends` 从中统计各次运行的状态计数、在可用和已注册之间变化过的域名，以及可用域名
+//! 首次和最近出现的时间。数据库通过编译在程序中的 SQLite(`rusqlite` 的 bundled 特性)读写，
+//! 不需要系统中安装 `sqlite3`。
+
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OpenFlags, Row, TransactionBehavior};
+use serde::Serialize;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use crate::{filter::status_name, DomainResult, DomainStatus};
+
+/// 其他进程正在写入数据库时等待的时间
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 历史数据库
+pub struct HistoryStore {
+    path: PathBuf,
+    /// 最近一次明确结果(available 或 registered)为已注册的域名，启动时一次读取，追加结果后更新
+    registered: Mutex<HashSet<String>>,
+}
+
+impl HistoryStore {
+    /// 读取已有数据库中最近一次为已注册的域名，数据库文件在第一次写入时创建
+    pub fn new(path: &Path) -> Result<Self> {
+        let registered = if path.exists() {
+            load_registered(path).with_context(|| format!("Failed to read history database {}", path.display()))?
+        } else {
+            HashSet::new()
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            registered: Mutex::new(registered),
+        })
+    }
+
+    /// 域名在以往运行中最近一次的明确结果是否为已注册
+    pub fn was_registered(&self, domain: &str) -> bool {
+        self.registered.lock().unwrap().contains(domain)
+    }
+
+    /// 在一个事务中追加全部结果，中途出错时事务回滚
+    pub fn append(&self, results: impl IntoIterator<Item = Result<DomainResult>>) -> Result<()> {
+        let mut db = Connection::open(&self.path)
+            .with_context(|| format!("Failed to open history database {}", self.path.display()))?;
+        db.busy_timeout(BUSY_TIMEOUT)?;
+        let transaction = db.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        transaction.execute_batch(
+            "CREATE TABLE IF NOT EXISTS history (
+                 domain TEXT NOT NULL,
+                 status TEXT NOT NULL,
+                 reply_code INTEGER NOT NULL,
+                 checked_at TEXT NOT NULL,
+                 run_id TEXT NOT NULL,
+                 UNIQUE (domain, run_id, checked_at)
+             );
+             CREATE INDEX IF NOT EXISTS history_run ON history (run_id);",
+        )?;
+        let mut registered = self.registered.lock().unwrap();
+        {
+            let mut insert = transaction.prepare("INSERT OR IGNORE INTO history VALUES (?1, ?2, ?3, ?4, ?5)")?;
+            for result in results {
+                let result = result?;
+                match result.status {
+                    DomainStatus::Registered => {
+                        registered.insert(result.domain.clone());
+                    }
+                    DomainStatus::Available => {
+                        registered.remove(&result.domain);
+                    }
+                    _ => {}
+                }
+                // 统一为 UTC 的固定格式，按文本排序即为时间顺序
+                let checked_at = result
+                    .timestamp
+                    .with_timezone(&chrono::Utc)
+                    .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+                    .to_string();
+                insert.execute((
+                    &result.domain,
+                    status_name(&result.status),
+                    result.reply_code,
+                    checked_at,
+                    &result.run_id,
+                ))?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+/// 一次运行中各状态的域名数
+#[derive(Debug, Serialize)]
+pub struct RunTrend {
+    pub run_id: String,
+    /// 该运行中最早的检查时间(UTC)
+    pub started_at: String,
+    pub domains: u64,
+    pub available: u64,
+    pub registered: u64,
+    pub rate_limited: u64,
+    pub errors: u64,
+}
+
+/// 在可用和已注册之间变化过的域名
+#[derive(Debug, Serialize)]
+pub struct Flapping {
+    pub domain: String,
+    /// 相邻两次明确结果不同的次数
+    pub changes: u64,
+    /// 最近一次的明确结果
+    pub status: String,
+    pub last_change: String,
+}
+
+/// 曾经可用的域名首次和最近出现的时间(UTC)
+#[derive(Debug, Serialize)]
+pub struct DomainSeen {
+    pub domain: String,
+    pub status: String,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub first_available: String,
+    pub last_available: String,
+}
+
+/// 历史数据库中的趋势
+#[derive(Debug, Serialize)]
+pub struct Trends {
+    /// 按时间排列
+    pub runs: Vec<RunTrend>,
+    /// 按变化次数从多到少排列
+    pub flapping: Vec<Flapping>,
+    /// 按最近可用时间从新到旧排列
+    pub domains: Vec<DomainSeen>,
+}
+
+/// 读取历史数据库中的趋势
+pub fn trends(path: &Path) -> Result<Trends> {
+    anyhow::ensure!(path.is_file(), "History database {} does not exist", path.display());
+    let db = open_readonly(path).with_context(|| format!("Failed to open history database {}", path.display()))?;
+
+    let runs = query(
+        &db,
+        "SELECT run_id, MIN(checked_at) AS started_at, COUNT(*) AS domains,
+             SUM(status = 'available') AS available, SUM(status = 'registered') AS registered,
+             SUM(status = 'rate_limited') AS rate_limited, SUM(status = 'error') AS errors
+         FROM history GROUP BY run_id ORDER BY started_at",
+        |row| {
+            Ok(RunTrend {
+                run_id: row.get(0)?,
+                started_at: row.get(1)?,
+                domains: row.get(2)?,
+                available: row.get(3)?,
+                registered: row.get(4)?,
+                rate_limited: row.get(5)?,
+                errors: row.get(6)?,
+            })
+        },
+    )?;
+    // 只比较明确的结果，中间出错或被限流的检查不算作变化
+    let flapping = query(
+        &db,
+        "WITH definite AS (
+             SELECT domain, status, checked_at,
+                 LAG(status) OVER (PARTITION BY domain ORDER BY checked_at) AS previous,
+                 ROW_NUMBER() OVER (PARTITION BY domain ORDER BY checked_at DESC) AS latest
+             FROM history WHERE status IN ('available', 'registered')
+         )
+         SELECT domain, SUM(previous IS NOT NULL AND previous <> status) AS changes,
+             MAX(CASE WHEN latest = 1 THEN status END) AS status,
+             MAX(CASE WHEN previous <> status THEN checked_at END) AS last_change
+         FROM definite GROUP BY domain HAVING changes > 0
+         ORDER BY changes DESC, last_change DESC, domain",
+        |row| {
+            Ok(Flapping {
+                domain: row.get(0)?,
+                changes: row.get(1)?,
+                status: row.get(2)?,
+                last_change: row.get(3)?,
+            })
+        },
+    )?;
+    let domains = query(
+        &db,
+        "WITH seen AS (
+             SELECT domain, MIN(checked_at) AS first_seen, MAX(checked_at) AS last_seen,
+                 MIN(CASE WHEN status = 'available' THEN checked_at END) AS first_available,
+                 MAX(CASE WHEN status = 'available' THEN checked_at END) AS last_available
+             FROM history GROUP BY domain
+         )
+         SELECT seen.domain,
+             (SELECT status FROM history WHERE history.domain = seen.domain
+                 AND status IN ('available', 'registered') ORDER BY checked_at DESC LIMIT 1) AS status,
+             first_seen, last_seen, first_available, last_available
+         FROM seen WHERE first_available IS NOT NULL
+         ORDER BY last_available DESC, seen.domain",
+        |row| {
+            Ok(DomainSeen {
+                domain: row.get(0)?,
+                status: row.get(1)?,
+                first_seen: row.get(2)?,
+                last_seen: row.get(3)?,
+                first_available: row.get(4)?,
+                last_available: row.get(5)?,
+            })
+        },
+    )?;
+    Ok(Trends { runs, flapping, domains })
+}
+
+/// 最近一次明确结果为已注册的域名
+fn load_registered(path: &Path) -> Result<HashSet<String>> {
+    let db = open_readonly(path)?;
+    let existing: bool = db.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'history')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !existing {
+        return Ok(HashSet::new());
+    }
+    let domains = query(
+        &db,
+        "SELECT domain FROM (
+             SELECT domain, status,
+                 ROW_NUMBER() OVER (PARTITION BY domain ORDER BY checked_at DESC) AS latest
+             FROM history WHERE status IN ('available', 'registered')
+         ) WHERE latest = 1 AND status = 'registered'",
+        |row| row.get(0),
+    )?;
+    Ok(domains.into_iter().collect())
+}
+
+/// 以只读方式打开数据库
+fn open_readonly(path: &Path) -> Result<Connection> {
+    let db = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)?;
+    db.busy_timeout(BUSY_TIMEOUT)?;
+    Ok(db)
+}
+
+/// 执行查询，每行转换为一个 T
+fn query<T>(db: &Connection, sql: &str, map: impl FnMut(&Row) -> rusqlite::Result<T>) -> Result<Vec<T>> {
+    let mut statement = db.prepare(sql)?;
+    let rows = statement.query_map([], map)?.collect::<rusqlite::Result<_>>()?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(domain: &str, status: &str, timestamp: &str, run_id: &str) -> Result<DomainResult> {
+        Ok(serde_json::from_value(serde_json::json!({
+            "domain": domain,
+            "status": status,
+            "reply_code": i32::from(status == "Available"),
+            "message": "",
+            "timestamp": timestamp,
+            "run_id": run_id,
+        }))?)
+    }
+
+    #[test]
+    fn registered_domains_are_loaded_and_trends_computed() {
+        let path = std::env::temp_dir().join(format!("history-test-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let store = HistoryStore::new(&path).unwrap();
+        store
+            .append([
+                result("ab.li", "Registered", "2024-01-01T00:00:00Z", "one"),
+                result("cd.li", "Available", "2024-01-01T00:00:01Z", "one"),
+            ])
+            .unwrap();
+        assert!(store.was_registered("ab.li"));
+
+        let store = HistoryStore::new(&path).unwrap();
+        assert!(store.was_registered("ab.li"));
+        assert!(!store.was_registered("cd.li"));
+        store
+            .append([
+                result("ab.li", "Available", "2024-01-02T00:00:00Z", "two"),
+                result("cd.li", "RateLimited", "2024-01-02T00:00:01Z", "two"),
+            ])
+            .unwrap();
+        assert!(!store.was_registered("ab.li"));
+
+        let trends = trends(&path).unwrap();
+        assert_eq!(trends.runs.len(), 2);
+        assert_eq!(trends.flapping.len(), 1);
+        assert_eq!(trends.flapping[0].domain, "ab.li");
+        assert_eq!(trends.domains.len(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,103 @@
+//! 通过系统中的 curl 发送 HTTP 请求
+//!
+//! 请求参数经标准输入以 curl 配置文件的形式传入，避免 API 密钥等出现在进程参数中。
+
+use anyhow::{Context, Result};
+use std::process::{Command, Stdio};
+use tokio::io::AsyncWriteExt;
+
+/// 系统中是否可以调用 curl
+pub fn curl_available() -> bool {
+    Command::new("curl")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// 通过标准输入传给 curl 的配置文件
+pub struct CurlConfig {
+    content: String,
+    timeout_secs: u32,
+}
+
+impl CurlConfig {
+    /// timeout_secs 为整个请求的超时时间
+    pub fn new(url: &str, timeout_secs: u32) -> Self {
+        let mut config = Self {
+            content: String::new(),
+            timeout_secs,
+        };
+        config.option("url", url);
+        config
+    }
+
+    /// 追加一行 `name = "value"`，value 为空时只写选项名
+    pub fn option(&mut self, name: &str, value: &str) {
+        if value.is_empty() {
+            self.content.push_str(name);
+        } else {
+            let escaped = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r");
+            self.content.push_str(&format!("{} = \"{}\"", name, escaped));
+        }
+        self.content.push('\n');
+    }
+
+    /// 发送请求，返回 HTTP 状态码和响应体
+    pub async fn send(self) -> Result<(u16, String)> {
+        let response = self.fetch().await?;
+        Ok((response.status, response.body))
+    }
+
+    /// 发送请求，同时返回重定向次数和最终地址(需要设置 location 选项才会跟随重定向)
+    pub async fn fetch(self) -> Result<Response> {
+        let mut child = tokio::process::Command::new("curl")
+            .args(["--silent", "--show-error", "--max-time"])
+            .arg(self.timeout_secs.to_string())
+            .args(["--write-out", "\n%{http_code} %{num_redirects} %{url_effective}", "--config", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to run curl")?;
+        let mut stdin = child.stdin.take().context("Failed to open curl stdin")?;
+        stdin.write_all(self.content.as_bytes()).await?;
+        drop(stdin);
+
+        let output = child.wait_with_output().await.context("Failed to run curl")?;
+        anyhow::ensure!(
+            output.status.success(),
+            "curl failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let (body, info) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+        let mut info = info.splitn(3, ' ');
+        let status = info
+            .next()
+            .and_then(|status| status.trim().parse().ok())
+            .context("curl returned no HTTP status")?;
+        Ok(Response {
+            status,
+            redirects: info.next().and_then(|n| n.parse().ok()).unwrap_or(0),
+            url: info.next().unwrap_or_default().trim().to_string(),
+            body: body.to_string(),
+        })
+    }
+}
+
+/// curl 返回的响应
+pub struct Response {
+    pub status: u16,
+    /// 跟随的重定向次数
+    pub redirects: u32,
+    /// 最终请求的地址
+    pub url: String,
+    pub body: String,
+}
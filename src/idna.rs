@@ -0,0 +1,69 @@
+//! IDN 标签转换
+//!
+//! 词表、流式输入和插件给出的标签可以是 Unicode 形式(U-label)，查询前转换为
+//! `xn--` 加 Punycode 的 A-label，结果中另外记录 Unicode 形式。
+//!
+//! 转换使用 `idna` crate 的 UTS #46 处理(非过渡模式、STD3 规则、检查连字符和长度)：
+//! 标签先映射为小写并做 NFC 规范化，因此 NFD 形式的输入(如 `e` 加 U+0301)与预组合字符得到
+//! 同样的 A-label；不允许的码位、双向文本和上下文规则不满足的标签被拒绝。已是 A-label 的标签
+//! 会解码后重新校验。纯 ASCII 的普通标签只转换为小写，由调用方检查标签格式。
+
+/// A-label 的前缀
+const ACE_PREFIX: &str = "xn--";
+
+/// 把标签转换为查询用的 A-label，纯 ASCII 的标签只转换为小写
+pub fn to_ascii(label: &str) -> Result<String, String> {
+    if label.is_ascii() && !label.to_ascii_lowercase().starts_with(ACE_PREFIX) {
+        return Ok(label.to_ascii_lowercase());
+    }
+    ::idna::domain_to_ascii_strict(label)
+        .ok()
+        .filter(|ascii| !ascii.is_empty() && !ascii.contains('.'))
+        .ok_or_else(|| format!("`{}` is not a valid IDN label", label))
+}
+
+/// A-label 对应的 Unicode 形式，不是合法的 A-label 时为 `None`
+pub fn to_unicode(label: &str) -> Option<String> {
+    label.strip_prefix(ACE_PREFIX)?;
+    let (unicode, result) = ::idna::domain_to_unicode(label);
+    result.ok().map(|()| unicode).filter(|unicode| unicode != label)
+}
+
+/// 域名中含 A-label 时返回其 Unicode 形式
+pub fn unicode_domain(domain: &str) -> Option<String> {
+    if !domain.split('.').any(|label| label.starts_with(ACE_PREFIX)) {
+        return None;
+    }
+    let labels: Vec<String> = domain
+        .split('.')
+        .map(|label| to_unicode(label).unwrap_or_else(|| label.to_string()))
+        .collect();
+    Some(labels.join("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unicode_labels_become_a_labels() {
+        assert_eq!(to_ascii("Zürich").unwrap(), "xn--zrich-kva");
+        assert_eq!(to_ascii("vaduz").unwrap(), "vaduz");
+        assert_eq!(to_unicode("xn--zrich-kva").as_deref(), Some("zürich"));
+        assert_eq!(unicode_domain("xn--zrich-kva.li").as_deref(), Some("zürich.li"));
+        assert_eq!(unicode_domain("vaduz.li"), None);
+    }
+
+    #[test]
+    fn decomposed_input_is_normalized() {
+        assert_eq!(to_ascii("cafe\u{0301}").unwrap(), to_ascii("caf\u{00E9}").unwrap());
+    }
+
+    #[test]
+    fn invalid_labels_are_rejected() {
+        assert!(to_ascii("ä b").is_err());
+        assert!(to_ascii("ä.b").is_err());
+        assert!(to_ascii("xn--abc-").is_err());
+        assert!(to_ascii("\u{0301}a").is_err());
+    }
+}
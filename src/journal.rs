@@ -0,0 +1,234 @@
+//! 结果日志
+//!
+//! 每条结果得出后立即以一行 JSON 追加到输出目录中的日志文件，由单独的线程批量写入并
+//! fsync。日志同时是本次运行全部结果的存放处：内存中只保留计数和可用域名，扫描结束后
+//! 从日志依次读出结果写入完整结果文件，全量扫描的数百万条结果不会同时留在内存中。
+//! 完整结果保存后删除日志。
+//!
+//! 进程在扫描途中被杀(断电、OOM)时日志保留，用 --output-dir-exact 再次指向同一输出目录
+//! 时读取残留的日志，已得出明确结果(可用或已注册)的域名不再查询。进程在写入一行的中途
+//! 退出时只会留下不完整的最后一行，读取时忽略。
+
+use anyhow::{Context, Result};
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Lines, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::JoinHandle,
+};
+
+use crate::{atomic, DomainResult, DomainStatus};
+
+pub const JOURNAL_FILE: &str = "scan_results.journal";
+
+/// 追加写入的结果日志
+pub struct Journal {
+    path: PathBuf,
+    sender: Mutex<Option<mpsc::Sender<String>>>,
+    writer: Mutex<Option<JoinHandle<Result<()>>>>,
+    /// 日志中的结果数
+    count: AtomicUsize,
+    /// 写入失败后置为 true，之后的结果不再记录
+    failed: Arc<AtomicBool>,
+}
+
+impl Journal {
+    /// 打开目录中的日志，返回日志和其中已有的明确结果(同一域名只保留最后一条)
+    ///
+    /// 残留的日志中有不完整的行、出错或被限流的结果时，只保留明确结果重写日志，
+    /// 这些域名在本次运行中重新查询。
+    pub fn open(dir: &Path) -> Result<(Self, Vec<DomainResult>)> {
+        let path = dir.join(JOURNAL_FILE);
+        let mut answered: Vec<DomainResult> = Vec::new();
+        if path.exists() {
+            let (recovered, skipped) = recover(&path)?;
+            let lines = recovered.len();
+
+            let mut index = HashMap::new();
+            for result in recovered {
+                if !matches!(result.status, DomainStatus::Available | DomainStatus::Registered) {
+                    continue;
+                }
+                match index.get(&result.domain) {
+                    Some(&position) => answered[position] = result,
+                    None => {
+                        index.insert(result.domain.clone(), answered.len());
+                        answered.push(result);
+                    }
+                }
+            }
+
+            if skipped || answered.len() < lines {
+                let mut compacted = String::new();
+                for result in &answered {
+                    compacted.push_str(&serde_json::to_string(result)?);
+                    compacted.push('\n');
+                }
+                atomic::write(&path, compacted).context("Failed to rewrite result journal")?;
+            }
+        }
+
+        let failed = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        let writer = {
+            let path = path.clone();
+            let failed = failed.clone();
+            std::thread::spawn(move || write_lines(&path, receiver, &failed))
+        };
+        let journal = Self {
+            path,
+            sender: Mutex::new(Some(sender)),
+            writer: Mutex::new(Some(writer)),
+            count: AtomicUsize::new(answered.len()),
+            failed,
+        };
+        Ok((journal, answered))
+    }
+
+    /// 追加一条结果，写入在后台进行
+    pub fn append(&self, result: &DomainResult) {
+        let Ok(line) = serde_json::to_string(result) else {
+            return;
+        };
+        if let Some(sender) = self.sender.lock().unwrap().as_ref() {
+            if sender.send(line).is_ok() {
+                self.count.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// 日志中的结果数，包括从上次中断的运行中恢复的结果
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// 是否已经无法写入日志
+    pub fn failed(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// 停止接收结果，等待已追加的结果全部写入磁盘
+    pub fn close(&self) -> Result<()> {
+        drop(self.sender.lock().unwrap().take());
+        match self.writer.lock().unwrap().take() {
+            Some(writer) => writer
+                .join()
+                .map_err(|_| anyhow::anyhow!("Result journal writer panicked"))?,
+            None => Ok(()),
+        }
+    }
+
+    /// 停止接收结果，按写入顺序依次读出日志中的全部结果
+    pub fn results(&self) -> Result<JournalReader> {
+        self.close()?;
+        let lines = match File::open(&self.path) {
+            Ok(file) => Some(BufReader::new(file).lines()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read result journal {}", self.path.display()))
+            }
+        };
+        Ok(JournalReader { lines })
+    }
+
+    /// 完整结果保存后删除日志
+    pub fn remove(&self) -> Result<()> {
+        self.close()?;
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to remove result journal {}", self.path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 逐行读取日志中的结果
+pub(crate) struct JournalReader {
+    lines: Option<Lines<BufReader<File>>>,
+}
+
+impl Iterator for JournalReader {
+    type Item = Result<DomainResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.as_mut()?.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e).context("Failed to read result journal")),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(serde_json::from_str(&line).context("Invalid line in result journal"));
+        }
+    }
+}
+
+/// 读取目录中残留的日志(不区分结果状态)，没有日志时返回空列表
+pub fn read(dir: &Path) -> Result<Vec<DomainResult>> {
+    let path = dir.join(JOURNAL_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(recover(&path)?.0)
+}
+
+/// 逐行读取日志，返回结果和是否有被忽略的行；无法解析的行(只可能是写入中断的最后一行)被忽略
+fn recover(path: &Path) -> Result<(Vec<DomainResult>, bool)> {
+    let file = File::open(path).with_context(|| format!("Failed to read result journal {}", path.display()))?;
+    let mut results = Vec::new();
+    let mut skipped = false;
+    for line in BufReader::new(file).lines() {
+        let line = match line {
+            Ok(line) => line,
+            // 中断的行可能截断在多字节字符的中间
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                skipped = true;
+                continue;
+            }
+            Err(e) => return Err(e).with_context(|| format!("Failed to read result journal {}", path.display())),
+        };
+        match serde_json::from_str(&line) {
+            Ok(result) => results.push(result),
+            Err(_) => skipped |= !line.trim().is_empty(),
+        }
+    }
+    Ok((results, skipped))
+}
+
+/// 写入线程：在第一条结果到达时创建日志，之后每次取出通道中所有待写的行，写入后 fsync 一次
+fn write_lines(path: &Path, receiver: mpsc::Receiver<String>, failed: &AtomicBool) -> Result<()> {
+    let mut file: Option<File> = None;
+    let mut error = None;
+    while let Ok(line) = receiver.recv() {
+        if error.is_some() {
+            continue;
+        }
+        let mut batch = line;
+        batch.push('\n');
+        for line in receiver.try_iter() {
+            batch.push_str(&line);
+            batch.push('\n');
+        }
+        let written = match &mut file {
+            Some(file) => Ok(file),
+            None => OpenOptions::new().create(true).append(true).open(path).map(|opened| file.insert(opened)),
+        }
+        .and_then(|file| file.write_all(batch.as_bytes()).and_then(|_| file.sync_data()));
+        if let Err(e) = written {
+            eprintln!("错误: 无法写入结果日志 {}: {}", path.display(), e);
+            failed.store(true, Ordering::Relaxed);
+            error = Some(e);
+        }
+    }
+    match error {
+        Some(e) => Err(e).with_context(|| format!("Failed to write result journal {}", path.display())),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio::time::sleep_until;
+
+use crate::rng;
+
+/// 限流后间隔的下限和上限
+const MIN_BACKOFF_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 限流响应中给出的等待时间的上限，避免异常的提示让扫描长时间停顿
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(600);
+
+/// 限流响应中引出等待时间的短语
+const RETRY_HINTS: &[&str] = &[
+    "retry-after",
+    "retry after",
+    "retry in",
+    "try again in",
+    "try again after",
+    "back off",
+    "backoff",
+    "wait",
+];
+
+/// 全局查询速率限制器
+///
+/// 所有查询(包括重试)都需先从这里领取一个时间槽。收到限流响应时间隔加倍，
+/// 之后每次正常响应逐步恢复到配置的基础间隔；响应中给出了等待时间时改为暂停到该时间之后。设置抖动比例后，每个间隔在
+/// ±jitter 范围内随机伸缩，避免固定节奏的查询模式。
+pub struct RateLimiter {
+    base: Duration,
+    jitter: f64,
+    state: Mutex<LimiterState>,
+}
+
+struct LimiterState {
+    next: Instant,
+    interval: Duration,
+}
+
+impl RateLimiter {
+    pub fn new(base: Duration, jitter: f64) -> Self {
+        Self {
+            base,
+            jitter,
+            state: Mutex::new(LimiterState {
+                next: Instant::now(),
+                interval: base,
+            }),
+        }
+    }
+
+    /// 等待下一个可用的查询时间槽
+    pub async fn wait(&self) {
+        let at = {
+            let mut state = self.state.lock().unwrap();
+            let at = state.next.max(Instant::now());
+            state.next = at + jittered(state.interval, self.jitter);
+            at
+        };
+        sleep_until(at.into()).await;
+    }
+
+    /// 收到限流响应，放慢速率
+    pub fn on_rate_limited(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.interval = (state.interval * 2).max(MIN_BACKOFF_INTERVAL).min(MAX_INTERVAL);
+    }
+
+    /// 收到给出等待时间的限流响应，在此之前不再发放时间槽，间隔保持不变
+    pub fn pause_for(&self, wait: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.next = state.next.max(Instant::now() + wait);
+    }
+
+    /// 收到正常响应，逐步恢复到基础速率
+    pub fn on_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.interval > self.base {
+            state.interval = state.interval.mul_f64(0.95).max(self.base);
+        }
+    }
+
+    /// 当前的查询间隔
+    pub fn current_interval(&self) -> Duration {
+        self.state.lock().unwrap().interval
+    }
+}
+
+/// 在 ±jitter 比例范围内随机伸缩一个时间间隔
+pub fn jittered(interval: Duration, jitter: f64) -> Duration {
+    if jitter <= 0.0 {
+        return interval;
+    }
+    interval.mul_f64(1.0 + jitter * (2.0 * rng::next_f64() - 1.0))
+}
+
+/// 从限流响应的文字中解析服务器要求的等待时间
+///
+/// 识别 `retry after 30 seconds`、`Retry-After: 10`、`try again in 2 min` 之类的写法，
+/// 不带单位的数字按秒计算，结果不超过 [`MAX_RETRY_AFTER`]。
+pub fn retry_after(message: &str) -> Option<Duration> {
+    let message = message.to_ascii_lowercase();
+    RETRY_HINTS
+        .iter()
+        .find_map(|hint| {
+            let position = message.find(hint)?;
+            leading_duration(&message[position + hint.len()..])
+        })
+        .map(|wait| wait.min(MAX_RETRY_AFTER))
+}
+
+/// 解析文字开头的 `<数字> [单位]`
+fn leading_duration(text: &str) -> Option<Duration> {
+    let text = text.trim_start_matches(|c: char| c.is_whitespace() || matches!(c, ':' | '=' | '~'));
+    let text = text.strip_prefix("for ").unwrap_or(text).trim_start();
+    let end = text.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(text.len());
+    let value: f64 = text[..end].parse().ok()?;
+    let unit: String = text[end..]
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect();
+    let scale = match unit.as_str() {
+        "" | "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+        "ms" | "msec" | "millisecond" | "milliseconds" => 0.001,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+        "h" | "hr" | "hrs" | "hour" | "hours" => 3600.0,
+        _ => return None,
+    };
+    Duration::try_from_secs_f64(value * scale)
+        .ok()
+        .filter(|wait| !wait.is_zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limit_doubles_interval_within_bounds() {
+        let limiter = RateLimiter::new(Duration::from_millis(100), 0.0);
+        limiter.on_rate_limited();
+        // 加倍后不低于限流后的下限
+        assert_eq!(limiter.current_interval(), MIN_BACKOFF_INTERVAL);
+        limiter.on_rate_limited();
+        assert_eq!(limiter.current_interval(), Duration::from_secs(1));
+        for _ in 0..10 {
+            limiter.on_rate_limited();
+        }
+        assert_eq!(limiter.current_interval(), MAX_INTERVAL);
+    }
+
+    #[test]
+    fn success_recovers_to_base_interval() {
+        let base = Duration::from_millis(400);
+        let limiter = RateLimiter::new(base, 0.0);
+        limiter.on_rate_limited();
+        limiter.on_rate_limited();
+        let slowed = limiter.current_interval();
+        assert!(slowed > base);
+        limiter.on_success();
+        let recovering = limiter.current_interval();
+        assert!(recovering < slowed && recovering > base);
+        for _ in 0..100 {
+            limiter.on_success();
+        }
+        assert_eq!(limiter.current_interval(), base);
+        // 已在基础间隔时不再减小
+        limiter.on_success();
+        assert_eq!(limiter.current_interval(), base);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pause_for_delays_next_slot_without_changing_interval() {
+        let base = Duration::from_millis(10);
+        let limiter = RateLimiter::new(base, 0.0);
+        limiter.wait().await;
+        limiter.pause_for(Duration::from_secs(5));
+        assert_eq!(limiter.current_interval(), base);
+        let next = limiter.state.lock().unwrap().next;
+        assert!(next >= Instant::now() + Duration::from_secs(4));
+    }
+
+    #[test]
+    fn jitter_stays_within_ratio() {
+        let interval = Duration::from_millis(1000);
+        assert_eq!(jittered(interval, 0.0), interval);
+        for _ in 0..100 {
+            let value = jittered(interval, 0.3);
+            assert!(value >= Duration::from_millis(700) && value <= Duration::from_millis(1300));
+        }
+    }
+
+    #[test]
+    fn retry_after_parses_common_phrasings() {
+        assert_eq!(retry_after("rate limit exceeded, retry after 30 seconds"), Some(Duration::from_secs(30)));
+        assert_eq!(retry_after("Retry-After: 10"), Some(Duration::from_secs(10)));
+        assert_eq!(retry_after("please try again in 2 min"), Some(Duration::from_secs(120)));
+        assert_eq!(retry_after("wait 500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(retry_after("retry after 2 hours"), Some(MAX_RETRY_AFTER));
+        assert_eq!(retry_after("rate limit exceeded"), None);
+        assert_eq!(retry_after("retry after 0 seconds"), None);
+        assert_eq!(retry_after("retry after soon"), None);
+    }
+}
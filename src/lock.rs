@@ -0,0 +1,97 @@
+//! 输出目录的单实例锁
+//!
+//! 在输出目录中持有一个咨询锁文件，防止定时任务和手动运行同时写入同一目录
+//! (latest 指针、旧目录清理、--append 合并)。锁由操作系统在进程退出时释放，
+//! 进程崩溃不会留下需要手动清理的锁；锁文件中记录持有者的信息，用于错误提示。
+
+use anyhow::{Context, Result};
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Seek, Write},
+    path::Path,
+};
+
+use crate::redacted_arguments;
+
+const LOCK_FILE: &str = ".li-domain-checker.lock";
+
+/// 持有期间其他运行无法获得同一目录的锁
+pub struct OutputLock {
+    _file: File,
+}
+
+impl OutputLock {
+    /// 获取目录的锁；已被其他进程持有时报错，force 为 true 时只给出警告并返回 `None`
+    pub fn acquire(dir: &Path, force: bool, started_at: &str) -> Result<Option<Self>> {
+        let path = dir.join(LOCK_FILE);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("Failed to open lock file {}", path.display()))?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(std::fs::TryLockError::WouldBlock) => {
+                let mut holder = String::new();
+                let _ = file.read_to_string(&mut holder);
+                let holder = describe(&holder);
+                if force {
+                    eprintln!(
+                        "警告: {} 正被另一个运行使用({})，因 --force 继续",
+                        dir.display(),
+                        holder
+                    );
+                    return Ok(None);
+                }
+                anyhow::bail!(
+                    "{} is in use by another li-domain-checker run ({}); wait for it to finish or pass --force",
+                    dir.display(),
+                    holder
+                );
+            }
+            Err(std::fs::TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("Failed to lock {}", path.display()))
+            }
+        }
+
+        // 与 summary.json 一样隐去 URL 中的密码和 webhook 地址
+        let command: Vec<String> = std::env::args().take(1).chain(redacted_arguments()).collect();
+        let info = format!(
+            "pid={}\nstarted_at={}\ncommand={}\n",
+            std::process::id(),
+            started_at,
+            command.join(" ")
+        );
+        file.set_len(0)?;
+        file.rewind()?;
+        file.write_all(info.as_bytes())
+            .with_context(|| format!("Failed to write lock file {}", path.display()))?;
+        Ok(Some(Self { _file: file }))
+    }
+}
+
+/// 把锁文件内容整理为一行说明
+fn describe(holder: &str) -> String {
+    let field = |name: &str| {
+        holder
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+            .map(str::trim)
+    };
+    match field("pid") {
+        Some(pid) => {
+            let mut description = format!("pid {}", pid);
+            if let Some(started_at) = field("started_at") {
+                description.push_str(&format!(", started {}", started_at));
+            }
+            if let Some(command) = field("command") {
+                description.push_str(&format!(": `{}`", command));
+            }
+            description
+        }
+        None => "unknown process".to_string(),
+    }
+}
@@ -0,0 +1,294 @@
+//! 输出目录中的 manifest.toml
+//!
+//! 每次运行开始时在输出目录写入 manifest.toml，记录程序版本和构建时的 git 提交、主机信息、
+//! 全部生效的命令行参数(包括未指定而取默认值的)、配置文件内容和合并命令行参数后各查询服务
+//! 实际使用的速率设置，以及生成候选域名的参数和输入文件，以便日后重现或理解某次运行的结果。
+//! 继续中断的扫描时按本次运行的参数重写。密码、API 密钥和 webhook 地址不写入。
+
+use anyhow::{Context, Result};
+use clap::{parser::ValueSource, ArgAction, ArgMatches, Command};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{atomic, redact_url_password, redacted_arguments, toml, SECRET_ARGUMENTS};
+
+pub const MANIFEST_FILE: &str = "manifest.toml";
+
+/// 配置文件中的凭据：所在的表、键，以及未设置时读取的环境变量
+#[cfg(not(feature = "registrar"))]
+const SECRET_KEYS: &[(&str, &str, &str)] = &[("epp", "password", crate::epp::PASSWORD_ENV)];
+#[cfg(feature = "registrar")]
+const SECRET_KEYS: &[(&str, &str, &str)] = &[
+    ("epp", "password", crate::epp::PASSWORD_ENV),
+    ("registrar", "api_key", crate::registrar::API_KEY_ENV),
+];
+
+/// 影响结果显示的环境变量
+const ENVIRONMENT: &[&str] = &["TZ", "TZDIR"];
+
+/// 写入 manifest.toml 的内容
+#[derive(Debug, Serialize)]
+pub struct Manifest {
+    run_id: String,
+    started_at: chrono::DateTime<chrono::FixedOffset>,
+    output_dir: PathBuf,
+    build: BuildInfo,
+    host: HostInfo,
+    /// 启动时的命令行参数，URL 中的密码和 webhook 地址已隐去
+    command_line: Vec<String>,
+    /// 命令行中指定的参数
+    arguments: BTreeMap<String, Value>,
+    /// 未指定、取默认值的参数
+    defaults: BTreeMap<String, Value>,
+    subcommand: Option<SubcommandInfo>,
+    environment: BTreeMap<String, String>,
+    /// 合并命令行参数和配置文件后各查询服务实际使用的设置
+    servers: Vec<ServerSettings>,
+    config: Option<ConfigInfo>,
+    generator: GeneratorInfo,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BuildInfo {
+    version: &'static str,
+    /// 构建时的 git 提交，工作区有未提交的修改时带 -dirty 后缀
+    git_commit: &'static str,
+    profile: &'static str,
+    features: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HostInfo {
+    hostname: Option<String>,
+    os: &'static str,
+    arch: &'static str,
+    kernel: Option<String>,
+    cpus: Option<usize>,
+    user: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubcommandInfo {
+    name: String,
+    arguments: BTreeMap<String, Value>,
+    defaults: BTreeMap<String, Value>,
+}
+
+/// 一个查询服务实际使用的设置
+#[derive(Debug, Serialize)]
+pub struct ServerSettings {
+    pub endpoint: String,
+    pub tlds: Vec<String>,
+    /// 查询间隔(秒)，取 delay 与 qps 换算出的间隔中较大者
+    pub interval: f64,
+    pub jitter: f64,
+    pub concurrency: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConfigInfo {
+    path: PathBuf,
+    /// 配置文件的内容，凭据已隐去
+    contents: Value,
+}
+
+/// 生成候选域名的参数
+#[derive(Debug, Serialize)]
+pub struct GeneratorInfo {
+    pub tlds: Vec<String>,
+    pub full_scan: bool,
+    pub letters_only: bool,
+    pub order: String,
+    pub include_reserved: bool,
+    pub min_vowels: usize,
+    pub no_double_letters: bool,
+    pub alternating_cv: bool,
+    pub confusables: Vec<String>,
+    pub wordpacks: Vec<String>,
+    pub wordlists: Vec<InputFile>,
+    pub acronyms: Vec<InputFile>,
+    /// --rescan 的结果目录和 --rescan-where 的条件
+    pub rescan: Option<PathBuf>,
+    pub rescan_where: Option<String>,
+}
+
+/// 输入文件及其大小和修改时间，用于判断文件在运行之后是否改变过
+#[derive(Debug, Serialize)]
+pub struct InputFile {
+    path: PathBuf,
+    bytes: u64,
+    lines: usize,
+    modified: Option<chrono::DateTime<chrono::Local>>,
+}
+
+impl InputFile {
+    pub fn describe(path: &Path) -> Result<Self> {
+        let content = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let modified = fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+        Ok(Self {
+            path: path.to_path_buf(),
+            bytes: content.len() as u64,
+            lines: content.split(|&byte| byte == b'\n').filter(|line| !line.is_empty()).count(),
+            modified: modified.map(chrono::DateTime::from),
+        })
+    }
+}
+
+impl BuildInfo {
+    fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            git_commit: option_env!("LDC_GIT_COMMIT").unwrap_or("unknown"),
+            profile: if cfg!(debug_assertions) { "debug" } else { "release" },
+            features: [cfg!(feature = "registrar").then_some("registrar")]
+                .into_iter()
+                .flatten()
+                .collect(),
+        }
+    }
+}
+
+impl HostInfo {
+    fn current() -> Self {
+        let read = |path: &str| {
+            fs::read_to_string(path)
+                .ok()
+                .map(|text| text.trim().to_string())
+                .filter(|text| !text.is_empty())
+        };
+        Self {
+            hostname: read("/proc/sys/kernel/hostname")
+                .or_else(|| read("/etc/hostname"))
+                .or_else(|| std::env::var("HOSTNAME").ok()),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            kernel: read("/proc/sys/kernel/osrelease"),
+            cpus: std::thread::available_parallelism().ok().map(usize::from),
+            user: std::env::var("USER").or_else(|_| std::env::var("USERNAME")).ok(),
+        }
+    }
+}
+
+impl ConfigInfo {
+    /// 读取配置文件，隐去凭据；凭据未写在文件中而由环境变量提供时注明来源
+    fn load(path: &Path) -> Result<Self> {
+        let mut contents: Value = toml::from_file(path)?;
+        for (table, key, env) in SECRET_KEYS {
+            let Some(Value::Object(table)) = contents.get_mut(*table) else {
+                continue;
+            };
+            if table.contains_key(*key) {
+                table.insert(key.to_string(), Value::from("<redacted>"));
+            } else if std::env::var_os(env).is_some() {
+                table.insert(key.to_string(), Value::from(format!("<redacted, from {}>", env)));
+            }
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            contents,
+        })
+    }
+}
+
+impl Manifest {
+    /// 按 clap 的定义重新解析本次运行的命令行，分出指定的参数和取默认值的参数
+    pub fn new(
+        run_id: &str,
+        started_at: chrono::DateTime<chrono::FixedOffset>,
+        output_dir: &Path,
+        mut command: Command,
+        servers: Vec<ServerSettings>,
+        config: Option<&Path>,
+        generator: GeneratorInfo,
+    ) -> Result<Self> {
+        command.build();
+        // 单元测试进程的命令行是测试框架的参数，按不带参数的命令记录
+        let argv: Vec<std::ffi::OsString> = if cfg!(test) {
+            vec![command.get_name().into()]
+        } else {
+            std::env::args_os().collect()
+        };
+        let matches = command
+            .clone()
+            .try_get_matches_from(argv)
+            .context("Failed to parse arguments for manifest")?;
+        let (arguments, defaults) = effective_arguments(&command, &matches, true);
+        let subcommand = matches.subcommand().and_then(|(name, sub_matches)| {
+            let (arguments, defaults) = effective_arguments(command.find_subcommand(name)?, sub_matches, false);
+            Some(SubcommandInfo {
+                name: name.to_string(),
+                arguments,
+                defaults,
+            })
+        });
+        Ok(Self {
+            run_id: run_id.to_string(),
+            started_at,
+            output_dir: output_dir.to_path_buf(),
+            build: BuildInfo::current(),
+            host: HostInfo::current(),
+            command_line: redacted_arguments(),
+            arguments,
+            defaults,
+            subcommand,
+            environment: ENVIRONMENT
+                .iter()
+                .filter_map(|name| Some((name.to_string(), std::env::var(name).ok()?)))
+                .collect(),
+            servers,
+            config: config.map(ConfigInfo::load).transpose()?,
+            generator,
+        })
+    }
+
+    /// 写入输出目录
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        let value = serde_json::to_value(self).context("Failed to serialize manifest")?;
+        let content = format!("# li-domain-checker 运行清单，由程序生成\n{}", toml::to_string(&value));
+        let path = dir.join(MANIFEST_FILE);
+        atomic::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// 一个命令(主命令或子命令)生效的参数：命令行中指定的和取默认值的，global 为 false 时
+/// 跳过从主命令继承的全局参数
+fn effective_arguments(command: &Command, matches: &ArgMatches, global: bool) -> (BTreeMap<String, Value>, BTreeMap<String, Value>) {
+    let (mut given, mut defaults) = (BTreeMap::new(), BTreeMap::new());
+    for arg in command.get_arguments() {
+        if matches!(arg.get_action(), ArgAction::Help | ArgAction::Version) || (!global && arg.is_global_set()) {
+            continue;
+        }
+        let id = arg.get_id().as_str();
+        let Some(raw) = matches.get_raw(id) else {
+            continue;
+        };
+        let secret = arg
+            .get_long()
+            .is_some_and(|long| SECRET_ARGUMENTS.contains(&format!("--{}", long).as_str()));
+        let values: Vec<Value> = raw
+            .map(|value| match arg.get_action() {
+                _ if secret => Value::from("<redacted>"),
+                ArgAction::SetTrue | ArgAction::SetFalse => Value::from(value == "true"),
+                _ => Value::from(redact_url_password(&value.to_string_lossy())),
+            })
+            .collect();
+        let multiple = matches!(arg.get_action(), ArgAction::Append)
+            || arg.get_value_delimiter().is_some()
+            || arg.get_num_args().is_some_and(|range| range.max_values() > 1);
+        let value = match (multiple, values.len()) {
+            (false, 1) => values.into_iter().next().expect("one value"),
+            _ => Value::Array(values),
+        };
+        let key = id.replace('_', "-");
+        match matches.value_source(id) {
+            Some(ValueSource::DefaultValue) => defaults.insert(key, value),
+            _ => given.insert(key, value),
+        };
+    }
+    (given, defaults)
+}
@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use clap::Args as ClapArgs;
+use console::style;
+use serde::Deserialize;
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{
+    client::{MockWhoisClient, RateLimitBurst, WhoisClient},
+    toml, HEALTH_CHECK_DOMAIN,
+};
+
+/// mock-server 子命令参数
+#[derive(ClapArgs, Debug)]
+pub struct MockServerArgs {
+    /// 监听地址
+    #[arg(long, default_value = "127.0.0.1:4343")]
+    listen: String,
+
+    /// 响应脚本文件(TOML)，不指定时所有域名均返回已注册
+    #[arg(long)]
+    fixture: Option<PathBuf>,
+}
+
+/// 响应脚本
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Fixture {
+    /// 未预设响应的域名返回的响应
+    default: String,
+    /// 未预设响应的域名随机返回可用的比例
+    available_ratio: f64,
+    /// 每次响应前的固定延迟和随机附加延迟(毫秒)
+    latency_ms: u64,
+    latency_jitter_ms: u64,
+    /// 是否支持在同一连接上多次查询
+    keep_alive: bool,
+    /// 按域名预设的响应
+    replies: HashMap<String, String>,
+    /// 模拟的限流区间
+    rate_limit_bursts: Vec<RateLimitBurst>,
+}
+
+impl Default for Fixture {
+    fn default() -> Self {
+        Self {
+            default: "0: registered".to_string(),
+            available_ratio: 0.0,
+            latency_ms: 0,
+            latency_jitter_ms: 0,
+            keep_alive: true,
+            replies: HashMap::new(),
+            rate_limit_bursts: Vec::new(),
+        }
+    }
+}
+
+impl Fixture {
+    /// 预检域名未预设响应时固定返回已注册，避免被随机可用比例命中
+    fn into_client(mut self) -> MockWhoisClient {
+        if !self.replies.keys().any(|domain| normalize(domain) == HEALTH_CHECK_DOMAIN) {
+            self.replies
+                .insert(HEALTH_CHECK_DOMAIN.to_string(), "0: registered".to_string());
+        }
+        let mut client = MockWhoisClient::new(self.default)
+            .with_available_ratio(self.available_ratio)
+            .with_latency(
+                Duration::from_millis(self.latency_ms),
+                Duration::from_millis(self.latency_jitter_ms),
+            );
+        for (domain, reply) in self.replies {
+            client = client.with_reply(normalize(&domain), reply);
+        }
+        for burst in self.rate_limit_bursts {
+            client = client.with_burst(burst);
+        }
+        client
+    }
+}
+
+/// 查询行和预设的域名统一为小写
+fn normalize(domain: &str) -> String {
+    domain.trim().to_ascii_lowercase()
+}
+
+/// 运行模拟查询服务，直到收到 Ctrl-C
+pub async fn run(args: MockServerArgs) -> Result<()> {
+    let fixture: Fixture = match &args.fixture {
+        Some(path) => toml::from_file(path)?,
+        None => Fixture::default(),
+    };
+    anyhow::ensure!(
+        (0.0..=1.0).contains(&fixture.available_ratio),
+        "available_ratio must be in the range [0, 1]"
+    );
+    let keep_alive = fixture.keep_alive;
+    let client = Arc::new(fixture.into_client());
+
+    let listener = TcpListener::bind(&args.listen)
+        .await
+        .with_context(|| format!("Failed to listen on {}", args.listen))?;
+    println!(
+        "{}",
+        style(format!("模拟查询服务已监听 {}，按 Ctrl-C 停止", listener.local_addr()?)).bold()
+    );
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, _) = accepted.context("Failed to accept connection")?;
+                tokio::spawn(serve(stream, client.clone(), keep_alive));
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    println!("\n共应答 {} 次查询", client.queries());
+    Ok(())
+}
+
+/// 按行读取查询并逐条应答，不支持复用时应答一次后关闭连接
+async fn serve(stream: TcpStream, client: Arc<MockWhoisClient>, keep_alive: bool) {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match reader.read_line(&mut line).await {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+        let domain = normalize(&line);
+        if domain.is_empty() {
+            continue;
+        }
+
+        let Ok(Some(reply)) = client.query(&mut (), &domain).await else {
+            return;
+        };
+        let reply = format!("{}\n", reply.trim_end());
+        if reader.get_mut().write_all(reply.as_bytes()).await.is_err() || !keep_alive {
+            return;
+        }
+    }
+}
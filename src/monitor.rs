@@ -0,0 +1,453 @@
+//! 品牌监测
+//!
+//! `monitor-brand <NAME>` 按固定间隔反复查询名称的拼写错误变体([`TyposquatGen`])和近形变体
+//! ([`ConfusableGen`])，各变体的状态保存在状态文件中。某个变体的状态与上一轮相比发生变化时
+//! (有人注册了新的仿冒域名，或已注册的变体被释放)发出提醒：打印到终端，并可 POST 到
+//! webhook 或通过 sendmail 发送邮件。第一次运行只建立基线，不发出提醒；查询出错或被限流的
+//! 变体沿用之前的状态。`--notify-if` 只对满足条件(语法与 query 子命令相同)的变化发出提醒，
+//! 不满足条件的变化照常记入状态文件。
+//!
+//! 收到 SIGTERM 或 Ctrl-C 时停止，已得出的结果照常保存到输出目录。
+
+use anyhow::{Context, Result};
+use clap::Args as ClapArgs;
+use console::style;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    atomic,
+    client::WhoisClient,
+    filter::{status_name, Query},
+    generate::{ConfusableGen, TyposquatGen, WithTlds},
+    http, systemd, DomainResult, DomainStatus, ExitCondition, HeldConnection, LiDomainScanner,
+};
+
+/// monitor-brand 子命令参数
+#[derive(ClapArgs, Debug)]
+pub struct MonitorArgs {
+    /// 要监测的名称(不含 TLD)，在 --tlds 的每个 TLD 下查询其变体
+    name: String,
+
+    /// 两轮查询之间的间隔，如 30m、24h、7d
+    #[arg(long, value_parser = parse_interval, default_value = "24h")]
+    interval: Duration,
+
+    /// 状态文件(默认为 --output 目录下的 monitor_<名称>.json)
+    #[arg(long, value_name = "FILE")]
+    state: Option<PathBuf>,
+
+    /// 状态变化时把提醒以 JSON POST 到该地址(需要 curl)
+    #[arg(long, value_name = "URL")]
+    webhook: Option<String>,
+
+    /// 状态变化时通过 sendmail 把提醒发送到该邮箱，可重复指定
+    #[arg(long, value_name = "ADDRESS")]
+    email: Vec<String>,
+
+    /// 只对查询结果满足条件的状态变化发出提醒，语法与 query 子命令相同(不带 ORDER BY 和 LIMIT)
+    #[arg(long, value_name = "CONDITION")]
+    notify_if: Option<String>,
+
+    /// 只查询一轮就退出，适合由 cron 或 systemd timer 定期运行
+    #[arg(long)]
+    once: bool,
+}
+
+/// 解析 `30s`、`15m`、`24h`、`7d` 形式的间隔，不带单位时为秒
+pub(crate) fn parse_interval(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit() && c != '.') {
+        Some(position) => text.split_at(position),
+        None => (text, "s"),
+    };
+    let number: f64 = number.parse().map_err(|_| format!("invalid interval `{}`", text))?;
+    let seconds = match unit {
+        "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        "d" => 86400.0,
+        _ => return Err(format!("unknown interval unit `{}`, use s, m, h or d", unit)),
+    };
+    if number <= 0.0 || !number.is_finite() {
+        return Err("interval must be positive".to_string());
+    }
+    Ok(Duration::from_secs_f64(number * seconds))
+}
+
+/// 变体的来源
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum VariantKind {
+    Typo,
+    Confusable,
+}
+
+impl VariantKind {
+    fn display(self) -> &'static str {
+        match self {
+            Self::Typo => "拼写错误",
+            Self::Confusable => "近形",
+        }
+    }
+}
+
+/// 状态文件的内容
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct MonitorState {
+    name: String,
+    /// 最近一轮查询完成的时间
+    #[serde(default)]
+    updated: Option<chrono::DateTime<chrono::FixedOffset>>,
+    #[serde(default)]
+    domains: BTreeMap<String, VariantState>,
+}
+
+/// 一个变体最近一次得出的状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VariantState {
+    kind: VariantKind,
+    status: DomainStatus,
+    /// 开始处于该状态的时间，首次查询时为首次查询的时间
+    since: chrono::DateTime<chrono::FixedOffset>,
+}
+
+impl MonitorState {
+    fn load(path: &Path, name: &str) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self {
+                name: name.to_string(),
+                ..Self::default()
+            });
+        }
+        let content =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let state: Self =
+            serde_json::from_str(&content).with_context(|| format!("Invalid monitor state {}", path.display()))?;
+        anyhow::ensure!(
+            state.name == name,
+            "{} is the state of `{}`, not `{}`; use --state to choose another file",
+            path.display(),
+            state.name,
+            name
+        );
+        Ok(state)
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).context("Failed to serialize monitor state")?;
+        atomic::write(path, content).context("Failed to write monitor state")
+    }
+}
+
+/// 一个变体在可用和已注册之间的变化
+#[derive(Debug, Serialize)]
+struct Change {
+    domain: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unicode: Option<String>,
+    kind: VariantKind,
+    from: &'static str,
+    to: &'static str,
+}
+
+impl Change {
+    fn display(&self) -> String {
+        let action = match self.to {
+            "registered" => "新注册",
+            _ => "已释放",
+        };
+        let mut line = format!("{}: {}", action, self.domain);
+        if let Some(unicode) = &self.unicode {
+            line.push_str(&format!(" [{}]", unicode));
+        }
+        line.push_str(&format!(" ({})", self.kind.display()));
+        line
+    }
+}
+
+impl<C: WhoisClient> LiDomainScanner<C> {
+    /// 运行品牌监测，直到收到终止信号(或使用 --once 时查询一轮后)保存结果退出
+    pub(crate) async fn monitor_brand(&self, args: MonitorArgs, skip_health_check: bool) -> Result<()> {
+        // 同一域名既是拼写错误又是近形变体时记为拼写错误
+        let mut variants: BTreeMap<String, VariantKind> = BTreeMap::new();
+        let typos = WithTlds::new(TyposquatGen::new(&args.name)?, self.tlds.clone());
+        let confusables = WithTlds::new(ConfusableGen::new(&args.name)?, self.tlds.clone());
+        for (domain, kind) in typos
+            .map(|domain| (domain, VariantKind::Typo))
+            .chain(confusables.map(|domain| (domain, VariantKind::Confusable)))
+        {
+            if self.rules.allows(&domain) {
+                variants.entry(domain).or_insert(kind);
+            }
+        }
+        anyhow::ensure!(!variants.is_empty(), "`{}` has no variants allowed under --tlds", args.name);
+
+        let state_path = match &args.state {
+            Some(path) => path.clone(),
+            None => self
+                .output_root
+                .as_deref()
+                .unwrap_or(&self.output_dir)
+                .join(format!("monitor_{}.json", args.name.to_lowercase())),
+        };
+        let mut state = MonitorState::load(&state_path, &args.name)?;
+        let notify_if = args
+            .notify_if
+            .as_deref()
+            .map(Query::parse_condition)
+            .transpose()
+            .context("Invalid --notify-if expression")?;
+        if args.webhook.is_some() && !http::curl_available() {
+            anyhow::bail!("--webhook requires curl, which was not found");
+        }
+
+        if !skip_health_check {
+            for server in self.servers.iter() {
+                println!("预检查询服务 {}...", server.client.endpoint());
+                self.health_check(server).await?;
+            }
+        }
+        println!(
+            "监测 {} 的 {} 个变体，每 {} 查询一次，状态保存在 {}",
+            args.name,
+            variants.len(),
+            display_interval(args.interval),
+            state_path.display()
+        );
+
+        // 以 Type=notify 运行时，预检和列出监视范围之后才算启动完成
+        systemd::notify("READY=1");
+        let watchdog = systemd::spawn_watchdog();
+        let shutdown = systemd::shutdown_signal();
+        tokio::pin!(shutdown);
+        loop {
+            let domains: Vec<String> = variants.keys().cloned().collect();
+            let results = tokio::select! {
+                results = self.check_domains(domains) => results,
+                _ = &mut shutdown => {
+                    self.set_exit(ExitCondition::Interrupted);
+                    break;
+                }
+            };
+            let changes = self.update_state(&mut state, &variants, results, notify_if.as_ref());
+            state.save(&state_path)?;
+            if !changes.is_empty() {
+                self.alert(&args, &changes).await;
+            }
+            if args.once {
+                break;
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(args.interval) => {}
+                _ = &mut shutdown => {
+                    self.set_exit(ExitCondition::Interrupted);
+                    break;
+                }
+            }
+        }
+
+        systemd::notify("STOPPING=1");
+        if let Some(watchdog) = watchdog {
+            watchdog.abort();
+        }
+        self.save_results().await?;
+        println!("结果已保存到: {} (运行 ID: {})", self.output_dir.display(), self.run_id);
+        Ok(())
+    }
+
+    /// 用 --workers 个并发任务查询全部域名，结果同时写入结果日志
+    pub(crate) async fn check_domains(&self, domains: Vec<String>) -> Vec<DomainResult> {
+        let queue = Arc::new(Mutex::new(domains.into_iter()));
+        let mut handles = Vec::with_capacity(self.workers);
+        for worker in 0..self.workers {
+            let queue = queue.clone();
+            let scanner = self.clone();
+            handles.push(tokio::spawn(async move {
+                let mut conns: Vec<HeldConnection<C>> = scanner.servers.iter().map(|_| None).collect();
+                let mut results = Vec::new();
+                loop {
+                    let Some(domain) = queue.lock().unwrap().next() else {
+                        break;
+                    };
+                    let result = scanner.check_with_retry(worker, &domain, &mut conns).await;
+                    scanner.journal.append(&result);
+                    scanner.record_postgres(&result);
+                    results.push(result);
+                }
+                results
+            }));
+        }
+        let mut results = Vec::new();
+        for handle in futures::future::join_all(handles).await {
+            match handle {
+                Ok(worker_results) => results.extend(worker_results),
+                Err(e) => eprintln!("警告: 查询任务异常退出: {}", e),
+            }
+        }
+        results
+    }
+
+    /// 用本轮结果更新状态，返回可用和已注册之间的变化；新出现的变体只记录不算作变化
+    fn update_state(
+        &self,
+        state: &mut MonitorState,
+        variants: &BTreeMap<String, VariantKind>,
+        results: Vec<DomainResult>,
+        notify_if: Option<&Query>,
+    ) -> Vec<Change> {
+        let baseline = state.updated.is_none();
+        let mut changes = Vec::new();
+        let (mut registered, mut errors, mut suppressed) = (0, 0, 0);
+        for result in results {
+            if !matches!(result.status, DomainStatus::Available | DomainStatus::Registered) {
+                errors += 1;
+                continue;
+            }
+            if result.status == DomainStatus::Registered {
+                registered += 1;
+            }
+            let kind = variants.get(&result.domain).copied().unwrap_or(VariantKind::Typo);
+            match state.domains.get_mut(&result.domain) {
+                Some(previous) if previous.status != result.status => {
+                    if notify_if.is_none_or(|query| query.matches(&result)) {
+                        changes.push(Change {
+                            domain: result.domain.clone(),
+                            unicode: result.unicode.clone(),
+                            kind,
+                            from: status_name(&previous.status),
+                            to: status_name(&result.status),
+                        });
+                    } else {
+                        suppressed += 1;
+                    }
+                    previous.status = result.status;
+                    previous.since = result.timestamp;
+                }
+                Some(_) => {}
+                None => {
+                    state.domains.insert(
+                        result.domain.clone(),
+                        VariantState {
+                            kind,
+                            status: result.status,
+                            since: result.timestamp,
+                        },
+                    );
+                }
+            }
+        }
+        // 不再生成的变体(如 --tlds 或标签规则改变后)从状态中移除
+        state.domains.retain(|domain, _| variants.contains_key(domain));
+        state.updated = Some(self.clock.now());
+
+        let mut summary = format!(
+            "[{}] {} 个变体中 {} 个已注册",
+            self.clock.display(&self.clock.now()),
+            variants.len(),
+            registered
+        );
+        if errors > 0 {
+            summary.push_str(&format!("，{} 个查询失败(沿用之前的状态)", errors));
+        }
+        if baseline {
+            summary.push_str("，已建立基线");
+        } else {
+            summary.push_str(&format!("，{} 个状态变化", changes.len() + suppressed));
+            if suppressed > 0 {
+                summary.push_str(&format!("(其中 {} 个不满足 --notify-if，未提醒)", suppressed));
+            }
+        }
+        println!("{}", summary);
+        changes
+    }
+
+    /// 打印变化并发送到 webhook 和邮箱，发送失败只给出提示
+    async fn alert(&self, args: &MonitorArgs, changes: &[Change]) {
+        for change in changes {
+            let line = change.display();
+            match change.to {
+                "registered" => println!("{}", style(format!("⚠ {}", line)).red().bold()),
+                _ => println!("{}", style(format!("✓ {}", line)).green()),
+            }
+        }
+
+        if let Some(url) = &args.webhook {
+            let body = json!({
+                "brand": args.name,
+                "run_id": self.run_id,
+                "timestamp": self.clock.now().to_rfc3339(),
+                "changes": changes,
+            });
+            let mut request = http::CurlConfig::new(url, 30);
+            request.option("request", "POST");
+            request.option("header", "Content-Type: application/json");
+            request.option("data-binary", &body.to_string());
+            match request.send().await {
+                Ok((status, _)) if (200..300).contains(&status) => {}
+                Ok((status, _)) => eprintln!("警告: webhook 返回 HTTP {}", status),
+                Err(e) => eprintln!("警告: 发送 webhook 失败: {:#}", e),
+            }
+        }
+
+        if !args.email.is_empty() {
+            let mut text = format!("{} 的变体状态发生变化:\n\n", args.name);
+            for change in changes {
+                text.push_str(&change.display());
+                text.push('\n');
+            }
+            if let Err(e) = send_mail(&args.email, &args.name, changes.len(), &text).await {
+                eprintln!("警告: 发送提醒邮件失败: {:#}", e);
+            }
+        }
+    }
+}
+
+/// 通过系统中的 sendmail 发送纯文本邮件
+async fn send_mail(recipients: &[String], name: &str, changes: usize, text: &str) -> Result<()> {
+    let message = format!(
+        "To: {}\nSubject: [li-domain-checker] {} lookalike change(s) for {}\nMIME-Version: 1.0\n\
+         Content-Type: text/plain; charset=utf-8\nContent-Transfer-Encoding: 8bit\n\n{}",
+        recipients.join(", "),
+        changes,
+        name,
+        text
+    );
+    let mut child = tokio::process::Command::new("sendmail")
+        .arg("-t")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("Failed to run sendmail")?;
+    let mut stdin = child.stdin.take().context("Failed to open sendmail stdin")?;
+    stdin.write_all(message.as_bytes()).await?;
+    drop(stdin);
+    let output = child.wait_with_output().await.context("Failed to run sendmail")?;
+    anyhow::ensure!(
+        output.status.success(),
+        "sendmail failed: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(())
+}
+
+pub(crate) fn display_interval(interval: Duration) -> String {
+    let seconds = interval.as_secs();
+    match seconds {
+        s if s >= 86400 && s % 86400 == 0 => format!("{} 天", s / 86400),
+        s if s >= 3600 && s % 3600 == 0 => format!("{} 小时", s / 3600),
+        s if s >= 60 && s % 60 == 0 => format!("{} 分钟", s / 60),
+        s => format!("{} 秒", s),
+    }
+}
+
@@ -0,0 +1,166 @@
+//! 扫描结束时的汇总表
+//!
+//! 保存完整结果时统计结果文件中的各状态域名数、各标签长度的可用率和评分最高的可用域名，
+//! 扫描结束后与耗时、实际查询速率等运行统计一起以表格打印。使用 `--summary-only` 时扫描
+//! 期间不逐条打印结果，只看这里的汇总。`report export` 的概览工作表使用同样的统计。
+
+use console::{measure_text_width, pad_str, style, Alignment};
+use std::collections::BTreeMap;
+
+use crate::{progress::StatusCounts, score, stats::StatsSummary, DomainResult, DomainStatus};
+
+/// 列出的评分最高的可用域名数
+const TOP_AVAILABLE: usize = 10;
+
+/// 完整结果的汇总
+#[derive(Debug, Default)]
+pub struct Overview {
+    counts: StatusCounts,
+    /// 各标签长度(字符数)已得出结果的域名数和其中可用的域名数
+    lengths: BTreeMap<usize, (u64, u64)>,
+    /// 可用域名及其评分，只保留评分最高的一部分
+    top: Vec<(u32, String)>,
+}
+
+impl Overview {
+    pub fn add(&mut self, result: &DomainResult) {
+        self.counts.add(&result.status);
+        let available = result.status == DomainStatus::Available;
+        if available || result.status == DomainStatus::Registered {
+            // IDN 按 Unicode 形式计算长度
+            let name = result.unicode.as_deref().unwrap_or(&result.domain);
+            let length = name.split('.').next().unwrap_or(name).chars().count();
+            let entry = self.lengths.entry(length).or_default();
+            entry.0 += 1;
+            entry.1 += available as u64;
+        }
+        if available {
+            self.top.push((score::score(&result.domain), result.domain.clone()));
+            if self.top.len() >= TOP_AVAILABLE * 4 {
+                self.trim_top();
+            }
+        }
+    }
+
+    fn trim_top(&mut self) {
+        self.top.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        self.top.truncate(TOP_AVAILABLE);
+    }
+
+    pub fn counts(&self) -> &StatusCounts {
+        &self.counts
+    }
+
+    pub fn lengths(&self) -> &BTreeMap<usize, (u64, u64)> {
+        &self.lengths
+    }
+
+    /// 打印结果、按长度的可用率、评分最高的可用域名和运行统计
+    pub fn print(mut self, stats: &StatsSummary, started: &str, finished: &str) {
+        let total = self.counts.total();
+        let mut table = Table::new(&["状态", "域名数", "占比"], Alignment::Right);
+        for (name, count) in self.counts.entries() {
+            table.row(vec![name.to_string(), count.to_string(), percent(count, total)]);
+        }
+        table.row(vec!["合计".to_string(), total.to_string(), String::new()]);
+        table.print("结果");
+
+        if !self.lengths.is_empty() {
+            let mut table = Table::new(&["长度", "已得出结果", "可用", "可用率"], Alignment::Right);
+            for (length, (checked, available)) in &self.lengths {
+                table.row(vec![
+                    length.to_string(),
+                    checked.to_string(),
+                    available.to_string(),
+                    percent(*available, *checked),
+                ]);
+            }
+            table.print("按标签长度");
+        }
+
+        if !self.top.is_empty() {
+            self.trim_top();
+            let mut table = Table::new(&["域名", "评分"], Alignment::Right);
+            for (score, domain) in &self.top {
+                table.row(vec![domain.clone(), score.to_string()]);
+            }
+            table.print("评分最高的可用域名");
+        }
+
+        let mut table = Table::new(&["项目", "值"], Alignment::Left);
+        table.row(vec!["开始".to_string(), started.to_string()]);
+        table.row(vec!["结束".to_string(), finished.to_string()]);
+        for (name, value) in stats.rows() {
+            table.row(vec![name.to_string(), value]);
+        }
+        table.print("运行统计");
+        stats.print_troubled_workers();
+    }
+}
+
+fn percent(part: u64, whole: u64) -> String {
+    match whole {
+        0 => "-".to_string(),
+        _ => format!("{:.1}%", part as f64 * 100.0 / whole as f64),
+    }
+}
+
+/// 带边框的表格，按显示宽度对齐中文
+struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    /// 第一列之外各列的对齐方式，第一列总是左对齐
+    alignment: Alignment,
+}
+
+impl Table {
+    fn new(headers: &[&str], alignment: Alignment) -> Self {
+        Self {
+            headers: headers.iter().map(|header| header.to_string()).collect(),
+            rows: Vec::new(),
+            alignment,
+        }
+    }
+
+    fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    fn print(&self, title: &str) {
+        let widths: Vec<usize> = (0..self.headers.len())
+            .map(|column| {
+                std::iter::once(&self.headers)
+                    .chain(&self.rows)
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| measure_text_width(cell))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let border = |left: &str, middle: &str, right: &str| {
+            let segments: Vec<String> = widths.iter().map(|width| "─".repeat(width + 2)).collect();
+            format!("{}{}{}", left, segments.join(middle), right)
+        };
+        let line = |cells: &[String]| {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(column, &width)| {
+                    let cell = cells.get(column).map_or("", String::as_str);
+                    let alignment = if column == 0 { Alignment::Left } else { self.alignment };
+                    format!(" {} ", pad_str(cell, width, alignment, None))
+                })
+                .collect();
+            format!("│{}│", cells.join("│"))
+        };
+
+        println!("\n{}", style(title).bold());
+        println!("{}", border("┌", "┬", "┐"));
+        println!("{}", style(line(&self.headers)).bold());
+        println!("{}", border("├", "┼", "┤"));
+        for row in &self.rows {
+            println!("{}", line(row));
+        }
+        println!("{}", border("└", "┴", "┘"));
+    }
+}
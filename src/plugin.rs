@@ -0,0 +1,273 @@
+//! WASM 插件
+//!
+//! 插件是编译为 WASI 命令模块的 `.wasm` 文件，由系统中的 WASM 运行时(默认
+//! `wasmtime run`)执行，通过标准输入输出逐行交换 JSON 消息，可以用任何能编译到
+//! wasm32-wasip1 的语言编写，不需要修改本程序。主机每次发送一行 `{"call": ...}`
+//! 请求，插件回复一行：
+//!
+//! - `init`: 回复 `{"exports": ["generate", "filter", "on_result"]}`，列出实现的函数
+//! - `generate`: 每个候选标签回复一行 `{"label": "..."}`，最后回复 `{"done": true}`
+//! - `filter`: 请求带 `domain`，回复 `{"keep": true}` 或 `{"keep": false}`
+//! - `on_result`: 请求带 `result`(格式与结果文件相同)，回复 `{}`，可带 `message`
+//!   在终端中显示
+//!
+//! 出错时回复 `{"error": "..."}`。`generate` 在单独的运行时进程中进行，不与扫描途中
+//! 的 `filter`/`on_result` 调用交错；插件出错或在 `--plugin-timeout` 内没有回复时(运行时
+//! 进程被终止)给出一次提示，之后不再调用该插件。调用会阻塞，扫描只在阻塞线程中调用插件。
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{generate, DomainResult};
+
+/// 默认的 WASM 运行时命令
+pub const DEFAULT_RUNTIME: &str = "wasmtime run";
+
+/// 已加载的插件
+pub struct Plugin {
+    name: String,
+    /// 运行时命令和插件路径
+    command: Vec<String>,
+    /// 等待每行回复的时间
+    timeout: Duration,
+    exports: Vec<String>,
+    /// `filter` 和 `on_result` 使用的运行时进程
+    session: Mutex<Session>,
+    /// 出错后置为 true，之后不再调用
+    failed: AtomicBool,
+}
+
+/// 插件的一行回复
+#[derive(Debug, Default, Deserialize)]
+struct Reply {
+    #[serde(default)]
+    exports: Vec<String>,
+    label: Option<String>,
+    #[serde(default)]
+    done: bool,
+    keep: Option<bool>,
+    message: Option<String>,
+    error: Option<String>,
+}
+
+impl Plugin {
+    /// 启动运行时加载插件，询问插件实现的函数
+    pub fn load(path: &Path, runtime: &str, timeout: Duration) -> Result<Self> {
+        anyhow::ensure!(path.is_file(), "Plugin {} does not exist", path.display());
+        let mut command: Vec<String> = runtime.split_whitespace().map(str::to_string).collect();
+        anyhow::ensure!(!command.is_empty(), "--plugin-runtime must not be empty");
+        command.push(path.to_string_lossy().into_owned());
+        let name = path
+            .file_stem()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy()
+            .into_owned();
+
+        let mut session = Session::spawn(&command, timeout)?;
+        let reply = session
+            .call(&json!({"call": "init"}))
+            .with_context(|| format!("Plugin {} did not answer init", name))?;
+        for export in &reply.exports {
+            anyhow::ensure!(
+                ["generate", "filter", "on_result"].contains(&export.as_str()),
+                "Plugin {} exports unknown function `{}`",
+                name,
+                export
+            );
+        }
+        Ok(Self {
+            name,
+            command,
+            timeout,
+            exports: reply.exports,
+            session: Mutex::new(session),
+            failed: AtomicBool::new(false),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn exports(&self) -> &[String] {
+        &self.exports
+    }
+
+    fn exports_fn(&self, function: &str) -> bool {
+        self.exports.iter().any(|export| export == function)
+    }
+
+    /// 插件是否生成候选标签
+    pub fn generates(&self) -> bool {
+        self.exports_fn("generate")
+    }
+
+    /// 插件是否过滤候选域名
+    pub fn filters(&self) -> bool {
+        self.exports_fn("filter")
+    }
+
+    /// 插件生成的候选标签，转换为 A-label 并跳过不合法的标签
+    pub fn labels(&self) -> Result<PluginLabels> {
+        let mut session = Session::spawn(&self.command, self.timeout)?;
+        session
+            .send(&json!({"call": "generate"}))
+            .with_context(|| format!("Failed to call generate in plugin {}", self.name))?;
+        Ok(PluginLabels {
+            name: self.name.clone(),
+            session: Some(session),
+        })
+    }
+
+    /// 是否扫描该域名，插件出错后不再过滤
+    pub fn keep(&self, domain: &str) -> bool {
+        if !self.filters() || self.failed.load(Ordering::Relaxed) {
+            return true;
+        }
+        match self.call(&json!({"call": "filter", "domain": domain})) {
+            Some(reply) => reply.keep.unwrap_or(true),
+            None => true,
+        }
+    }
+
+    /// 把结果交给插件，返回插件要显示的消息
+    pub fn on_result(&self, result: &DomainResult) -> Option<String> {
+        if !self.exports_fn("on_result") || self.failed.load(Ordering::Relaxed) {
+            return None;
+        }
+        self.call(&json!({"call": "on_result", "result": result}))?
+            .message
+            .map(|message| format!("[{}] {}", self.name, message))
+    }
+
+    fn call(&self, request: &serde_json::Value) -> Option<Reply> {
+        let reply = self.session.lock().unwrap().call(request);
+        match reply {
+            Ok(reply) => Some(reply),
+            Err(e) => {
+                if !self.failed.swap(true, Ordering::Relaxed) {
+                    eprintln!("警告: 插件 {} 出错，之后不再调用: {:#}", self.name, e);
+                }
+                None
+            }
+        }
+    }
+}
+
+/// 插件 `generate` 逐行回复的标签
+pub struct PluginLabels {
+    name: String,
+    /// 收到 `done` 或出错后为 `None`
+    session: Option<Session>,
+}
+
+impl Iterator for PluginLabels {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            let reply = match self.session.as_mut()?.receive() {
+                Ok(reply) if !reply.done => reply,
+                Ok(_) => {
+                    self.session = None;
+                    return None;
+                }
+                Err(e) => {
+                    eprintln!("警告: 插件 {} 生成候选时出错，之后的候选被忽略: {:#}", self.name, e);
+                    self.session = None;
+                    return None;
+                }
+            };
+            let Some(label) = reply.label else {
+                continue;
+            };
+            if let Some(label) = generate::normalize_label(label.trim()) {
+                return Some(label);
+            }
+        }
+    }
+}
+
+/// 一个运行时进程
+struct Session {
+    child: Child,
+    stdin: ChildStdin,
+    /// 读取线程逐行转发的标准输出，管道读取没有超时，放在单独的线程中
+    replies: Receiver<std::io::Result<String>>,
+    timeout: Duration,
+}
+
+impl Session {
+    fn spawn(command: &[String], timeout: Duration) -> Result<Self> {
+        let mut child = Command::new(&command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run `{}`, is the WASM runtime installed?", command[0]))?;
+        let stdin = child.stdin.take().context("Plugin stdin is not available")?;
+        let stdout = child.stdout.take().context("Plugin stdout is not available")?;
+        let (sender, replies) = mpsc::channel();
+        // 进程退出(包括超时后被终止)时标准输出关闭，线程随之结束
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                if sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(Self {
+            child,
+            stdin,
+            replies,
+            timeout,
+        })
+    }
+
+    fn call(&mut self, request: &serde_json::Value) -> Result<Reply> {
+        self.send(request)?;
+        self.receive()
+    }
+
+    fn send(&mut self, request: &serde_json::Value) -> Result<()> {
+        writeln!(self.stdin, "{}", request)
+            .and_then(|_| self.stdin.flush())
+            .context("Plugin exited")
+    }
+
+    /// 读取一行回复，超时后终止运行时进程
+    fn receive(&mut self) -> Result<Reply> {
+        let line = match self.replies.recv_timeout(self.timeout) {
+            Ok(line) => line.context("Failed to read plugin reply")?,
+            Err(RecvTimeoutError::Timeout) => {
+                let _ = self.child.kill();
+                anyhow::bail!("Plugin did not reply within {:.0}s", self.timeout.as_secs_f64());
+            }
+            Err(RecvTimeoutError::Disconnected) => anyhow::bail!("Plugin exited"),
+        };
+        let reply: Reply = serde_json::from_str(&line).with_context(|| format!("Invalid plugin reply: {}", line))?;
+        if let Some(error) = reply.error {
+            anyhow::bail!("{}", error);
+        }
+        Ok(reply)
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
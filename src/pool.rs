@@ -0,0 +1,88 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
+use tokio::{sync::Notify, time::sleep};
+
+use crate::client::WhoisClient;
+
+/// 空闲连接的最长保留时间，超过后视为过期并替换
+const MAX_IDLE: Duration = Duration::from_secs(30);
+/// 建立连接失败后的重试间隔
+const REFILL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// 预先建立的查询连接池
+///
+/// 后台任务持续把空闲连接补足到 `size` 个，worker 借出连接时跳过已关闭或过期的连接，
+/// 从而把建立连接的耗时移出查询路径。
+pub struct ConnectionPool<C: WhoisClient> {
+    client: Arc<C>,
+    size: usize,
+    idle: Mutex<VecDeque<(C::Connection, Instant)>>,
+    taken: Notify,
+}
+
+impl<C: WhoisClient> ConnectionPool<C> {
+    /// 创建连接池并启动后台补充任务
+    pub fn start(client: Arc<C>, size: usize) -> Arc<Self> {
+        let pool = Arc::new(Self {
+            client,
+            size,
+            idle: Mutex::new(VecDeque::with_capacity(size)),
+            taken: Notify::new(),
+        });
+        tokio::spawn(refill(Arc::downgrade(&pool)));
+        pool
+    }
+
+    /// 借出一个健康的连接，池中没有可用连接时直接新建
+    pub async fn acquire(&self) -> anyhow::Result<C::Connection> {
+        let reused = {
+            let mut idle = self.idle.lock().unwrap();
+            let mut found = None;
+            while let Some((conn, since)) = idle.pop_front() {
+                if since.elapsed() < MAX_IDLE && self.client.is_alive(&conn) {
+                    found = Some(conn);
+                    break;
+                }
+            }
+            found
+        };
+        self.taken.notify_one();
+
+        match reused {
+            Some(conn) => Ok(conn),
+            None => self.client.connect().await,
+        }
+    }
+
+    fn needs_refill(&self) -> bool {
+        let mut idle = self.idle.lock().unwrap();
+        idle.retain(|(conn, since)| since.elapsed() < MAX_IDLE && self.client.is_alive(conn));
+        idle.len() < self.size
+    }
+}
+
+/// 后台补充任务，连接池被释放后自动退出
+async fn refill<C: WhoisClient>(pool: Weak<ConnectionPool<C>>) {
+    loop {
+        let Some(pool) = pool.upgrade() else {
+            return;
+        };
+
+        if pool.needs_refill() {
+            match pool.client.connect().await {
+                Ok(conn) => pool.idle.lock().unwrap().push_back((conn, Instant::now())),
+                Err(_) => {
+                    drop(pool);
+                    sleep(REFILL_BACKOFF).await;
+                }
+            }
+            continue;
+        }
+
+        // 连接池已满，等待有连接被借出或定期检查过期连接
+        let _ = tokio::time::timeout(Duration::from_secs(1), pool.taken.notified()).await;
+    }
+}
@@ -0,0 +1,154 @@
+//! 可用域名的价格标注
+//!
+//! 查询服务只回答域名是否可注册，不提供价格。配置文件的 [pricing] 部分给出按 TLD
+//! 的注册和续费价格(通常照抄所用注册商的价目表)，以及按通配符匹配的溢价域名价格，
+//! 可用的结果会附带对应的价格。
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::glob::glob_match;
+
+/// 配置文件中的 [pricing] 部分
+///
+/// ```toml
+/// [pricing]
+/// currency = "CHF"
+///
+/// [pricing.tlds.li]
+/// register = 9.0
+/// renew = 12.0
+///
+/// [[pricing.premium]]
+/// pattern = "?.li"
+/// register = 500.0
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PricingConfig {
+    /// 价格使用的货币
+    #[serde(default = "default_currency")]
+    currency: String,
+    /// 各 TLD 的常规价格
+    #[serde(default)]
+    tlds: BTreeMap<String, TldPrice>,
+    /// 溢价域名，按顺序使用第一个匹配的模式
+    #[serde(default)]
+    premium: Vec<PremiumPrice>,
+}
+
+fn default_currency() -> String {
+    "CHF".to_string()
+}
+
+/// 一个 TLD 的常规价格(每年)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TldPrice {
+    register: f64,
+    /// 未设置时与注册价格相同
+    #[serde(default)]
+    renew: Option<f64>,
+}
+
+/// 匹配模式的溢价价格
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PremiumPrice {
+    /// 支持 `*` 和 `?` 通配符的完整域名模式，如 `??.li`
+    pattern: String,
+    register: f64,
+    /// 未设置时使用该 TLD 的常规续费价格，也没有时与注册价格相同
+    #[serde(default)]
+    renew: Option<f64>,
+}
+
+/// 结果中附带的价格
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Price {
+    pub currency: String,
+    /// 首年注册价格
+    pub register: f64,
+    /// 每年续费价格
+    pub renew: f64,
+    /// 是否按溢价域名定价
+    pub premium: bool,
+}
+
+impl PricingConfig {
+    /// 检查价格不为负数，并统一 TLD 和模式的大小写
+    pub fn validate(mut self) -> Result<Self> {
+        let mut tlds = BTreeMap::new();
+        for (tld, price) in self.tlds {
+            let tld = tld.trim_start_matches('.').to_ascii_lowercase();
+            anyhow::ensure!(
+                price.register >= 0.0 && price.renew.is_none_or(|renew| renew >= 0.0),
+                "pricing.tlds.{} must not have negative prices",
+                tld
+            );
+            tlds.insert(tld, price);
+        }
+        self.tlds = tlds;
+        for premium in &mut self.premium {
+            premium.pattern = premium.pattern.to_ascii_lowercase();
+            anyhow::ensure!(
+                premium.register >= 0.0 && premium.renew.is_none_or(|renew| renew >= 0.0),
+                "pricing.premium `{}` must not have negative prices",
+                premium.pattern
+            );
+        }
+        Ok(self)
+    }
+
+    /// 域名的价格，TLD 不在价目表中且不匹配溢价模式时为 `None`
+    pub fn quote(&self, domain: &str) -> Option<Price> {
+        let domain = domain.to_ascii_lowercase();
+        let regular = domain
+            .split_once('.')
+            .and_then(|(_, tld)| self.tlds.get(tld));
+
+        if let Some(premium) = self
+            .premium
+            .iter()
+            .find(|premium| glob_match(premium.pattern.as_bytes(), domain.as_bytes()))
+        {
+            return Some(Price {
+                currency: self.currency.clone(),
+                register: premium.register,
+                renew: premium
+                    .renew
+                    .or_else(|| regular.map(TldPrice::renew))
+                    .unwrap_or(premium.register),
+                premium: true,
+            });
+        }
+
+        regular.map(|price| Price {
+            currency: self.currency.clone(),
+            register: price.register,
+            renew: price.renew(),
+            premium: false,
+        })
+    }
+}
+
+impl TldPrice {
+    fn renew(&self) -> f64 {
+        self.renew.unwrap_or(self.register)
+    }
+}
+
+impl Price {
+    /// 终端中显示的价格
+    pub fn display(&self) -> String {
+        let mut text = format!("{} {:.2}", self.currency, self.register);
+        if self.renew != self.register {
+            text.push_str(&format!(", 续费 {:.2}", self.renew));
+        }
+        if self.premium {
+            text.push_str(", 溢价");
+        }
+        text
+    }
+}
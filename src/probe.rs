@@ -0,0 +1,140 @@
+//! 已注册域名的 HTTP 探测
+//!
+//! 调研已注册域名的使用情况时，依次尝试 HTTPS 和 HTTP 访问首页(跟随最多 5 次重定向)，
+//! 记录状态码、重定向后的地址和页面标题，并按页面内容和跳转目标中的常见停放/出售标记
+//! 判断域名是否只是停放或待售。判断是启发式的，只作为参考。
+
+use serde::{Deserialize, Serialize};
+
+use crate::http::{CurlConfig, Response};
+
+/// 默认同时进行的探测数
+pub const DEFAULT_CONCURRENCY: usize = 20;
+
+const PROBE_TIMEOUT_SECS: u32 = 10;
+/// 只读取页面开头的部分，足以找到标题和停放标记
+const MAX_BODY_BYTES: usize = 64 * 1024;
+const MAX_TITLE_CHARS: usize = 200;
+
+/// 停放页面、域名交易平台常见的文字或地址片段(小写)
+const PARKING_MARKERS: &[&str] = &[
+    "domain is for sale",
+    "domain may be for sale",
+    "buy this domain",
+    "domain for sale",
+    "make an offer",
+    "domain parking",
+    "parked free",
+    "parkingcrew",
+    "zu verkaufen",
+    "steht zum verkauf",
+    "est à vendre",
+    "sedo.com",
+    "sedoparking",
+    "dan.com",
+    "afternic",
+    "hugedomains",
+    "bodis.com",
+    "above.com",
+];
+
+/// 结果中附带的探测信息
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HttpProbe {
+    /// 最终响应的状态码，无法访问时没有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// 发生重定向时的最终地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirect: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// 是否像停放或待售页面
+    pub parked: bool,
+    /// HTTPS 和 HTTP 都无法访问时的错误
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 探测域名的首页，先尝试 HTTPS，失败时再尝试 HTTP
+pub async fn probe(domain: &str) -> HttpProbe {
+    let mut error = String::new();
+    for scheme in ["https", "http"] {
+        let mut request = CurlConfig::new(&format!("{}://{}/", scheme, domain), PROBE_TIMEOUT_SECS);
+        request.option("location", "");
+        request.option("max-redirs", "5");
+        request.option("max-filesize", "5000000");
+        request.option("user-agent", concat!("li-domain-checker/", env!("CARGO_PKG_VERSION")));
+        match request.fetch().await {
+            Ok(response) => return classify(response),
+            Err(e) => error = format!("{:#}", e),
+        }
+    }
+    HttpProbe {
+        status: None,
+        redirect: None,
+        title: None,
+        parked: false,
+        error: Some(error),
+    }
+}
+
+fn classify(response: Response) -> HttpProbe {
+    let mut end = response.body.len().min(MAX_BODY_BYTES);
+    while !response.body.is_char_boundary(end) {
+        end -= 1;
+    }
+    let head = response.body[..end].to_lowercase();
+    let redirect = (response.redirects > 0).then_some(response.url);
+    let target = redirect.as_deref().unwrap_or_default().to_lowercase();
+    let parked = PARKING_MARKERS
+        .iter()
+        .any(|marker| head.contains(marker) || target.contains(marker));
+
+    HttpProbe {
+        status: Some(response.status),
+        redirect,
+        title: title(&response.body[..end]),
+        parked,
+        error: None,
+    }
+}
+
+/// 页面 `<title>` 的文本，合并空白并解码常见的字符实体
+fn title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let text = html[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ");
+    (!text.is_empty()).then(|| text.chars().take(MAX_TITLE_CHARS).collect())
+}
+
+impl HttpProbe {
+    /// 终端中显示的说明
+    pub fn display(&self) -> String {
+        let Some(status) = self.status else {
+            return "无法访问".to_string();
+        };
+        let mut text = format!("HTTP {}", status);
+        if let Some(redirect) = &self.redirect {
+            text.push_str(&format!(" → {}", redirect));
+        }
+        if let Some(title) = &self.title {
+            text.push_str(&format!(" \"{}\"", title));
+        }
+        if self.parked {
+            text.push_str(" (停放/待售)");
+        }
+        text
+    }
+}
@@ -0,0 +1,473 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressState, ProgressStyle};
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    io::Write as _,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{sync::Notify, task::JoinHandle};
+
+use crate::{limiter::RateLimiter, stats::ScanStats, DomainStatus};
+
+/// 估算吞吐量使用的滑动窗口长度
+const ETA_WINDOW: Duration = Duration::from_secs(60);
+
+/// 剩余时间估算
+///
+/// 同时考虑各查询服务限速器当前的间隔(含限流后的自适应降速)、已观测到的每个域名平均重试次数,
+/// 以及滑动窗口内实际完成的速率，取两者中较慢的一个。
+pub struct EtaEstimator {
+    limiters: Vec<Arc<RateLimiter>>,
+    stats: Arc<ScanStats>,
+    window: Mutex<VecDeque<Instant>>,
+}
+
+impl EtaEstimator {
+    pub fn new(limiters: Vec<Arc<RateLimiter>>, stats: Arc<ScanStats>) -> Self {
+        Self {
+            limiters,
+            stats,
+            window: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// 记录一个域名查询完成
+    fn record(&self) {
+        let now = Instant::now();
+        let mut window = self.window.lock().unwrap();
+        window.push_back(now);
+        while window.front().is_some_and(|t| now.duration_since(*t) > ETA_WINDOW) {
+            window.pop_front();
+        }
+    }
+
+    /// 估算剩余 remaining 个域名所需的时间
+    fn estimate(&self, done: u64, remaining: u64) -> Option<Duration> {
+        if remaining == 0 {
+            return Some(Duration::ZERO);
+        }
+
+        let attempts_per_domain = if done > 0 {
+            (self.stats.queries() as f64 / done as f64).max(1.0)
+        } else {
+            1.0
+        };
+        // 各查询服务并行工作，合计速率为各自速率之和
+        let rate: f64 = self
+            .limiters
+            .iter()
+            .map(|limiter| 1.0 / limiter.current_interval().as_secs_f64())
+            .sum();
+        let limited = if rate.is_finite() {
+            remaining as f64 * attempts_per_domain / rate
+        } else {
+            0.0
+        };
+
+        let observed = self.observed_rate().map(|rate| remaining as f64 / rate);
+
+        match observed {
+            Some(observed) => Some(Duration::from_secs_f64(observed.max(limited))),
+            None if limited > 0.0 => Some(Duration::from_secs_f64(limited)),
+            None => None,
+        }
+    }
+
+    /// 滑动窗口内实际完成的速率(域名/秒)，完成的域名少于两个时为 `None`
+    fn observed_rate(&self) -> Option<f64> {
+        let window = self.window.lock().unwrap();
+        match (window.front(), window.back()) {
+            (Some(first), Some(last)) if window.len() >= 2 && last > first => {
+                Some((window.len() - 1) as f64 / last.duration_since(*first).as_secs_f64())
+            }
+            _ => None,
+        }
+    }
+
+    /// 供进度条模板使用的格式化函数
+    fn write(&self, state: &ProgressState, w: &mut dyn Write) {
+        let done = state.pos();
+        let remaining = state.len().unwrap_or(done).saturating_sub(done);
+        match self.estimate(done, remaining) {
+            Some(eta) => {
+                let secs = eta.as_secs();
+                let _ = write!(w, "{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+            }
+            None => {
+                let _ = write!(w, "--:--:--");
+            }
+        }
+    }
+}
+
+/// 按状态分类的实时计数
+#[derive(Default)]
+struct StatusCounters {
+    available: AtomicU64,
+    registered: AtomicU64,
+    rate_limited: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl StatusCounters {
+    fn record(&self, status: &DomainStatus) {
+        let counter = match status {
+            DomainStatus::Available => &self.available,
+            DomainStatus::Registered => &self.registered,
+            DomainStatus::RateLimited => &self.rate_limited,
+            DomainStatus::Error => &self.errors,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn counts(&self) -> StatusCounts {
+        StatusCounts {
+            available: self.available.load(Ordering::Relaxed),
+            registered: self.registered.load(Ordering::Relaxed),
+            rate_limited: self.rate_limited.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+
+    fn message(&self) -> String {
+        format!(
+            "可用 {} / 已注册 {} / 限流 {} / 错误 {}",
+            self.available.load(Ordering::Relaxed),
+            self.registered.load(Ordering::Relaxed),
+            self.rate_limited.load(Ordering::Relaxed),
+            self.errors.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// 某一时刻的状态计数
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct StatusCounts {
+    available: u64,
+    registered: u64,
+    rate_limited: u64,
+    errors: u64,
+}
+
+impl StatusCounts {
+    pub fn add(&mut self, status: &DomainStatus) {
+        match status {
+            DomainStatus::Available => self.available += 1,
+            DomainStatus::Registered => self.registered += 1,
+            DomainStatus::RateLimited => self.rate_limited += 1,
+            DomainStatus::Error => self.errors += 1,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.available + self.registered + self.rate_limited + self.errors
+    }
+
+    /// 各状态的名称和域名数
+    pub fn entries(&self) -> [(&'static str, u64); 4] {
+        [
+            ("可用", self.available),
+            ("已注册", self.registered),
+            ("限流", self.rate_limited),
+            ("错误", self.errors),
+        ]
+    }
+}
+
+/// 写入 summary.json 的一个生成阶段的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct StageSummary {
+    name: String,
+    total: u64,
+    done: u64,
+    #[serde(flatten)]
+    counts: StatusCounts,
+}
+
+/// --progress-json 输出的一条进度记录
+#[derive(Debug, Serialize)]
+struct ProgressRecord<'a> {
+    run_id: &'a str,
+    /// 扫描结束时的最后一条记录为 true
+    finished: bool,
+    elapsed_secs: f64,
+    done: u64,
+    total: u64,
+    #[serde(flatten)]
+    counts: StatusCounts,
+    /// 最近一分钟实际完成的速率(域名/秒)
+    qps: Option<f64>,
+    eta_secs: Option<u64>,
+    stages: Vec<StageRecord>,
+}
+
+#[derive(Debug, Serialize)]
+struct StageRecord {
+    name: String,
+    done: u64,
+    total: u64,
+}
+
+/// 扫描进度显示：一个总进度条加每个生成阶段一个进度条
+pub struct ScanProgress {
+    multi: MultiProgress,
+    overall: ProgressBar,
+    overall_counters: StatusCounters,
+    stages: Vec<(ProgressBar, StatusCounters)>,
+    eta: Arc<EtaEstimator>,
+    /// 各阶段的域名是否已全部完成，保证完成只返回一次
+    completed: Vec<AtomicBool>,
+    /// 各阶段是否已完成并报告
+    reported: Vec<AtomicBool>,
+    /// 每当有阶段报告完成时通知
+    stage_reported: Notify,
+}
+
+impl ScanProgress {
+    /// 为给定的阶段(名称, 域名数)创建进度条
+    pub fn new(stages: &[(&str, usize)], eta: Arc<EtaEstimator>) -> Self {
+        let multi = MultiProgress::new();
+        let total: usize = stages.iter().map(|(_, len)| len).sum();
+
+        let overall = multi.add(ProgressBar::new(total as u64));
+        let estimator = eta.clone();
+        overall.set_style(
+            ProgressStyle::default_bar()
+                .template("[{elapsed_precise}] {prefix:>12} {bar:40.cyan/blue} {pos:>7}/{len:7} 剩余 {eta_model} {msg}")
+                .unwrap()
+                .with_key("eta_model", move |state: &ProgressState, w: &mut dyn Write| {
+                    estimator.write(state, w)
+                }),
+        );
+        overall.set_prefix("总进度");
+
+        let stage_style = ProgressStyle::default_bar()
+            .template("             {prefix:>12} {bar:40.green/white} {pos:>7}/{len:7} {msg}")
+            .unwrap();
+        let stages: Vec<_> = stages
+            .iter()
+            .map(|(name, len)| {
+                let pb = multi.add(ProgressBar::new(*len as u64));
+                pb.set_style(stage_style.clone());
+                pb.set_prefix(name.to_string());
+                (pb, StatusCounters::default())
+            })
+            .collect();
+
+        let completed = stages.iter().map(|_| AtomicBool::new(false)).collect();
+        let reported = stages.iter().map(|_| AtomicBool::new(false)).collect();
+
+        Self {
+            multi,
+            overall,
+            overall_counters: StatusCounters::default(),
+            stages,
+            eta,
+            completed,
+            reported,
+            stage_reported: Notify::new(),
+        }
+    }
+
+    /// 记录某个阶段完成的一次查询，这次查询使该阶段全部完成时返回 true
+    pub fn record(&self, stage: usize, status: &DomainStatus) -> bool {
+        let (pb, counters) = &self.stages[stage];
+        counters.record(status);
+        pb.set_message(counters.message());
+        pb.inc(1);
+
+        self.eta.record();
+        self.overall_counters.record(status);
+        self.overall.set_message(self.overall_counters.message());
+        self.overall.inc(1);
+        self.complete(stage)
+    }
+
+    /// 记录插件或脚本过滤掉的一个域名：从阶段和总进度的域名数中扣除，使该阶段全部完成时
+    /// 返回 true
+    pub fn skip(&self, stage: usize) -> bool {
+        self.stages[stage].0.dec_length(1);
+        self.overall.dec_length(1);
+        self.complete(stage)
+    }
+
+    /// 阶段的域名是否刚刚全部完成，查询和过滤同时结束时只有一方得到 true
+    fn complete(&self, stage: usize) -> bool {
+        let (pb, _) = &self.stages[stage];
+        let finished =
+            pb.position() == pb.length().unwrap_or(0) && !self.completed[stage].swap(true, Ordering::AcqRel);
+        if finished {
+            pb.finish();
+        }
+        finished
+    }
+
+    /// 标记阶段已完成并报告，唤醒等待它的任务
+    pub fn report_stage(&self, stage: usize) {
+        self.reported[stage].store(true, Ordering::Release);
+        self.stage_reported.notify_waiters();
+    }
+
+    /// 等待阶段中的查询全部完成并报告，没有域名的阶段立即返回
+    pub async fn wait_stage(&self, stage: usize) {
+        let (pb, _) = &self.stages[stage];
+        if pb.length().unwrap_or(0) == 0 {
+            return;
+        }
+        loop {
+            let notified = self.stage_reported.notified();
+            if self.reported[stage].load(Ordering::Acquire) {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// 本次扫描中已得出的可用域名数
+    pub fn available(&self) -> u64 {
+        self.overall_counters.counts().available
+    }
+
+    /// 阶段完成时的一行摘要
+    pub fn stage_message(&self, stage: usize) -> String {
+        let (pb, counters) = &self.stages[stage];
+        format!("阶段 {} 完成: {} 个域名，{}", pb.prefix(), pb.position(), counters.message())
+    }
+
+    /// 在进度条上方打印一行，避免与进度条相互覆盖
+    pub fn println(&self, line: impl AsRef<str>) {
+        // 输出被重定向时进度条处于隐藏状态，会丢弃 println 的内容
+        if self.multi.is_hidden() {
+            println!("{}", line.as_ref());
+        } else {
+            let _ = self.multi.println(line);
+        }
+    }
+
+    fn record_json(&self, run_id: &str, finished: bool) -> String {
+        let done = self.overall.position();
+        let total = self.overall.length().unwrap_or(done);
+        let record = ProgressRecord {
+            run_id,
+            finished,
+            elapsed_secs: (self.overall.elapsed().as_secs_f64() * 10.0).round() / 10.0,
+            done,
+            total,
+            counts: self.overall_counters.counts(),
+            qps: self.eta.observed_rate().map(|rate| (rate * 100.0).round() / 100.0),
+            eta_secs: self
+                .eta
+                .estimate(done, total.saturating_sub(done))
+                .map(|eta| eta.as_secs()),
+            stages: self
+                .stages
+                .iter()
+                .map(|(pb, _)| StageRecord {
+                    name: pb.prefix(),
+                    done: pb.position(),
+                    total: pb.length().unwrap_or(0),
+                })
+                .collect(),
+        };
+        serde_json::to_string(&record).unwrap_or_default()
+    }
+
+    /// 各阶段的域名数、已完成数和按状态的计数
+    pub fn stage_summaries(&self) -> Vec<StageSummary> {
+        self.stages
+            .iter()
+            .map(|(pb, counters)| StageSummary {
+                name: pb.prefix(),
+                total: pb.length().unwrap_or(0),
+                done: pb.position(),
+                counts: counters.counts(),
+            })
+            .collect()
+    }
+
+    /// 结束所有进度条
+    pub fn finish(&self) {
+        for (pb, _) in &self.stages {
+            pb.finish();
+        }
+        self.overall.finish();
+    }
+}
+
+/// 扫描结束后的附加阶段(如 HTTP 探测)使用的单个进度条，输出被重定向时自动隐藏
+pub fn stage_bar(len: usize, prefix: &str) -> ProgressBar {
+    let bar = ProgressBar::new(len as u64);
+    bar.set_style(
+        ProgressStyle::default_bar()
+            .template("[{elapsed_precise}] {prefix:>12} {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}")
+            .unwrap(),
+    );
+    bar.set_prefix(prefix.to_string());
+    bar
+}
+
+/// 定期把进度以 JSON 行写到标准错误或文件(可为命名管道)的任务
+pub struct JsonReporter {
+    stop: Arc<Notify>,
+    handle: JoinHandle<()>,
+}
+
+impl JsonReporter {
+    /// path 为 `-` 时写到标准错误；写入失败时给出一次提示并停止输出
+    pub fn start(progress: Arc<ScanProgress>, path: PathBuf, interval: Duration, run_id: String) -> Self {
+        let stop = Arc::new(Notify::new());
+        let notified = stop.clone();
+        let handle = tokio::spawn(async move {
+            let mut output: Box<dyn std::io::Write + Send> = if path.as_os_str() == "-" {
+                Box::new(std::io::stderr())
+            } else {
+                // 命名管道在有读取方之前会阻塞，扫描结束时仍没有读取方则放弃
+                let target = path.clone();
+                let open = tokio::task::spawn_blocking(move || {
+                    std::fs::OpenOptions::new().create(true).append(true).open(target)
+                });
+                let opened = tokio::select! {
+                    opened = open => opened,
+                    _ = notified.notified() => return,
+                };
+                match opened {
+                    Ok(Ok(file)) => Box::new(file),
+                    Ok(Err(e)) => {
+                        eprintln!("警告: 无法打开 --progress-json 输出 {}: {}", path.display(), e);
+                        return;
+                    }
+                    Err(_) => return,
+                }
+            };
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                let finished = tokio::select! {
+                    _ = ticker.tick() => false,
+                    _ = notified.notified() => true,
+                };
+                let line = progress.record_json(&run_id, finished);
+                if let Err(e) = writeln!(output, "{}", line).and_then(|_| output.flush()) {
+                    eprintln!("警告: 写入 --progress-json 输出失败，之后不再输出进度: {}", e);
+                    return;
+                }
+                if finished {
+                    return;
+                }
+            }
+        });
+        Self { stop, handle }
+    }
+
+    /// 写出最后一条记录后结束
+    pub async fn finish(self) {
+        self.stop.notify_one();
+        let _ = self.handle.await;
+    }
+}
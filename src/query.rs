@@ -0,0 +1,81 @@
+//! query 子命令：在已保存的结果中查询
+//!
+//! 条件语法见 [`crate::filter`]，读取目录中的完整结果文件，中断的运行没有完整结果时读取
+//! 结果日志。默认读取输出根目录中的 `latest`，没有该符号链接时(Windows)按 latest.json 找到
+//! 最近一次运行的结果目录。
+
+use anyhow::{Context, Result};
+use clap::Args as ClapArgs;
+use std::path::PathBuf;
+
+use crate::{filter::{self, Query}, load_results, result_dir, tags};
+
+/// query 子命令参数
+#[derive(ClapArgs, Debug)]
+pub struct QueryArgs {
+    /// 查询条件，如 "status = 'available' AND length(label) <= 3 ORDER BY score DESC LIMIT 20"
+    #[arg(default_value = "")]
+    query: String,
+
+    /// 保存结果的目录(默认为最近一次运行的结果)
+    #[arg(long, default_value = "li_domain_results/latest")]
+    dir: PathBuf,
+
+    /// 每行输出一条 JSON 结果，而不是表格
+    #[arg(long)]
+    json: bool,
+}
+
+/// 在已保存的结果中查询并输出匹配的记录
+pub fn run(args: QueryArgs) -> Result<()> {
+    let query = Query::parse(&args.query).context("Invalid query")?;
+    let dir = result_dir(&args.dir)?;
+    let results = load_results(&dir)?;
+
+    let mut matched: Vec<_> = results.iter().filter(|result| query.matches(result)).collect();
+    query.sort(&mut matched);
+    if let Some(limit) = query.limit {
+        matched.truncate(limit);
+    }
+
+    if args.json {
+        for result in &matched {
+            println!("{}", serde_json::to_string(result)?);
+        }
+        return Ok(());
+    }
+
+    let width = matched.iter().map(|r| r.domain.len()).max().unwrap_or(0).max(6);
+    for result in &matched {
+        let mut line = format!(
+            "{:<width$}  {:<12}  {:>5}  {}  {}",
+            result.domain,
+            filter::status_name(&result.status),
+            result.reply_code,
+            result.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            result.message,
+            width = width
+        );
+        if let Some(unicode) = &result.unicode {
+            line.push_str(&format!("  [{}]", unicode));
+        }
+        if let Some(price) = &result.price {
+            line.push_str(&format!("  ({})", price.display()));
+        }
+        if let Some(ct) = result.ct.as_ref().and_then(|ct| ct.display()) {
+            line.push_str(&format!("  [CT: {}]", ct));
+        }
+        if let Some(probe) = &result.http {
+            line.push_str(&format!("  [{}]", probe.display()));
+        }
+        if let Some(text) = result.whois.as_ref().and_then(|whois| whois.display()) {
+            line.push_str(&format!("  [{}]", text));
+        }
+        if !result.tags.is_empty() {
+            line.push_str(&format!("  {}", tags::display(&result.tags)));
+        }
+        println!("{}", line);
+    }
+    eprintln!("{} / {} 条记录", matched.len(), results.len());
+    Ok(())
+}
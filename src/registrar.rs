@@ -0,0 +1,360 @@
+//! 注册商接口
+//!
+//! 扫描中发现的可用域名匹配优先列表时，立即调用注册商(Gandi 或 Namecheap)的注册接口，
+//! 或通过 [epp] 中配置的 EPP 会话直接向注册局注册。HTTP 请求通过系统中的 curl 发送，
+//! 见 [`crate::http`]。
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{fs, path::PathBuf, sync::Mutex};
+
+use crate::{
+    epp::{EppClient, EppConfig},
+    glob::glob_match,
+    http::{self, CurlConfig},
+};
+
+/// 未在配置文件中设置 api_key 时读取的环境变量
+pub const API_KEY_ENV: &str = "REGISTRAR_API_KEY";
+/// 单次注册请求的超时时间(秒)
+const REQUEST_TIMEOUT_SECS: u32 = 30;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    Gandi,
+    Namecheap,
+    /// 使用 [epp] 中的凭据直接向注册局注册
+    Epp,
+}
+
+/// 配置文件中的 [registrar] 部分
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegistrarConfig {
+    provider: Provider,
+    /// API 密钥，未设置时读取环境变量 REGISTRAR_API_KEY
+    #[serde(default)]
+    api_key: Option<String>,
+    /// Namecheap 的 API 用户名
+    #[serde(default)]
+    api_user: Option<String>,
+    /// Namecheap 要求的已加入白名单的客户端 IP
+    #[serde(default)]
+    client_ip: Option<String>,
+    /// 使用注册商的测试环境
+    #[serde(default)]
+    sandbox: bool,
+    /// 只记录将要发送的注册请求，不实际调用接口
+    #[serde(default)]
+    dry_run: bool,
+    /// 注册年限
+    #[serde(default = "default_years")]
+    years: u32,
+    /// 优先列表，支持 `*` 和 `?` 通配符，如 `abc.li`、`??.ch`
+    #[serde(default)]
+    priority: Vec<String>,
+    /// 优先列表文件，每行一个模式，`#` 开头的行为注释
+    #[serde(default)]
+    priority_file: Option<PathBuf>,
+    /// 注册人联系信息(Gandi / Namecheap)
+    #[serde(default)]
+    contact: Option<Contact>,
+    /// 注册局中已有的联系人标识(EPP)
+    #[serde(default)]
+    epp: Option<EppContacts>,
+}
+
+fn default_years() -> u32 {
+    1
+}
+
+/// 注册人联系信息(同时用作管理、技术和账单联系人)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Contact {
+    first_name: String,
+    last_name: String,
+    email: String,
+    /// 国际格式，如 +41.441234567
+    phone: String,
+    address: String,
+    city: String,
+    zip: String,
+    /// 两位国家代码，如 CH
+    country: String,
+    #[serde(default)]
+    state: Option<String>,
+    #[serde(default)]
+    organization: Option<String>,
+}
+
+/// EPP 注册使用的联系人标识，需预先在注册局创建
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EppContacts {
+    registrant: String,
+    #[serde(default)]
+    admin: Option<String>,
+    #[serde(default)]
+    tech: Option<String>,
+}
+
+/// 一次注册尝试的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct Registration {
+    pub domain: String,
+    pub provider: Provider,
+    pub success: bool,
+    pub dry_run: bool,
+    pub message: String,
+}
+
+/// 注册商客户端
+pub struct Registrar {
+    config: RegistrarConfig,
+    api_key: String,
+    epp: Option<EppClient>,
+    patterns: Vec<String>,
+    outcomes: Mutex<Vec<Registration>>,
+}
+
+impl Registrar {
+    /// 校验配置并读取优先列表，扫描开始前即报告配置错误
+    pub fn new(config: RegistrarConfig, epp: Option<&EppConfig>) -> Result<Self> {
+        let mut patterns: Vec<String> = config
+            .priority
+            .iter()
+            .map(|pattern| pattern.trim().to_ascii_lowercase())
+            .collect();
+        if let Some(path) = &config.priority_file {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read priority file {}", path.display()))?;
+            patterns.extend(
+                content
+                    .lines()
+                    .map(|line| line.trim().to_ascii_lowercase())
+                    .filter(|line| !line.is_empty() && !line.starts_with('#')),
+            );
+        }
+        anyhow::ensure!(
+            !patterns.is_empty(),
+            "registrar.priority or registrar.priority_file must list at least one pattern"
+        );
+
+        let api_key = config
+            .api_key
+            .clone()
+            .or_else(|| std::env::var(API_KEY_ENV).ok())
+            .unwrap_or_default();
+        let mut epp_client = None;
+        if config.provider == Provider::Epp {
+            let epp = epp.context("registrar.provider = \"epp\" requires an [epp] section")?;
+            anyhow::ensure!(
+                config.epp.is_some(),
+                "registrar.provider = \"epp\" requires registrar.epp.registrant"
+            );
+            if !config.dry_run {
+                epp_client = Some(EppClient::new(&epp.server, epp)?);
+            }
+        } else {
+            anyhow::ensure!(
+                config.contact.is_some(),
+                "registrar.contact is required for {:?}",
+                config.provider
+            );
+        }
+        if !config.dry_run && config.provider != Provider::Epp {
+            anyhow::ensure!(
+                !api_key.is_empty(),
+                "registrar.api_key is not set and {} is empty",
+                API_KEY_ENV
+            );
+            if config.provider == Provider::Namecheap {
+                anyhow::ensure!(
+                    config.api_user.is_some() && config.client_ip.is_some(),
+                    "Namecheap requires registrar.api_user and registrar.client_ip"
+                );
+            }
+            anyhow::ensure!(
+                http::curl_available(),
+                "The registrar hook requires the `curl` command to be installed"
+            );
+        }
+
+        Ok(Self {
+            config,
+            api_key,
+            epp: epp_client,
+            patterns,
+            outcomes: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// 域名是否在优先列表中
+    pub fn matches(&self, domain: &str) -> bool {
+        let domain = domain.to_ascii_lowercase();
+        self.patterns
+            .iter()
+            .any(|pattern| glob_match(pattern.as_bytes(), domain.as_bytes()))
+    }
+
+    /// 调用注册接口并记录结果
+    pub async fn register(&self, domain: &str) -> Registration {
+        let outcome = if self.config.dry_run {
+            Ok(format!(
+                "dry run: would register for {} year(s) via {:?}",
+                self.config.years, self.config.provider
+            ))
+        } else {
+            match self.config.provider {
+                Provider::Gandi => self.register_gandi(domain).await,
+                Provider::Namecheap => self.register_namecheap(domain).await,
+                Provider::Epp => self.register_epp(domain).await,
+            }
+        };
+
+        let registration = Registration {
+            domain: domain.to_string(),
+            provider: self.config.provider,
+            success: outcome.is_ok(),
+            dry_run: self.config.dry_run,
+            message: match outcome {
+                Ok(message) => message,
+                Err(e) => format!("{:#}", e),
+            },
+        };
+        self.outcomes.lock().unwrap().push(registration.clone());
+        registration
+    }
+
+    /// 本次运行中所有注册尝试的结果
+    pub fn outcomes(&self) -> Vec<Registration> {
+        self.outcomes.lock().unwrap().clone()
+    }
+
+    /// 注册人联系信息，创建时已确认 Gandi / Namecheap 配置了该项
+    fn contact(&self) -> Result<&Contact> {
+        self.config.contact.as_ref().context("registrar.contact is not set")
+    }
+
+    async fn register_gandi(&self, domain: &str) -> Result<String> {
+        let contact = self.contact()?;
+        let mut owner = json!({
+            "type": if contact.organization.is_some() { 1 } else { 0 },
+            "given": contact.first_name,
+            "family": contact.last_name,
+            "email": contact.email,
+            "phone": contact.phone,
+            "streetaddr": contact.address,
+            "city": contact.city,
+            "zip": contact.zip,
+            "country": contact.country,
+        });
+        if let Some(state) = &contact.state {
+            owner["state"] = json!(state);
+        }
+        if let Some(organization) = &contact.organization {
+            owner["orgname"] = json!(organization);
+        }
+        let body = json!({
+            "fqdn": domain,
+            "duration": self.config.years,
+            "owner": owner,
+        });
+
+        let host = if self.config.sandbox {
+            "api.sandbox.gandi.net"
+        } else {
+            "api.gandi.net"
+        };
+        let mut request = CurlConfig::new(&format!("https://{}/v5/domain/domains", host), REQUEST_TIMEOUT_SECS);
+        request.option("request", "POST");
+        request.option("header", &format!("Authorization: Bearer {}", self.api_key));
+        request.option("header", "Content-Type: application/json");
+        request.option("data-binary", &body.to_string());
+
+        let (status, response) = request.send().await?;
+        let message = serde_json::from_str::<serde_json::Value>(&response)
+            .ok()
+            .and_then(|value| value.get("message").and_then(|m| m.as_str()).map(str::to_string))
+            .unwrap_or_else(|| response.trim().to_string());
+        anyhow::ensure!((200..300).contains(&status), "HTTP {}: {}", status, message);
+        Ok(message)
+    }
+
+    async fn register_namecheap(&self, domain: &str) -> Result<String> {
+        let contact = self.contact()?;
+        let host = if self.config.sandbox {
+            "api.sandbox.namecheap.com"
+        } else {
+            "api.namecheap.com"
+        };
+        let mut request = CurlConfig::new(&format!("https://{}/xml.response", host), REQUEST_TIMEOUT_SECS);
+        request.option("get", "");
+        let api_user = self.config.api_user.as_deref().unwrap_or_default();
+        let mut params: Vec<(String, String)> = [
+            ("ApiUser", api_user.to_string()),
+            ("ApiKey", self.api_key.clone()),
+            ("UserName", api_user.to_string()),
+            ("ClientIp", self.config.client_ip.clone().unwrap_or_default()),
+            ("Command", "namecheap.domains.create".to_string()),
+            ("DomainName", domain.to_string()),
+            ("Years", self.config.years.to_string()),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+        for role in ["Registrant", "Tech", "Admin", "AuxBilling"] {
+            let fields = [
+                ("FirstName", Some(&contact.first_name)),
+                ("LastName", Some(&contact.last_name)),
+                ("Address1", Some(&contact.address)),
+                ("City", Some(&contact.city)),
+                ("StateProvince", contact.state.as_ref()),
+                ("PostalCode", Some(&contact.zip)),
+                ("Country", Some(&contact.country)),
+                ("Phone", Some(&contact.phone)),
+                ("EmailAddress", Some(&contact.email)),
+                ("OrganizationName", contact.organization.as_ref()),
+            ];
+            for (field, value) in fields {
+                if let Some(value) = value {
+                    params.push((format!("{}{}", role, field), value.clone()));
+                }
+            }
+        }
+        for (key, value) in params {
+            request.option("data-urlencode", &format!("{}={}", key, value));
+        }
+
+        let (status, response) = request.send().await?;
+        anyhow::ensure!((200..300).contains(&status), "HTTP {}: {}", status, response.trim());
+        if response.contains("Status=\"OK\"") && response.contains("Registered=\"true\"") {
+            return Ok("registered".to_string());
+        }
+        let error = response
+            .split("<Error")
+            .nth(1)
+            .and_then(|rest| rest.split_once('>'))
+            .and_then(|(_, rest)| rest.split_once("</Error>"))
+            .map(|(message, _)| message.trim().to_string())
+            .unwrap_or_else(|| "unexpected response from Namecheap".to_string());
+        anyhow::bail!(error)
+    }
+
+    async fn register_epp(&self, domain: &str) -> Result<String> {
+        let client = self.epp.as_ref().context("EPP client is not configured")?;
+        let contacts = self.config.epp.as_ref().context("registrar.epp is not set")?;
+        client
+            .create(
+                domain,
+                self.config.years,
+                &contacts.registrant,
+                contacts.admin.as_deref(),
+                contacts.tech.as_deref(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,245 @@
+//! 原始响应存档和离线回放
+//!
+//! 使用 `--save-raw` 时，每次查询收到的原始响应连同当时的解析结果追加到输出目录的
+//! `raw_responses.ndjson`。`replay <ARCHIVE>` 不访问网络，只用当前版本的解析和分类逻辑
+//! 重新处理存档中的响应，列出分类与存档时不同的响应，并可把结果写入目录供 query 和
+//! report 子命令查看，用于以真实的历史响应检验解析逻辑的改动。
+
+use anyhow::{Context, Result};
+use clap::Args as ClapArgs;
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{
+    atomic, client, clock::Clock, compress, epp, filter::status_name, idna, schema, write_json_results, DomainResult,
+    DomainStatus, Protocol, RESULTS_JSON,
+};
+
+pub const RAW_FILE: &str = "raw_responses.ndjson";
+
+/// 存档中的一条原始响应
+#[derive(Debug, Serialize, Deserialize)]
+struct RawRecord {
+    domain: String,
+    /// 应答的查询服务
+    server: String,
+    protocol: Protocol,
+    response: String,
+    /// 存档时解析得出的响应码和状态
+    reply_code: i32,
+    status: DomainStatus,
+    timestamp: chrono::DateTime<chrono::FixedOffset>,
+    run_id: String,
+}
+
+/// 追加写入的原始响应存档
+pub struct RawArchive {
+    path: PathBuf,
+    protocol: Protocol,
+    run_id: String,
+    /// 写入失败后置为 None，之后的响应不再存档
+    writer: Mutex<Option<BufWriter<File>>>,
+}
+
+impl RawArchive {
+    /// 打开目录中的存档，已有的存档(中断后继续时)在末尾追加
+    pub fn open(dir: &Path, protocol: Protocol, run_id: &str) -> Result<Self> {
+        let path = dir.join(RAW_FILE);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open raw response archive {}", path.display()))?;
+        Ok(Self {
+            path,
+            protocol,
+            run_id: run_id.to_string(),
+            writer: Mutex::new(Some(BufWriter::new(file))),
+        })
+    }
+
+    /// 存档一条原始响应及其解析结果
+    pub fn record(
+        &self,
+        domain: &str,
+        server: &str,
+        response: &str,
+        reply_code: i32,
+        timestamp: chrono::DateTime<chrono::FixedOffset>,
+    ) {
+        let record = RawRecord {
+            domain: domain.to_string(),
+            server: server.to_string(),
+            protocol: self.protocol,
+            response: response.trim_end_matches(['\r', '\n']).to_string(),
+            reply_code,
+            status: DomainStatus::from_reply_code(reply_code),
+            timestamp,
+            run_id: self.run_id.clone(),
+        };
+        let Ok(line) = serde_json::to_string(&record) else {
+            return;
+        };
+        let mut writer = self.writer.lock().unwrap();
+        if let Some(file) = writer.as_mut() {
+            if let Err(e) = writeln!(file, "{}", line) {
+                eprintln!(
+                    "{}",
+                    style(format!("! 无法写入原始响应存档 {}: {}，之后的响应不再存档", self.path.display(), e)).yellow()
+                );
+                *writer = None;
+            }
+        }
+    }
+
+    /// 把缓冲中的响应写入磁盘
+    pub fn flush(&self) -> Result<()> {
+        if let Some(file) = self.writer.lock().unwrap().as_mut() {
+            file.flush()
+                .with_context(|| format!("Failed to write raw response archive {}", self.path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// replay 子命令参数
+#[derive(ClapArgs, Debug)]
+pub struct ReplayArgs {
+    /// --save-raw 写下的原始响应存档(可为压缩文件)，或包含它的结果目录
+    #[arg(value_name = "ARCHIVE")]
+    archive: PathBuf,
+
+    /// 把重新解析得到的结果(每个域名最后一条响应)写入该目录，可再用 query 和 report 查看
+    #[arg(long, value_name = "DIR")]
+    output_dir: Option<PathBuf>,
+
+    /// 有响应的分类与存档时不同时以非零状态退出
+    #[arg(long)]
+    fail_on_change: bool,
+}
+
+/// 用当前的解析逻辑重新处理存档中的原始响应
+pub fn run(args: ReplayArgs) -> Result<()> {
+    let path = if args.archive.is_dir() {
+        args.archive.join(RAW_FILE)
+    } else {
+        args.archive.clone()
+    };
+    let content = compress::read_to_string(&path)?;
+    let lines: Vec<&str> = content.lines().filter(|line| !line.trim().is_empty()).collect();
+
+    let mut results: Vec<DomainResult> = Vec::new();
+    let mut latest: HashMap<String, usize> = HashMap::new();
+    let mut counts: HashMap<&'static str, usize> = HashMap::new();
+    let mut changed = 0;
+    for (number, line) in lines.iter().enumerate() {
+        let record: RawRecord = match serde_json::from_str(line) {
+            Ok(record) => record,
+            // 写入中断的最后一行
+            Err(_) if number + 1 == lines.len() => break,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Invalid record on line {} of {}", number + 1, path.display()))
+            }
+        };
+        let (reply_code, message) = match record.protocol {
+            Protocol::Whois => client::parse_reply(&record.response),
+            Protocol::Epp => epp::parse_check(&record.response),
+        };
+        let status = DomainStatus::from_reply_code(reply_code);
+        *counts.entry(status_name(&status)).or_default() += 1;
+        if status != record.status || reply_code != record.reply_code {
+            changed += 1;
+            println!(
+                "{}",
+                style(format!(
+                    "~ {}: {} ({}) → {} ({})  响应: {}",
+                    record.domain,
+                    status_name(&record.status),
+                    record.reply_code,
+                    status_name(&status),
+                    reply_code,
+                    record.response
+                ))
+                .yellow()
+            );
+        }
+
+        let result = DomainResult {
+            schema_version: schema::SCHEMA_VERSION,
+            unicode: idna::unicode_domain(&record.domain),
+            domain: record.domain,
+            status,
+            reply_code,
+            message,
+            timestamp: record.timestamp,
+            run_id: record.run_id,
+            price: None,
+            ct: None,
+            http: None,
+            whois: None,
+            verification: None,
+            tags: Vec::new(),
+            cached: false,
+        };
+        match latest.get(&result.domain) {
+            Some(&position) => results[position] = result,
+            None => {
+                latest.insert(result.domain.clone(), results.len());
+                results.push(result);
+            }
+        }
+    }
+
+    let replayed: usize = counts.values().sum();
+    println!(
+        "回放 {} 条响应({} 个域名): 可用 {} / 已注册 {} / 限流 {} / 错误 {}",
+        replayed,
+        results.len(),
+        counts.get("available").unwrap_or(&0),
+        counts.get("registered").unwrap_or(&0),
+        counts.get("rate_limited").unwrap_or(&0),
+        counts.get("error").unwrap_or(&0)
+    );
+    if changed == 0 {
+        println!("{}", style("所有响应的分类与存档时一致").green());
+    } else {
+        println!("{}", style(format!("{} 条响应的分类与存档时不同", changed)).yellow().bold());
+    }
+
+    if let Some(dir) = &args.output_dir {
+        std::fs::create_dir_all(dir).context("Failed to create output directory")?;
+        write_json_results(&dir.join(RESULTS_JSON), results.iter().cloned().map(Ok))
+            .context("Failed to write replayed results")?;
+        let mut available: Vec<&str> = results
+            .iter()
+            .filter(|result| result.status == DomainStatus::Available)
+            .map(|result| result.domain.as_str())
+            .collect();
+        available.sort_unstable();
+        let mut content = format!(
+            "# 可用域名列表(回放 {})\n# 扫描时间: {}\n\n",
+            path.display(),
+            Clock::new(false).header_timestamp()
+        );
+        for domain in available {
+            content.push_str(domain);
+            content.push('\n');
+        }
+        atomic::write(&dir.join("available_domains.txt"), content).context("Failed to write available domains file")?;
+        println!("结果已保存到: {}", dir.display());
+    }
+
+    anyhow::ensure!(
+        !(args.fail_on_change && changed > 0),
+        "{} replayed responses were classified differently than when archived",
+        changed
+    );
+    Ok(())
+}
@@ -0,0 +1,498 @@
+use anyhow::{Context, Result};
+use clap::{Args as ClapArgs, ValueEnum};
+use console::{measure_text_width, pad_str, style, Alignment};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    clock::Clock,
+    filter::{self, Query},
+    history::{self, Trends},
+    load_results, result_dir,
+    overview::Overview,
+    score,
+    xlsx::{self, Cell, Chart, Sheet, Workbook},
+    DomainResult, DomainStatus,
+};
+
+/// report 子命令参数
+#[derive(ClapArgs, Debug)]
+pub struct ReportArgs {
+    /// 要生成的报告
+    #[arg(value_enum)]
+    view: ReportView,
+
+    /// 保存结果的目录(默认为最近一次运行的结果)
+    #[arg(long, default_value = "li_domain_results/latest")]
+    dir: PathBuf,
+
+    /// 扫描时 --history 写入的 SQLite 历史数据库(trends 报告需要)
+    #[arg(long, value_name = "FILE")]
+    history: Option<PathBuf>,
+
+    /// 注册商表格和域名表格最多显示的行数，注册商其余的合并为"其他"
+    #[arg(long, default_value_t = 20)]
+    top: usize,
+
+    /// 只统计满足条件的结果，如 "tld == 'li' && len <= 3"，语法与 query 子命令相同
+    #[arg(long = "where", value_name = "EXPR")]
+    condition: Option<String>,
+
+    /// 输出 JSON 而不是表格
+    #[arg(long)]
+    json: bool,
+
+    /// export 报告写入的 Excel 工作簿
+    #[arg(long, value_name = "FILE")]
+    xlsx: Option<PathBuf>,
+
+    /// export 报告中时间使用的显示时区，如 Europe/Zurich、UTC 或 +01:00(默认为本地时区)
+    #[arg(long)]
+    timezone: Option<String>,
+}
+
+/// 可生成的报告
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum ReportView {
+    /// 已注册域名的注册商份额和首次注册年份分布(需要使用 --whois-details 扫描的结果)
+    Registrars,
+    /// 各次运行的状态计数变化、在可用和已注册之间变化过的域名，以及可用域名首次和最近出现的时间
+    /// (需要 --history 数据库)
+    Trends,
+    /// 把结果导出为 Excel 工作簿：概览表(计数和图表)和每种状态一个工作表(需要 --xlsx)
+    Export,
+}
+
+/// 注册商和注册时间分布
+#[derive(Debug, Serialize)]
+struct RegistrarReport {
+    /// 结果中的已注册域名数
+    registered: usize,
+    /// 其中有 whois 信息的域名数
+    with_details: usize,
+    /// 按域名数从多到少排列
+    registrars: Vec<RegistrarShare>,
+    /// 首次注册年份及该年注册的域名数，按年份排列
+    years: BTreeMap<String, usize>,
+    /// 有 whois 信息但没有可识别的注册日期的域名数
+    without_date: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct RegistrarShare {
+    /// whois 信息中没有注册商时为 `None`
+    registrar: Option<String>,
+    domains: usize,
+    /// 占有 whois 信息的已注册域名的百分比
+    share: f64,
+}
+
+/// 生成报告
+pub fn run(args: ReportArgs) -> Result<()> {
+    anyhow::ensure!(
+        args.xlsx.is_none() || args.view == ReportView::Export,
+        "--xlsx is only supported by report export"
+    );
+    anyhow::ensure!(
+        args.timezone.is_none() || args.view == ReportView::Export,
+        "--timezone is only supported by report export"
+    );
+    match args.view {
+        ReportView::Registrars => {
+            let results = load_filtered(&args)?;
+            let report = registrars(&results);
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_registrars(&report, args.top);
+            }
+        }
+        // 趋势来自历史数据库，不读取结果目录
+        ReportView::Trends => {
+            anyhow::ensure!(args.condition.is_none(), "--where is not supported by report trends");
+            let path = args
+                .history
+                .as_deref()
+                .context("report trends requires --history <FILE>")?;
+            let trends = history::trends(path)?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&trends)?);
+            } else {
+                print_trends(&trends, args.top);
+            }
+        }
+        ReportView::Export => {
+            let path = args.xlsx.as_deref().context("report export requires --xlsx <FILE>")?;
+            anyhow::ensure!(!args.json, "--json is not supported by report export");
+            let results = load_filtered(&args)?;
+            export(&args, &results, path)?;
+            println!("{} 条结果已导出到 {}", results.len(), path.display());
+        }
+    }
+    Ok(())
+}
+
+/// 读取结果目录中满足 --where 条件的结果
+fn load_filtered(args: &ReportArgs) -> Result<Vec<crate::DomainResult>> {
+    let condition = args
+        .condition
+        .as_deref()
+        .map(Query::parse_condition)
+        .transpose()
+        .context("Invalid --where expression")?;
+    let dir = result_dir(&args.dir)?;
+    let mut results = load_results(&dir)?;
+    if let Some(condition) = &condition {
+        results.retain(|result| condition.matches(result));
+    }
+    Ok(results)
+}
+
+fn registrars(results: &[crate::DomainResult]) -> RegistrarReport {
+    let registered: Vec<_> = results
+        .iter()
+        .filter(|result| result.status == DomainStatus::Registered)
+        .collect();
+    let details: Vec<_> = registered.iter().filter_map(|result| result.whois.as_ref()).collect();
+
+    let mut counts: BTreeMap<Option<&str>, usize> = BTreeMap::new();
+    let mut years = BTreeMap::new();
+    let mut without_date = 0;
+    for whois in &details {
+        *counts.entry(whois.registrar.as_deref()).or_default() += 1;
+        let year = whois
+            .registered
+            .as_deref()
+            .and_then(|date| date.get(..4))
+            .filter(|year| year.bytes().all(|b| b.is_ascii_digit()));
+        match year {
+            Some(year) => *years.entry(year.to_string()).or_default() += 1,
+            None => without_date += 1,
+        }
+    }
+
+    let mut registrars: Vec<_> = counts
+        .into_iter()
+        .map(|(registrar, domains)| RegistrarShare {
+            registrar: registrar.map(str::to_string),
+            domains,
+            share: domains as f64 * 100.0 / details.len() as f64,
+        })
+        .collect();
+    registrars.sort_by(|a, b| b.domains.cmp(&a.domains).then_with(|| a.registrar.cmp(&b.registrar)));
+
+    RegistrarReport {
+        registered: registered.len(),
+        with_details: details.len(),
+        registrars,
+        years,
+        without_date,
+    }
+}
+
+fn print_registrars(report: &RegistrarReport, top: usize) {
+    if report.with_details == 0 {
+        println!(
+            "{} 个已注册域名都没有 whois 信息，请使用 --whois-details 重新扫描",
+            report.registered
+        );
+        return;
+    }
+
+    println!("{}", style("注册商份额").bold());
+    let shown = &report.registrars[..report.registrars.len().min(top)];
+    let name = |share: &RegistrarShare| share.registrar.clone().unwrap_or_else(|| "(未知)".to_string());
+    // 注册商名称可能含有中文等宽字符，按显示宽度对齐
+    let width = shown.iter().map(|share| measure_text_width(&name(share))).max().unwrap_or(0).max(8);
+    for share in shown {
+        println!(
+            "  {}  {:>6}  {:>5.1}%",
+            pad_str(&name(share), width, Alignment::Left, None),
+            share.domains,
+            share.share
+        );
+    }
+    let others: usize = report.registrars[shown.len()..].iter().map(|r| r.domains).sum();
+    if others > 0 {
+        println!(
+            "  {}  {:>6}  {:>5.1}%  ({} 个注册商)",
+            pad_str("其他", width, Alignment::Left, None),
+            others,
+            others as f64 * 100.0 / report.with_details as f64,
+            report.registrars.len() - shown.len()
+        );
+    }
+
+    println!("\n{}", style("首次注册年份").bold());
+    let max = report.years.values().copied().max().unwrap_or(1);
+    for (year, count) in &report.years {
+        let bar = "█".repeat((count * 40).div_ceil(max));
+        println!("  {:<6}  {:>6}  {}", year, count, bar);
+    }
+    if report.without_date > 0 {
+        println!("  {} 个域名没有可识别的注册日期", report.without_date);
+    }
+
+    println!(
+        "\n{} 个已注册域名，其中 {} 个有 whois 信息",
+        report.registered, report.with_details
+    );
+}
+
+fn print_trends(trends: &Trends, top: usize) {
+    if trends.runs.is_empty() {
+        println!("历史数据库中还没有结果");
+        return;
+    }
+
+    println!("{}", style("各次运行 (UTC)").bold());
+    let max = trends.runs.iter().map(|run| run.available).max().unwrap_or(0).max(1);
+    for run in &trends.runs {
+        let bar = "█".repeat((run.available * 40).div_ceil(max) as usize);
+        println!(
+            "  {}  {:<22}  {:>7} 个域名  可用 {:>5}  已注册 {:>7}  {}",
+            minute(&run.started_at),
+            run.run_id,
+            run.domains,
+            run.available,
+            run.registered,
+            bar
+        );
+    }
+
+    println!("\n{}", style("在可用和已注册之间变化过的域名").bold());
+    if trends.flapping.is_empty() {
+        println!("  (无)");
+    }
+    let width = trends.flapping.iter().take(top).map(|f| measure_text_width(&f.domain)).max().unwrap_or(0);
+    for flapping in trends.flapping.iter().take(top) {
+        println!(
+            "  {}  变化 {:>3} 次  现为{}  最近变化 {}",
+            pad_str(&flapping.domain, width, Alignment::Left, None),
+            flapping.changes,
+            pad_str(status_label(&flapping.status), 6, Alignment::Left, None),
+            minute(&flapping.last_change)
+        );
+    }
+    if trends.flapping.len() > top {
+        println!("  ... 另有 {} 个域名", trends.flapping.len() - top);
+    }
+
+    println!("\n{}", style("曾经可用的域名 (UTC)").bold());
+    let width = trends.domains.iter().take(top).map(|d| measure_text_width(&d.domain)).max().unwrap_or(0);
+    for domain in trends.domains.iter().take(top) {
+        println!(
+            "  {}  现为{}  可用 {} ~ {}  查询 {} ~ {}",
+            pad_str(&domain.domain, width, Alignment::Left, None),
+            pad_str(status_label(&domain.status), 6, Alignment::Left, None),
+            minute(&domain.first_available),
+            minute(&domain.last_available),
+            minute(&domain.first_seen),
+            minute(&domain.last_seen)
+        );
+    }
+    if trends.domains.len() > top {
+        println!("  ... 另有 {} 个域名", trends.domains.len() - top);
+    }
+
+    println!(
+        "\n{} 次运行，{} 个域名曾经可用，其中 {} 个在可用和已注册之间变化过",
+        trends.runs.len(),
+        trends.domains.len(),
+        trends.flapping.len()
+    );
+}
+
+/// 历史数据库中的时间精确到分钟显示
+fn minute(timestamp: &str) -> String {
+    timestamp.get(..16).unwrap_or(timestamp).replace('T', " ")
+}
+
+fn status_label(status: &str) -> &str {
+    match status {
+        "available" => "可用",
+        "registered" => "已注册",
+        "rate_limited" => "限流",
+        "error" => "错误",
+        other => other,
+    }
+}
+
+/// 工作表中的一列，只有结果中有该项信息时才导出
+struct Column {
+    header: &'static str,
+    value: fn(&DomainResult) -> Cell,
+    /// 总是导出
+    always: bool,
+}
+
+const COLUMNS: &[Column] = &[
+    Column {
+        header: "域名",
+        value: |result| Cell::from(result.domain.clone()),
+        always: true,
+    },
+    Column {
+        header: "Unicode",
+        value: |result| Cell::from(result.unicode.clone()),
+        always: false,
+    },
+    Column {
+        header: "评分",
+        value: |result| Cell::Number(score::score(&result.domain) as f64),
+        always: true,
+    },
+    Column {
+        header: "价格",
+        value: |result| Cell::from(result.price.as_ref().map(|price| price.display())),
+        always: false,
+    },
+    Column {
+        header: "标记",
+        value: |result| Cell::from(Some(result.tags.join(", ")).filter(|tags| !tags.is_empty())),
+        always: false,
+    },
+    Column {
+        header: "证书透明度",
+        value: |result| Cell::from(result.ct.as_ref().and_then(|ct| ct.display())),
+        always: false,
+    },
+    Column {
+        header: "网站",
+        value: |result| Cell::from(result.http.as_ref().map(|probe| probe.display())),
+        always: false,
+    },
+    Column {
+        header: "注册商",
+        value: |result| Cell::from(result.whois.as_ref().and_then(|whois| whois.registrar.clone())),
+        always: false,
+    },
+    Column {
+        header: "首次注册",
+        value: |result| Cell::from(result.whois.as_ref().and_then(|whois| whois.registered.clone())),
+        always: false,
+    },
+    Column {
+        header: "持有人",
+        value: |result| Cell::from(result.whois.as_ref().and_then(|whois| whois.holder.clone())),
+        always: false,
+    },
+    Column {
+        header: "查询时间",
+        value: |result| Cell::from(result.timestamp.format("%Y-%m-%d %H:%M:%S").to_string()),
+        always: true,
+    },
+    Column {
+        header: "响应",
+        value: |result| Cell::from(result.message.clone()),
+        always: true,
+    },
+];
+
+/// 概览表和每种状态一个工作表，可用域名按评分从高到低排列，其余按域名排列
+///
+/// 超过 Excel 行数上限的状态拆分为多个工作表，如 "可用"、"可用 (2)"。
+fn export(args: &ReportArgs, results: &[DomainResult], path: &Path) -> Result<()> {
+    let clock = Clock::with_timezone(false, args.timezone.as_deref())?;
+    let mut overview = Overview::default();
+    for result in results {
+        overview.add(result);
+    }
+
+    let mut summary = Sheet::new("概览", &["状态", "域名数", "占比"]);
+    let total = overview.counts().total();
+    let first = summary.next_row();
+    for (name, count) in overview.counts().entries() {
+        let share = if total == 0 { 0.0 } else { count as f64 / total as f64 };
+        summary.row(vec![Cell::from(name), Cell::Number(count as f64), Cell::Percent(share)]);
+    }
+    summary.chart(Chart {
+        title: "各状态的域名数".to_string(),
+        rows: (first, summary.next_row() - 1),
+        category_column: 0,
+        value_column: 1,
+        anchor: (6, 0),
+    });
+    summary.row(vec![Cell::from("合计"), Cell::Number(total as f64)]);
+
+    if !overview.lengths().is_empty() {
+        summary.row(Vec::new());
+        summary.header(&["标签长度", "已得出结果", "可用", "可用率"]);
+        let first = summary.next_row();
+        for (length, (checked, available)) in overview.lengths() {
+            summary.row(vec![
+                Cell::from(format!("{} 字符", length)),
+                Cell::Number(*checked as f64),
+                Cell::Number(*available as f64),
+                Cell::Percent(*available as f64 / *checked as f64),
+            ]);
+        }
+        summary.chart(Chart {
+            title: "各标签长度的可用率".to_string(),
+            rows: (first, summary.next_row() - 1),
+            category_column: 0,
+            value_column: 3,
+            anchor: (6, 16),
+        });
+    }
+
+    summary.row(Vec::new());
+    summary.header(&["项目", "内容"]);
+    summary.row(vec![Cell::from("结果目录"), Cell::from(result_dir(&args.dir)?.display().to_string())]);
+    if let Some(condition) = &args.condition {
+        summary.row(vec![Cell::from("筛选条件"), Cell::from(condition.clone())]);
+    }
+    let timestamps = results.iter().map(|result| result.timestamp);
+    if let (Some(earliest), Some(latest)) = (timestamps.clone().min(), timestamps.max()) {
+        summary.row(vec![
+            Cell::from("查询时间"),
+            Cell::from(format!("{} ~ {}", clock.display(&earliest), clock.display(&latest))),
+        ]);
+    }
+    summary.row(vec![
+        Cell::from("导出时间"),
+        Cell::from(clock.display(&clock.now())),
+    ]);
+
+    let columns: Vec<&Column> = COLUMNS
+        .iter()
+        .filter(|column| column.always || results.iter().any(|result| !matches!((column.value)(result), Cell::Empty)))
+        .collect();
+    let headers: Vec<&str> = columns.iter().map(|column| column.header).collect();
+    let mut workbook = Workbook::create(path)?;
+    workbook.add(&summary)?;
+    for status in [
+        DomainStatus::Available,
+        DomainStatus::Registered,
+        DomainStatus::RateLimited,
+        DomainStatus::Error,
+    ] {
+        let mut matched: Vec<&DomainResult> = results.iter().filter(|result| result.status == status).collect();
+        if matched.is_empty() {
+            continue;
+        }
+        match status {
+            DomainStatus::Available => matched.sort_by(|a, b| {
+                score::score(&b.domain)
+                    .cmp(&score::score(&a.domain))
+                    .then_with(|| a.domain.cmp(&b.domain))
+            }),
+            _ => matched.sort_by(|a, b| a.domain.cmp(&b.domain)),
+        }
+        let label = status_label(filter::status_name(&status));
+        for (index, part) in matched.chunks(xlsx::MAX_ROWS - 1).enumerate() {
+            let name = match index {
+                0 => label.to_string(),
+                index => format!("{} ({})", label, index + 1),
+            };
+            let rows = part
+                .iter()
+                .map(|result| columns.iter().map(|column| (column.value)(result)).collect());
+            workbook.stream(&name, &headers, rows)?;
+        }
+    }
+    workbook.finish()
+}
@@ -0,0 +1,170 @@
+//! 扫描结束后的交互式筛选
+//!
+//! 列出可用域名及其评分，输入字符按子序列模糊过滤，标记的域名由调用方导出到
+//! shortlist.txt 或交给注册商接口。
+
+use anyhow::{Context, Result};
+use console::{style, Key, Term};
+
+use crate::score;
+
+/// 一屏最多显示的候选数
+const PAGE_SIZE: usize = 15;
+
+struct Candidate {
+    domain: String,
+    score: u32,
+    marked: bool,
+}
+
+/// 筛选状态
+struct Picker {
+    candidates: Vec<Candidate>,
+    query: String,
+    /// 匹配当前过滤条件的候选下标，按评分从高到低排列
+    visible: Vec<usize>,
+    cursor: usize,
+}
+
+impl Picker {
+    fn new(domains: &[String]) -> Self {
+        let mut candidates: Vec<Candidate> = domains
+            .iter()
+            .map(|domain| Candidate {
+                score: score::score(domain),
+                domain: domain.clone(),
+                marked: false,
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.domain.cmp(&b.domain)));
+        let mut picker = Self {
+            candidates,
+            query: String::new(),
+            visible: Vec::new(),
+            cursor: 0,
+        };
+        picker.refilter();
+        picker
+    }
+
+    fn refilter(&mut self) {
+        self.visible = (0..self.candidates.len())
+            .filter(|&index| fuzzy_match(&self.query, &self.candidates[index].domain))
+            .collect();
+        self.cursor = self.cursor.min(self.visible.len().saturating_sub(1));
+    }
+
+    fn toggle(&mut self) {
+        if let Some(&index) = self.visible.get(self.cursor) {
+            self.candidates[index].marked ^= true;
+        }
+    }
+
+    fn marked(&self) -> Vec<String> {
+        self.candidates
+            .iter()
+            .filter(|candidate| candidate.marked)
+            .map(|candidate| candidate.domain.clone())
+            .collect()
+    }
+
+    /// 当前画面的各行
+    fn render(&self) -> Vec<String> {
+        let marked = self.candidates.iter().filter(|c| c.marked).count();
+        let mut lines = vec![format!(
+            "筛选: {}_  ({}/{} 个, 已标记 {})",
+            self.query,
+            self.visible.len(),
+            self.candidates.len(),
+            marked
+        )];
+
+        let start = (self.cursor / PAGE_SIZE) * PAGE_SIZE;
+        for (offset, &index) in self.visible.iter().skip(start).take(PAGE_SIZE).enumerate() {
+            let candidate = &self.candidates[index];
+            let label = candidate.domain.split('.').next().unwrap_or(&candidate.domain);
+            let line = format!(
+                "{} [{}] {:<20} 评分 {:>3}  {} 字符",
+                if start + offset == self.cursor { ">" } else { " " },
+                if candidate.marked { "x" } else { " " },
+                candidate.domain,
+                candidate.score,
+                label.chars().count()
+            );
+            lines.push(if start + offset == self.cursor {
+                style(line).cyan().bold().to_string()
+            } else {
+                line
+            });
+        }
+
+        lines.push(
+            style("↑/↓ 移动  空格/Tab 标记  输入字符过滤  Enter 完成  Esc 取消")
+                .dim()
+                .to_string(),
+        );
+        lines
+    }
+}
+
+/// 子序列模糊匹配，空查询匹配所有域名
+fn fuzzy_match(query: &str, domain: &str) -> bool {
+    let mut chars = domain.chars();
+    query.chars().all(|q| chars.any(|c| c == q))
+}
+
+/// 运行交互式筛选，返回标记的域名，按 Esc 取消时返回 `None`
+///
+/// 需要在终端中运行，会阻塞当前线程直到用户完成选择。
+pub fn pick(domains: &[String]) -> Result<Option<Vec<String>>> {
+    let term = Term::stderr();
+    anyhow::ensure!(term.is_term(), "Interactive review requires a terminal");
+
+    let mut picker = Picker::new(domains);
+    let mut drawn = 0;
+    term.hide_cursor()?;
+    let outcome = loop {
+        term.clear_last_lines(drawn)?;
+        let lines = picker.render();
+        for line in &lines {
+            term.write_line(line)?;
+        }
+        drawn = lines.len();
+
+        match term.read_key().context("Failed to read key")? {
+            Key::ArrowUp => picker.cursor = picker.cursor.saturating_sub(1),
+            Key::ArrowDown => {
+                picker.cursor = (picker.cursor + 1).min(picker.visible.len().saturating_sub(1))
+            }
+            Key::PageUp => picker.cursor = picker.cursor.saturating_sub(PAGE_SIZE),
+            Key::PageDown => {
+                picker.cursor = (picker.cursor + PAGE_SIZE).min(picker.visible.len().saturating_sub(1))
+            }
+            Key::Char(' ') | Key::Tab => picker.toggle(),
+            Key::Backspace => {
+                picker.query.pop();
+                picker.refilter();
+            }
+            Key::Char(c) if c.is_ascii_alphanumeric() || c == '-' || c == '.' => {
+                picker.query.push(c.to_ascii_lowercase());
+                picker.cursor = 0;
+                picker.refilter();
+            }
+            Key::Enter => break Some(picker.marked()),
+            Key::Escape => break None,
+            _ => {}
+        }
+    };
+    term.clear_last_lines(drawn)?;
+    term.show_cursor()?;
+    Ok(outcome)
+}
+
+/// 询问是否继续，默认为否
+#[cfg(feature = "registrar")]
+pub fn confirm(prompt: &str) -> Result<bool> {
+    let term = Term::stderr();
+    term.write_str(&format!("{} [y/N] ", prompt))?;
+    let answer = term.read_line()?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
@@ -0,0 +1,38 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// 全局伪随机数状态(xorshift64*)，0 表示尚未播种
+static STATE: AtomicU64 = AtomicU64::new(0);
+
+/// 返回一个伪随机的 u64
+///
+/// 只用于抖动和标识符之类不需要密码学强度的场合。
+pub fn next_u64() -> u64 {
+    let mut current = STATE.load(Ordering::Relaxed);
+    loop {
+        let seed = if current == 0 { seed() } else { current };
+        let mut x = seed;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        match STATE.compare_exchange_weak(current, x, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => return x.wrapping_mul(0x2545_F491_4F6C_DD1D),
+            Err(actual) => current = actual,
+        }
+    }
+}
+
+/// 返回 [0, 1) 区间内的伪随机数
+pub fn next_f64() -> f64 {
+    (next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    (nanos ^ ((std::process::id() as u64) << 32)) | 1
+}
@@ -0,0 +1,206 @@
+//! 各 TLD 的标签规则
+//!
+//! 不同注册局对可注册的标签要求不同(最短长度、是否允许数字和连字符、是否接受 IDN)。
+//! 内置表给出已知 TLD 的规则，配置文件的 `[label_rules.<tld>]` 可以覆盖其中任意一项：
+//!
+//! ```toml
+//! [label_rules.eu]
+//! min_length = 2
+//! reserved_prefixes = ["eu-"]
+//! ```
+//!
+//! 生成的候选和流式输入在查询前按各自 TLD 的规则检查，不可注册的域名不查询。`xn--` 开头但
+//! 不是合法 IDN 的标签总被跳过；注册局保留的标签、单个字母和保留的前缀在使用
+//! `--include-reserved` 时照常查询。
+
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// 标签的最大长度(DNS 限制)
+const MAX_LABEL_LENGTH: usize = 63;
+
+/// SWITCH(.ch / .li)保留的标签：注册局自身使用的技术名称和 RFC 2606 的特殊用途名称
+const SWITCH_RESERVED: &[&str] = &["nic", "whois", "www", "dns", "example", "invalid", "localhost", "test"];
+
+/// 一个 TLD 下可注册标签的规则
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelRules {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub digits: bool,
+    pub hyphens: bool,
+    /// 是否接受 IDN 的 A-label(`xn--` 开头)
+    pub idn: bool,
+    /// 第 3、4 位为 `--` 的标签是否保留(`xn--` 除外)
+    pub reserved_hyphens: bool,
+    /// 不可注册的前缀
+    pub reserved_prefixes: Vec<String>,
+    /// 单个字母(a-z)的标签是否可以注册
+    pub single_letters: bool,
+    /// 注册局保留、不可注册的标签
+    pub reserved_labels: Vec<String>,
+}
+
+impl Default for LabelRules {
+    fn default() -> Self {
+        Self {
+            min_length: 1,
+            max_length: MAX_LABEL_LENGTH,
+            digits: true,
+            hyphens: true,
+            idn: true,
+            reserved_hyphens: true,
+            reserved_prefixes: Vec::new(),
+            single_letters: true,
+            reserved_labels: Vec::new(),
+        }
+    }
+}
+
+/// 内置的规则，未列出的 TLD 使用默认规则
+fn builtin(tld: &str) -> LabelRules {
+    let defaults = LabelRules::default();
+    match tld {
+        // SWITCH 不分配单个字母的标签
+        "li" | "ch" => LabelRules {
+            single_letters: false,
+            reserved_labels: SWITCH_RESERVED.iter().map(|label| label.to_string()).collect(),
+            ..defaults
+        },
+        "de" => defaults,
+        "eu" => LabelRules {
+            min_length: 2,
+            ..defaults
+        },
+        _ => defaults,
+    }
+}
+
+/// 配置文件中对某个 TLD 规则的覆盖，未设置的项沿用内置规则
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RulesOverride {
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub digits: Option<bool>,
+    pub hyphens: Option<bool>,
+    pub idn: Option<bool>,
+    pub reserved_hyphens: Option<bool>,
+    pub reserved_prefixes: Option<Vec<String>>,
+    pub single_letters: Option<bool>,
+    pub reserved_labels: Option<Vec<String>>,
+}
+
+impl LabelRules {
+    fn apply(mut self, tld: &str, changes: &RulesOverride) -> Result<Self> {
+        self.min_length = changes.min_length.unwrap_or(self.min_length);
+        self.max_length = changes.max_length.unwrap_or(self.max_length);
+        self.digits = changes.digits.unwrap_or(self.digits);
+        self.hyphens = changes.hyphens.unwrap_or(self.hyphens);
+        self.idn = changes.idn.unwrap_or(self.idn);
+        self.reserved_hyphens = changes.reserved_hyphens.unwrap_or(self.reserved_hyphens);
+        if let Some(prefixes) = &changes.reserved_prefixes {
+            self.reserved_prefixes = prefixes.iter().map(|prefix| prefix.to_ascii_lowercase()).collect();
+        }
+        self.single_letters = changes.single_letters.unwrap_or(self.single_letters);
+        if let Some(labels) = &changes.reserved_labels {
+            self.reserved_labels = labels.iter().map(|label| label.to_ascii_lowercase()).collect();
+        }
+        anyhow::ensure!(
+            1 <= self.min_length && self.min_length <= self.max_length && self.max_length <= MAX_LABEL_LENGTH,
+            "label_rules.{}: lengths must satisfy 1 <= min_length <= max_length <= {}",
+            tld,
+            MAX_LABEL_LENGTH
+        );
+        Ok(self)
+    }
+
+    /// 去掉所有保留项，只保留长度、字符和 IDN 的规则
+    fn include_reserved(self) -> Self {
+        Self {
+            reserved_hyphens: false,
+            reserved_prefixes: Vec::new(),
+            single_letters: true,
+            reserved_labels: Vec::new(),
+            ..self
+        }
+    }
+
+    /// 标签(小写)是否可以注册
+    pub fn allows(&self, label: &str) -> bool {
+        if !(self.min_length..=self.max_length).contains(&label.len())
+            || label.starts_with('-')
+            || label.ends_with('-')
+        {
+            return false;
+        }
+        let chars_allowed = label.bytes().all(|b| {
+            b.is_ascii_lowercase() || (self.digits && b.is_ascii_digit()) || (self.hyphens && b == b'-')
+        });
+        if !chars_allowed {
+            return false;
+        }
+        if label.starts_with("xn--") {
+            // 不能解码的 A-label 不是 IDN，注册局不会接受
+            if !self.idn || crate::idna::to_ascii(label).is_err() {
+                return false;
+            }
+        } else if self.reserved_hyphens && label.get(2..4) == Some("--") {
+            return false;
+        }
+        if !self.single_letters && label.len() == 1 && label.as_bytes()[0].is_ascii_lowercase() {
+            return false;
+        }
+        !self.reserved_prefixes.iter().any(|prefix| label.starts_with(prefix.as_str()))
+            && !self.reserved_labels.iter().any(|reserved| reserved == label)
+    }
+}
+
+/// 待扫描各 TLD 的规则
+#[derive(Debug)]
+pub struct TldRules {
+    rules: HashMap<String, LabelRules>,
+    include_reserved: bool,
+}
+
+impl TldRules {
+    /// 合并内置规则和配置文件中的覆盖，include_reserved 时不跳过保留的标签
+    pub fn new(tlds: &[String], overrides: &BTreeMap<String, RulesOverride>, include_reserved: bool) -> Result<Self> {
+        let mut rules = HashMap::new();
+        for tld in tlds {
+            let base = builtin(tld);
+            let mut merged = match overrides.get(tld) {
+                Some(changes) => base.apply(tld, changes)?,
+                None => base,
+            };
+            if include_reserved {
+                merged = merged.include_reserved();
+            }
+            rules.insert(tld.clone(), merged);
+        }
+        for tld in overrides.keys() {
+            if !rules.contains_key(tld) {
+                eprintln!("提示: 配置文件中 label_rules.{} 对应的 TLD 不在 --tlds 中，已忽略", tld);
+            }
+        }
+        Ok(Self { rules, include_reserved })
+    }
+
+    /// 标签在该 TLD 下是否可以注册
+    pub fn allows_label(&self, label: &str, tld: &str) -> bool {
+        match self.rules.get(tld) {
+            Some(rules) => rules.allows(label),
+            None if self.include_reserved => builtin(tld).include_reserved().allows(label),
+            None => builtin(tld).allows(label),
+        }
+    }
+
+    /// 完整域名(`label.tld`)是否可以注册
+    pub fn allows(&self, domain: &str) -> bool {
+        match domain.split_once('.') {
+            Some((label, tld)) => self.allows_label(label, tld),
+            None => false,
+        }
+    }
+}
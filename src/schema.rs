@@ -0,0 +1,53 @@
+use anyhow::{Context, Result};
+use clap::{Args as ClapArgs, ValueEnum};
+use std::{fs, path::PathBuf};
+
+/// 输出格式的版本号，结果或摘要的字段有不兼容变化(删除、改名、改变含义)时递增，
+/// 只新增字段时不变
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// 随 crate 发布的 JSON Schema 定义
+const SCAN_RESULT_SCHEMA: &str = include_str!("../schema/scan_result.schema.json");
+const SUMMARY_SCHEMA: &str = include_str!("../schema/summary.schema.json");
+
+/// 有 schema 定义的输出文件
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum SchemaKind {
+    /// scan_results.json / scan_results.ndjson 中的每条结果
+    Result,
+    /// summary.json
+    Summary,
+}
+
+/// schema 子命令参数
+#[derive(ClapArgs, Debug)]
+pub struct SchemaArgs {
+    /// 要输出的 schema
+    #[arg(value_enum, default_value = "result")]
+    kind: SchemaKind,
+
+    /// 写入该文件而不是标准输出
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+/// 输出 JSON Schema 定义
+pub fn run(args: SchemaArgs) -> Result<()> {
+    let schema = match args.kind {
+        SchemaKind::Result => SCAN_RESULT_SCHEMA,
+        SchemaKind::Summary => SUMMARY_SCHEMA,
+    };
+    match &args.output {
+        Some(path) => fs::write(path, schema)
+            .with_context(|| format!("Failed to write {}", path.display())),
+        None => {
+            print!("{}", schema);
+            Ok(())
+        }
+    }
+}
+
+/// 读取旧版本写入、没有 schema_version 字段的结果时使用当前版本，重新写出时已是当前格式
+pub fn current() -> u32 {
+    SCHEMA_VERSION
+}
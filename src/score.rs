@@ -0,0 +1,54 @@
+//! 域名价值评分
+//!
+//! 按经验为标签打 0-100 分：越短越好，纯字母优于含数字，连字符扣分，
+//! 辅音元音交替(易读)或由重复字符组成的标签加分。
+
+const VOWELS: &[char] = &['a', 'e', 'i', 'o', 'u', 'y'];
+
+/// 为完整域名(如 `abc.li`)或标签打分，只看第一个点之前的标签
+pub fn score(domain: &str) -> u32 {
+    let label = domain.split('.').next().unwrap_or(domain);
+    let chars: Vec<char> = label.chars().collect();
+    if chars.is_empty() {
+        return 0;
+    }
+
+    let mut score: i32 = match chars.len() {
+        1 => 100,
+        2 => 90,
+        3 => 75,
+        4 => 60,
+        5 => 45,
+        6 => 35,
+        len => (35 - 3 * (len as i32 - 6)).max(10),
+    };
+
+    let digits = chars.iter().filter(|c| c.is_ascii_digit()).count();
+    if digits == chars.len() {
+        score -= 5;
+    } else if digits > 0 {
+        score -= 15;
+    }
+    if chars.contains(&'-') {
+        score -= 25;
+    }
+
+    if chars.iter().all(|&c| c == chars[0]) && chars.len() > 1 {
+        score += 15;
+    } else if chars.len() >= 3 && is_alternating(&chars) {
+        score += 10;
+    }
+    if chars.len() >= 3 && chars.iter().eq(chars.iter().rev()) {
+        score += 5;
+    }
+
+    score.clamp(0, 100) as u32
+}
+
+/// 纯字母且辅音元音交替
+fn is_alternating(chars: &[char]) -> bool {
+    chars.iter().all(|c| c.is_ascii_alphabetic())
+        && chars
+            .windows(2)
+            .all(|pair| VOWELS.contains(&pair[0]) != VOWELS.contains(&pair[1]))
+}
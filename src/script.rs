@@ -0,0 +1,1130 @@
+//! 脚本钩子
+//!
+//! `--script` 加载的脚本使用 Rhai 语法的一个子集，可以定义以下两个函数(可只定义其一)：
+//!
+//! ```text
+//! // 返回 false 的候选域名不查询
+//! fn filter(domain) {
+//!     !domain.contains("-")
+//! }
+//!
+//! // 每个可用域名调用一次，返回字符串时在终端中显示
+//! fn on_available(result) {
+//!     let bonus = if result.label.len() <= 3 { 20 } else { 0 };
+//!     if score(result.domain) + bonus >= 90 {
+//!         return "值得关注: " + result.domain;
+//!     }
+//! }
+//! ```
+//!
+//! 支持 `let`、赋值(`=` `+=` `-=`)、`if`/`else`、`while`、`for x in ...`、`return`、
+//! 自定义函数和递归；值有数字、字符串、布尔值、数组和 `()`。`result` 的字段与结果文件
+//! 相同，另有 `label`、`tld` 和 `score`，`status` 为小写；不存在的字段为 `()`，与它的
+//! 大小比较都不成立。内置函数 `print(x)` 在终端中显示(只在 `on_available` 中)，
+//! `score(domain)` 为内置评分。每次调用最多执行固定数量的步骤，脚本出错后给出一次提示，
+//! 之后不再调用。
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{filter, score, DomainResult};
+
+/// 每次调用最多执行的步骤数
+const MAX_STEPS: usize = 100_000;
+/// 函数调用的最大嵌套深度
+const MAX_DEPTH: usize = 64;
+
+/// 已加载的脚本
+pub struct Script {
+    name: String,
+    functions: HashMap<String, Function>,
+    /// 出错后置为 true，之后不再调用
+    failed: AtomicBool,
+}
+
+impl Script {
+    /// 读取并解析脚本
+    pub fn load(path: &Path) -> Result<Self> {
+        let source =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read script {}", path.display()))?;
+        let functions = parse(&source).with_context(|| format!("Failed to parse script {}", path.display()))?;
+        anyhow::ensure!(
+            functions.contains_key("filter") || functions.contains_key("on_available"),
+            "Script {} defines neither `filter` nor `on_available`",
+            path.display()
+        );
+        for hook in ["filter", "on_available"] {
+            if let Some(function) = functions.get(hook) {
+                anyhow::ensure!(function.params.len() == 1, "`{}` in the script must take one parameter", hook);
+            }
+        }
+        Ok(Self {
+            name: path
+                .file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned(),
+            functions,
+            failed: AtomicBool::new(false),
+        })
+    }
+
+    /// 脚本是否过滤候选域名
+    pub fn filters(&self) -> bool {
+        self.functions.contains_key("filter")
+    }
+
+    /// 是否扫描该域名，脚本出错后不再过滤
+    pub fn keep(&self, domain: &str) -> bool {
+        if !self.filters() {
+            return true;
+        }
+        match self.run("filter", Value::Str(domain.to_string())) {
+            Some((Value::Bool(keep), _)) => keep,
+            Some((other, _)) => {
+                self.fail(anyhow!("`filter` returned {} instead of a boolean", other.type_name()));
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// 对可用域名调用 `on_available`，返回要显示的消息
+    pub fn on_available(&self, result: &DomainResult) -> Vec<String> {
+        if !self.functions.contains_key("on_available") {
+            return Vec::new();
+        }
+        let Some((value, mut output)) = self.run("on_available", result_value(result)) else {
+            return Vec::new();
+        };
+        if let Value::Str(message) = value {
+            output.push(message);
+        }
+        output
+            .into_iter()
+            .map(|line| format!("[{}] {}", self.name, line))
+            .collect()
+    }
+
+    fn run(&self, function: &str, arg: Value) -> Option<(Value, Vec<String>)> {
+        if self.failed.load(Ordering::Relaxed) {
+            return None;
+        }
+        let mut interpreter = Interpreter {
+            functions: &self.functions,
+            steps: 0,
+            depth: 0,
+            output: Vec::new(),
+        };
+        match interpreter.call(function, vec![arg]) {
+            Ok(value) => Some((value, interpreter.output)),
+            Err(Control::Error(e)) => {
+                self.fail(e.context(format!("in `{}`", function)));
+                None
+            }
+            Err(Control::Return(value)) => Some((value, interpreter.output)),
+        }
+    }
+
+    fn fail(&self, error: anyhow::Error) {
+        if !self.failed.swap(true, Ordering::Relaxed) {
+            eprintln!("警告: 脚本 {} 出错，之后不再调用: {:#}", self.name, error);
+        }
+    }
+}
+
+/// 传给 `on_available` 的结果
+fn result_value(result: &DomainResult) -> Value {
+    let mut fields = match serde_json::to_value(result).map(Value::from_json) {
+        Ok(Value::Map(fields)) => fields,
+        _ => BTreeMap::new(),
+    };
+    let (label, tld) = result.domain.split_once('.').unwrap_or((&result.domain, ""));
+    fields.insert("label".to_string(), Value::Str(label.to_string()));
+    fields.insert("tld".to_string(), Value::Str(tld.to_string()));
+    fields.insert("status".to_string(), Value::Str(filter::status_name(&result.status).to_string()));
+    fields.insert("score".to_string(), Value::Num(score::score(&result.domain) as f64));
+    Value::Map(fields)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Unit,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+    Array(Vec<Value>),
+    Map(BTreeMap<String, Value>),
+}
+
+impl Value {
+    fn from_json(json: serde_json::Value) -> Self {
+        match json {
+            serde_json::Value::Null => Self::Unit,
+            serde_json::Value::Bool(b) => Self::Bool(b),
+            serde_json::Value::Number(n) => Self::Num(n.as_f64().unwrap_or(f64::NAN)),
+            serde_json::Value::String(s) => Self::Str(s),
+            serde_json::Value::Array(items) => Self::Array(items.into_iter().map(Self::from_json).collect()),
+            serde_json::Value::Object(fields) => {
+                Self::Map(fields.into_iter().map(|(key, value)| (key, Self::from_json(value))).collect())
+            }
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            Self::Unit => "()",
+            Self::Bool(_) => "bool",
+            Self::Num(_) => "number",
+            Self::Str(_) => "string",
+            Self::Array(_) => "array",
+            Self::Map(_) => "map",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unit => write!(f, "()"),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Num(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Self::Num(n) => write!(f, "{}", n),
+            Self::Str(s) => write!(f, "{}", s),
+            Self::Array(items) => {
+                let items: Vec<String> = items.iter().map(Value::to_string).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+            Self::Map(fields) => {
+                let fields: Vec<String> = fields.iter().map(|(key, value)| format!("{}: {}", key, value)).collect();
+                write!(f, "#{{{}}}", fields.join(", "))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Num(f64),
+    Str(String),
+    Punct(&'static str),
+}
+
+/// 多字符的符号在前，优先匹配
+const PUNCTS: &[&str] = &[
+    "==", "!=", "<=", ">=", "&&", "||", "+=", "-=", "+", "-", "*", "/", "%", "<", ">", "!", "=", "(", ")", "{",
+    "}", "[", "]", ",", ";", ".",
+];
+
+const KEYWORDS: &[&str] = &["fn", "let", "if", "else", "while", "for", "in", "return", "true", "false"];
+
+/// 分解为记号及其所在行号
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let (mut i, mut line) = (0, 1);
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            let start = line;
+            i += 2;
+            loop {
+                match chars.get(i) {
+                    None => bail!("line {}: unterminated comment", start),
+                    Some('*') if chars.get(i + 1) == Some(&'/') => break,
+                    Some('\n') => line += 1,
+                    _ => {}
+                }
+                i += 1;
+            }
+            i += 2;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_digit()
+                    || chars[i] == '_'
+                    || (chars[i] == '.' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit())))
+            {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().filter(|&&c| c != '_').collect();
+            let number = text.parse().map_err(|_| anyhow!("line {}: invalid number `{}`", line, text))?;
+            tokens.push((Token::Num(number), line));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((Token::Ident(chars[start..i].iter().collect()), line));
+        } else if c == '"' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None | Some('\n') => bail!("line {}: unterminated string", line),
+                    Some('"') => break,
+                    Some('\\') => {
+                        i += 1;
+                        text.push(match chars.get(i) {
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            Some('"') => '"',
+                            Some('\\') => '\\',
+                            other => bail!("line {}: invalid escape `\\{}`", line, other.copied().unwrap_or(' ')),
+                        });
+                    }
+                    Some(&c) => text.push(c),
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push((Token::Str(text), line));
+        } else {
+            let punct = PUNCTS
+                .iter()
+                .find(|punct| punct.chars().enumerate().all(|(offset, p)| chars.get(i + offset) == Some(&p)))
+                .ok_or_else(|| anyhow!("line {}: unexpected character `{}`", line, c))?;
+            i += punct.len();
+            tokens.push((Token::Punct(punct), line));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Function {
+    params: Vec<String>,
+    body: Block,
+}
+
+/// 花括号中的语句，最后一个不以分号结尾的表达式为块的值
+struct Block {
+    stmts: Vec<Stmt>,
+    tail: Option<Box<Expr>>,
+}
+
+enum Stmt {
+    Let(String, Expr),
+    Assign(String, Option<BinOp>, Expr),
+    Expr(Expr),
+    While(Expr, Block),
+    For(String, Expr, Block),
+    Return(Option<Expr>),
+}
+
+enum Expr {
+    Literal(Value),
+    Var(String),
+    Array(Vec<Expr>),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    Method(Box<Expr>, String, Vec<Expr>),
+    Property(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    If(Box<Expr>, Block, Option<Block>),
+    Block(Block),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl BinOp {
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        }
+    }
+}
+
+/// 解析脚本中的函数定义
+fn parse(source: &str) -> Result<HashMap<String, Function>> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        pos: 0,
+    };
+    let mut functions = HashMap::new();
+    while parser.pos < parser.tokens.len() {
+        if !parser.eat_keyword("fn") {
+            bail!("line {}: expected `fn`, only function definitions are allowed at the top level", parser.line());
+        }
+        let line = parser.line();
+        let name = parser.ident()?;
+        parser.expect("(")?;
+        let mut params = Vec::new();
+        while !parser.eat(")") {
+            if !params.is_empty() {
+                parser.expect(",")?;
+            }
+            params.push(parser.ident()?);
+        }
+        let body = parser.block()?;
+        if functions.insert(name.clone(), Function { params, body }).is_some() {
+            bail!("line {}: function `{}` is defined twice", line, name);
+        }
+    }
+    Ok(functions)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(1, |&(_, line)| line)
+    }
+
+    fn eat(&mut self, punct: &'static str) -> bool {
+        if self.peek() == Some(&Token::Punct(punct)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: &'static str) -> Result<()> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            bail!("line {}: expected `{}`, found {}", self.line(), punct, self.describe())
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(name)) if name == keyword)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.is_keyword(keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.peek() {
+            Some(Token::Ident(name)) if !KEYWORDS.contains(&name.as_str()) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => bail!("line {}: expected a name, found {}", self.line(), self.describe()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self.peek() {
+            None => "end of script".to_string(),
+            Some(Token::Ident(name)) => format!("`{}`", name),
+            Some(Token::Num(n)) => format!("`{}`", n),
+            Some(Token::Str(s)) => format!("\"{}\"", s),
+            Some(Token::Punct(p)) => format!("`{}`", p),
+        }
+    }
+
+    fn block(&mut self) -> Result<Block> {
+        self.expect("{")?;
+        let mut stmts = Vec::new();
+        let mut tail = None;
+        while !self.eat("}") {
+            if self.peek().is_none() {
+                bail!("line {}: expected `}}`", self.line());
+            }
+            if tail.is_some() {
+                bail!("line {}: expected `;`", self.line());
+            }
+            if self.eat_keyword("let") {
+                let name = self.ident()?;
+                let value = if self.eat("=") { self.expr()? } else { Expr::Literal(Value::Unit) };
+                self.expect(";")?;
+                stmts.push(Stmt::Let(name, value));
+            } else if self.eat_keyword("while") {
+                let condition = self.expr()?;
+                stmts.push(Stmt::While(condition, self.block()?));
+            } else if self.eat_keyword("for") {
+                let name = self.ident()?;
+                if !self.eat_keyword("in") {
+                    bail!("line {}: expected `in`", self.line());
+                }
+                let items = self.expr()?;
+                stmts.push(Stmt::For(name, items, self.block()?));
+            } else if self.eat_keyword("return") {
+                let value = if self.eat(";") {
+                    None
+                } else {
+                    let value = self.expr()?;
+                    self.expect(";")?;
+                    Some(value)
+                };
+                stmts.push(Stmt::Return(value));
+            } else if let Some(op) = self.assignment() {
+                let name = self.ident()?;
+                self.pos += 1;
+                let value = self.expr()?;
+                self.expect(";")?;
+                stmts.push(Stmt::Assign(name, op, value));
+            } else {
+                let expr = self.expr()?;
+                if self.eat(";") {
+                    stmts.push(Stmt::Expr(expr));
+                } else if matches!(expr, Expr::If(..) | Expr::Block(_)) && !matches!(self.peek(), Some(Token::Punct("}"))) {
+                    // if 和块作为语句时不需要分号
+                    stmts.push(Stmt::Expr(expr));
+                } else {
+                    tail = Some(Box::new(expr));
+                }
+            }
+        }
+        Ok(Block { stmts, tail })
+    }
+
+    /// 当前位置是否为赋值语句，返回复合赋值的运算符
+    fn assignment(&self) -> Option<Option<BinOp>> {
+        let Some(Token::Ident(name)) = self.peek() else {
+            return None;
+        };
+        if KEYWORDS.contains(&name.as_str()) {
+            return None;
+        }
+        match self.tokens.get(self.pos + 1).map(|(token, _)| token) {
+            Some(Token::Punct("=")) => Some(None),
+            Some(Token::Punct("+=")) => Some(Some(BinOp::Add)),
+            Some(Token::Punct("-=")) => Some(Some(BinOp::Sub)),
+            _ => None,
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let mut left = self.and_expr()?;
+        while self.eat("||") {
+            left = Expr::Or(Box::new(left), Box::new(self.and_expr()?));
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr> {
+        let mut left = self.binary(0)?;
+        while self.eat("&&") {
+            left = Expr::And(Box::new(left), Box::new(self.binary(0)?));
+        }
+        Ok(left)
+    }
+
+    /// 按优先级从低到高：相等、比较、加减、乘除
+    fn binary(&mut self, level: usize) -> Result<Expr> {
+        const LEVELS: &[&[(&str, BinOp)]] = &[
+            &[("==", BinOp::Eq), ("!=", BinOp::Ne)],
+            &[("<=", BinOp::Le), (">=", BinOp::Ge), ("<", BinOp::Lt), (">", BinOp::Gt)],
+            &[("+", BinOp::Add), ("-", BinOp::Sub)],
+            &[("*", BinOp::Mul), ("/", BinOp::Div), ("%", BinOp::Rem)],
+        ];
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+        let mut left = self.binary(level + 1)?;
+        'operators: loop {
+            for &(punct, op) in LEVELS[level] {
+                if self.eat(punct) {
+                    left = Expr::Binary(op, Box::new(left), Box::new(self.binary(level + 1)?));
+                    continue 'operators;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat("!") {
+            Ok(Expr::Not(Box::new(self.unary()?)))
+        } else if self.eat("-") {
+            Ok(Expr::Neg(Box::new(self.unary()?)))
+        } else {
+            self.postfix()
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Expr> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.eat(".") {
+                let name = self.ident()?;
+                expr = if self.eat("(") {
+                    Expr::Method(Box::new(expr), name, self.args(")")?)
+                } else {
+                    Expr::Property(Box::new(expr), name)
+                };
+            } else if self.eat("[") {
+                let index = self.expr()?;
+                self.expect("]")?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// 逗号分隔的表达式，直到结束符号
+    fn args(&mut self, close: &'static str) -> Result<Vec<Expr>> {
+        let mut args = Vec::new();
+        while !self.eat(close) {
+            if !args.is_empty() {
+                self.expect(",")?;
+            }
+            args.push(self.expr()?);
+        }
+        Ok(args)
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        if self.eat_keyword("true") {
+            return Ok(Expr::Literal(Value::Bool(true)));
+        }
+        if self.eat_keyword("false") {
+            return Ok(Expr::Literal(Value::Bool(false)));
+        }
+        if self.eat_keyword("if") {
+            return self.if_expr();
+        }
+        if self.eat("(") {
+            let expr = self.expr()?;
+            self.expect(")")?;
+            return Ok(expr);
+        }
+        if self.eat("[") {
+            return Ok(Expr::Array(self.args("]")?));
+        }
+        if matches!(self.peek(), Some(Token::Punct("{"))) {
+            return Ok(Expr::Block(self.block()?));
+        }
+        match self.peek().cloned() {
+            Some(Token::Num(n)) => {
+                self.pos += 1;
+                Ok(Expr::Literal(Value::Num(n)))
+            }
+            Some(Token::Str(s)) => {
+                self.pos += 1;
+                Ok(Expr::Literal(Value::Str(s)))
+            }
+            Some(Token::Ident(_)) => {
+                let name = self.ident()?;
+                if self.eat("(") {
+                    Ok(Expr::Call(name, self.args(")")?))
+                } else {
+                    Ok(Expr::Var(name))
+                }
+            }
+            _ => bail!("line {}: expected an expression, found {}", self.line(), self.describe()),
+        }
+    }
+
+    /// `if` 之后的部分，`else if` 作为 else 块的值
+    fn if_expr(&mut self) -> Result<Expr> {
+        let condition = self.expr()?;
+        let then = self.block()?;
+        let otherwise = if !self.eat_keyword("else") {
+            None
+        } else if self.eat_keyword("if") {
+            Some(Block {
+                stmts: Vec::new(),
+                tail: Some(Box::new(self.if_expr()?)),
+            })
+        } else {
+            Some(self.block()?)
+        };
+        Ok(Expr::If(Box::new(condition), then, otherwise))
+    }
+}
+
+enum Control {
+    Return(Value),
+    Error(anyhow::Error),
+}
+
+impl From<anyhow::Error> for Control {
+    fn from(error: anyhow::Error) -> Self {
+        Self::Error(error)
+    }
+}
+
+type Flow<T> = std::result::Result<T, Control>;
+
+fn error<T>(message: String) -> Flow<T> {
+    Err(Control::Error(anyhow!(message)))
+}
+
+/// 变量作用域，内层在后
+type Scopes = Vec<HashMap<String, Value>>;
+
+struct Interpreter<'a> {
+    functions: &'a HashMap<String, Function>,
+    steps: usize,
+    depth: usize,
+    /// `print` 的输出
+    output: Vec<String>,
+}
+
+impl Interpreter<'_> {
+    fn step(&mut self) -> Flow<()> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return error(format!("exceeded {} steps", MAX_STEPS));
+        }
+        Ok(())
+    }
+
+    fn call(&mut self, name: &str, args: Vec<Value>) -> Flow<Value> {
+        if let Some(function) = self.functions.get(name) {
+            if args.len() != function.params.len() {
+                return error(format!(
+                    "`{}` takes {} arguments but {} were given",
+                    name,
+                    function.params.len(),
+                    args.len()
+                ));
+            }
+            if self.depth >= MAX_DEPTH {
+                return error(format!("calls nested deeper than {}", MAX_DEPTH));
+            }
+            self.depth += 1;
+            let mut scopes = vec![function.params.iter().cloned().zip(args).collect()];
+            let result = self.block(&function.body, &mut scopes);
+            self.depth -= 1;
+            return match result {
+                Err(Control::Return(value)) => Ok(value),
+                other => other,
+            };
+        }
+        match (name, args.as_slice()) {
+            ("print", args) => {
+                let line: Vec<String> = args.iter().map(Value::to_string).collect();
+                self.output.push(line.join(" "));
+                Ok(Value::Unit)
+            }
+            ("score", [Value::Str(domain)]) => Ok(Value::Num(score::score(domain) as f64)),
+            ("score", _) => error("`score` takes one string".to_string()),
+            _ => error(format!("unknown function `{}`", name)),
+        }
+    }
+
+    fn block(&mut self, block: &Block, scopes: &mut Scopes) -> Flow<Value> {
+        scopes.push(HashMap::new());
+        let result = self.block_body(block, scopes);
+        scopes.pop();
+        result
+    }
+
+    fn block_body(&mut self, block: &Block, scopes: &mut Scopes) -> Flow<Value> {
+        for stmt in &block.stmts {
+            self.stmt(stmt, scopes)?;
+        }
+        match &block.tail {
+            Some(expr) => self.eval(expr, scopes),
+            None => Ok(Value::Unit),
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt, scopes: &mut Scopes) -> Flow<()> {
+        match stmt {
+            Stmt::Let(name, expr) => {
+                let value = self.eval(expr, scopes)?;
+                scopes.last_mut().expect("scope").insert(name.clone(), value);
+            }
+            Stmt::Assign(name, op, expr) => {
+                let value = self.eval(expr, scopes)?;
+                let Some(slot) = scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name)) else {
+                    return error(format!("undefined variable `{}`", name));
+                };
+                *slot = match op {
+                    Some(op) => binary(*op, std::mem::replace(slot, Value::Unit), value)?,
+                    None => value,
+                };
+            }
+            Stmt::Expr(expr) => {
+                self.eval(expr, scopes)?;
+            }
+            Stmt::While(condition, body) => loop {
+                self.step()?;
+                if !self.condition(condition, scopes)? {
+                    break;
+                }
+                self.block(body, scopes)?;
+            },
+            Stmt::For(name, items, body) => {
+                let items = match self.eval(items, scopes)? {
+                    Value::Array(items) => items,
+                    Value::Str(s) => s.chars().map(|c| Value::Str(c.to_string())).collect(),
+                    other => return error(format!("cannot iterate over {}", other.type_name())),
+                };
+                for item in items {
+                    self.step()?;
+                    scopes.push(HashMap::from([(name.clone(), item)]));
+                    let result = self.block(body, scopes);
+                    scopes.pop();
+                    result?;
+                }
+            }
+            Stmt::Return(expr) => {
+                let value = match expr {
+                    Some(expr) => self.eval(expr, scopes)?,
+                    None => Value::Unit,
+                };
+                return Err(Control::Return(value));
+            }
+        }
+        Ok(())
+    }
+
+    fn condition(&mut self, expr: &Expr, scopes: &mut Scopes) -> Flow<bool> {
+        match self.eval(expr, scopes)? {
+            Value::Bool(b) => Ok(b),
+            other => error(format!("expected a boolean condition, found {}", other.type_name())),
+        }
+    }
+
+    fn eval(&mut self, expr: &Expr, scopes: &mut Scopes) -> Flow<Value> {
+        self.step()?;
+        match expr {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Var(name) => match scopes.iter().rev().find_map(|scope| scope.get(name)) {
+                Some(value) => Ok(value.clone()),
+                None => error(format!("undefined variable `{}`", name)),
+            },
+            Expr::Array(items) => Ok(Value::Array(
+                items.iter().map(|item| self.eval(item, scopes)).collect::<Flow<_>>()?,
+            )),
+            Expr::Not(expr) => Ok(Value::Bool(!self.condition(expr, scopes)?)),
+            Expr::Neg(expr) => match self.eval(expr, scopes)? {
+                Value::Num(n) => Ok(Value::Num(-n)),
+                other => error(format!("cannot negate {}", other.type_name())),
+            },
+            Expr::And(left, right) => {
+                Ok(Value::Bool(self.condition(left, scopes)? && self.condition(right, scopes)?))
+            }
+            Expr::Or(left, right) => Ok(Value::Bool(self.condition(left, scopes)? || self.condition(right, scopes)?)),
+            Expr::Binary(op, left, right) => {
+                let left = self.eval(left, scopes)?;
+                let right = self.eval(right, scopes)?;
+                binary(*op, left, right)
+            }
+            Expr::Call(name, args) => {
+                let args = args.iter().map(|arg| self.eval(arg, scopes)).collect::<Flow<_>>()?;
+                self.call(name, args)
+            }
+            Expr::Method(target, name, args) => {
+                let target = self.eval(target, scopes)?;
+                let args: Vec<Value> = args.iter().map(|arg| self.eval(arg, scopes)).collect::<Flow<_>>()?;
+                method(target, name, &args)
+            }
+            Expr::Property(target, name) => match self.eval(target, scopes)? {
+                Value::Map(mut fields) => Ok(fields.remove(name).unwrap_or(Value::Unit)),
+                other => error(format!("{} has no property `{}`", other.type_name(), name)),
+            },
+            Expr::Index(target, index) => {
+                let target = self.eval(target, scopes)?;
+                let index = self.eval(index, scopes)?;
+                match (target, index) {
+                    (Value::Array(items), Value::Num(n)) => match position(n, items.len()) {
+                        Some(i) => Ok(items[i].clone()),
+                        None => error(format!("index {} is out of range", n)),
+                    },
+                    (Value::Str(s), Value::Num(n)) => match position(n, s.chars().count()) {
+                        Some(i) => Ok(Value::Str(s.chars().nth(i).expect("in range").to_string())),
+                        None => error(format!("index {} is out of range", n)),
+                    },
+                    (Value::Map(mut fields), Value::Str(key)) => Ok(fields.remove(&key).unwrap_or(Value::Unit)),
+                    (target, index) => {
+                        error(format!("cannot index {} with {}", target.type_name(), index.type_name()))
+                    }
+                }
+            }
+            Expr::If(condition, then, otherwise) => {
+                if self.condition(condition, scopes)? {
+                    self.block(then, scopes)
+                } else if let Some(otherwise) = otherwise {
+                    self.block(otherwise, scopes)
+                } else {
+                    Ok(Value::Unit)
+                }
+            }
+            Expr::Block(block) => self.block(block, scopes),
+        }
+    }
+}
+
+/// 数组或字符串的下标，负数从末尾数起
+fn position(n: f64, len: usize) -> Option<usize> {
+    if n.fract() != 0.0 {
+        return None;
+    }
+    let i = if n < 0.0 { len as f64 + n } else { n };
+    (0.0..len as f64).contains(&i).then_some(i as usize)
+}
+
+fn binary(op: BinOp, left: Value, right: Value) -> Flow<Value> {
+    use std::cmp::Ordering;
+    let ordering = match (&left, &right) {
+        (Value::Num(a), Value::Num(b)) => a.partial_cmp(b),
+        (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+    let compare = |test: fn(Ordering) -> bool| -> Flow<Value> {
+        match (&left, &right, ordering) {
+            (_, _, Some(ordering)) => Ok(Value::Bool(test(ordering))),
+            // 缺少的字段与任何值比较都不成立
+            (Value::Unit, _, _) | (_, Value::Unit, _) => Ok(Value::Bool(false)),
+            (Value::Num(_), Value::Num(_), None) => Ok(Value::Bool(false)),
+            _ => error(format!("cannot compare {} with {}", left.type_name(), right.type_name())),
+        }
+    };
+    match (op, &left, &right) {
+        (BinOp::Eq, _, _) => Ok(Value::Bool(left == right)),
+        (BinOp::Ne, _, _) => Ok(Value::Bool(left != right)),
+        (BinOp::Lt, _, _) => compare(Ordering::is_lt),
+        (BinOp::Le, _, _) => compare(Ordering::is_le),
+        (BinOp::Gt, _, _) => compare(Ordering::is_gt),
+        (BinOp::Ge, _, _) => compare(Ordering::is_ge),
+        (BinOp::Add, Value::Num(a), Value::Num(b)) => Ok(Value::Num(a + b)),
+        (BinOp::Add, Value::Str(a), _) => Ok(Value::Str(format!("{}{}", a, right))),
+        (BinOp::Add, _, Value::Str(b)) => Ok(Value::Str(format!("{}{}", left, b))),
+        (BinOp::Add, Value::Array(a), Value::Array(b)) => {
+            Ok(Value::Array(a.iter().chain(b).cloned().collect()))
+        }
+        (BinOp::Sub, Value::Num(a), Value::Num(b)) => Ok(Value::Num(a - b)),
+        (BinOp::Mul, Value::Num(a), Value::Num(b)) => Ok(Value::Num(a * b)),
+        (BinOp::Div | BinOp::Rem, Value::Num(_), Value::Num(b)) if *b == 0.0 => error("division by zero".to_string()),
+        (BinOp::Div, Value::Num(a), Value::Num(b)) => Ok(Value::Num(a / b)),
+        (BinOp::Rem, Value::Num(a), Value::Num(b)) => Ok(Value::Num(a % b)),
+        _ => error(format!(
+            "cannot apply `{}` to {} and {}",
+            op.symbol(),
+            left.type_name(),
+            right.type_name()
+        )),
+    }
+}
+
+fn method(target: Value, name: &str, args: &[Value]) -> Flow<Value> {
+    let string_arg = |index: usize| -> Flow<&str> {
+        match args.get(index) {
+            Some(Value::Str(s)) => Ok(s),
+            _ => error(format!("`{}` expects a string argument", name)),
+        }
+    };
+    let value = match (&target, name) {
+        (Value::Str(s), "len") => Value::Num(s.chars().count() as f64),
+        (Value::Str(s), "is_empty") => Value::Bool(s.is_empty()),
+        (Value::Str(s), "contains") => Value::Bool(s.contains(string_arg(0)?)),
+        (Value::Str(s), "starts_with") => Value::Bool(s.starts_with(string_arg(0)?)),
+        (Value::Str(s), "ends_with") => Value::Bool(s.ends_with(string_arg(0)?)),
+        (Value::Str(s), "to_upper") => Value::Str(s.to_uppercase()),
+        (Value::Str(s), "to_lower") => Value::Str(s.to_lowercase()),
+        (Value::Str(s), "trim") => Value::Str(s.trim().to_string()),
+        (Value::Str(s), "split") => {
+            Value::Array(s.split(string_arg(0)?).map(|part| Value::Str(part.to_string())).collect())
+        }
+        (Value::Str(s), "replace") => Value::Str(s.replace(string_arg(0)?, string_arg(1)?)),
+        (Value::Array(items), "len") => Value::Num(items.len() as f64),
+        (Value::Array(items), "is_empty") => Value::Bool(items.is_empty()),
+        (Value::Array(items), "contains") => match args.first() {
+            Some(item) => Value::Bool(items.contains(item)),
+            None => return error("`contains` expects an argument".to_string()),
+        },
+        (Value::Map(fields), "contains") => Value::Bool(fields.contains_key(string_arg(0)?)),
+        _ => return error(format!("{} has no method `{}`", target.type_name(), name)),
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 运行 `fn main() { ... }`，返回结果或错误信息
+    fn run(body: &str) -> std::result::Result<Value, String> {
+        let functions = parse(&format!("fn main() {{ {} }}", body)).map_err(|e| e.to_string())?;
+        let mut interpreter = Interpreter {
+            functions: &functions,
+            steps: 0,
+            depth: 0,
+            output: Vec::new(),
+        };
+        match interpreter.call("main", Vec::new()) {
+            Ok(value) | Err(Control::Return(value)) => Ok(value),
+            Err(Control::Error(e)) => Err(e.to_string()),
+        }
+    }
+
+    fn string(text: &str) -> Value {
+        Value::Str(text.to_string())
+    }
+
+    #[test]
+    fn tokenizes_literals_and_escapes() {
+        let tokens: Vec<Token> = tokenize("x 1_000 2.5 \"a\\n\\t\\\"\\\\\" >= += // 注释\n/* 多行\n注释 */ y")
+            .unwrap()
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                Token::Ident("x".to_string()),
+                Token::Num(1000.0),
+                Token::Num(2.5),
+                Token::Str("a\n\t\"\\".to_string()),
+                Token::Punct(">="),
+                Token::Punct("+="),
+                Token::Ident("y".to_string()),
+            ]
+        );
+        assert_eq!(tokenize("a\n\n/* x\n */ b").unwrap()[1].1, 4);
+    }
+
+    #[test]
+    fn evaluates_operators_with_precedence() {
+        assert_eq!(run("1 + 2 * 3 - 8 / 4"), Ok(Value::Num(5.0)));
+        assert_eq!(run("(1 + 2) * 3 % 5"), Ok(Value::Num(4.0)));
+        assert_eq!(run("-2 * -3"), Ok(Value::Num(6.0)));
+        assert_eq!(run("1 + 1 == 2 && !(3 < 2) || false"), Ok(Value::Bool(true)));
+        assert_eq!(run("\"ab\" < \"b\""), Ok(Value::Bool(true)));
+        assert_eq!(run("\"n\" + 1 + [2]"), Ok(string("n1[2]")));
+        assert_eq!(run("[1] + [2] == [1, 2]"), Ok(Value::Bool(true)));
+        // 缺少的字段与任何值比较都不成立
+        assert_eq!(run("let missing; missing < 1 || missing >= 1"), Ok(Value::Bool(false)));
+        // 短路求值：右侧不会因类型错误而失败
+        assert_eq!(run("false && 1"), Ok(Value::Bool(false)));
+    }
+
+    #[test]
+    fn evaluates_statements_and_functions() {
+        let source = "
+            fn fib(n) { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }
+            fn main() {
+                let total = 0;
+                for c in \"abc\" { total += 1; }
+                let i = 0;
+                while i < 3 { i = i + 1; }
+                let parts = \"a-b-c\".split(\"-\");
+                if parts.len() != 3 { return false; }
+                print(\"fib\", fib(10), parts[-1]);
+                [total, i, fib(10), parts.contains(\"b\"), \"Ab\".to_lower().starts_with(\"ab\")]
+            }";
+        let functions = parse(source).unwrap();
+        let mut interpreter = Interpreter {
+            functions: &functions,
+            steps: 0,
+            depth: 0,
+            output: Vec::new(),
+        };
+        let Ok(value) = interpreter.call("main", Vec::new()) else {
+            panic!("script failed");
+        };
+        assert_eq!(value.to_string(), "[3, 3, 55, true, true]");
+        assert_eq!(interpreter.output, ["fib 55 c"]);
+    }
+
+    #[test]
+    fn reports_parse_errors() {
+        let error = |source: &str| parse(source).err().map(|e| e.to_string()).unwrap_or_default();
+        assert_eq!(error("fn f() { \"open }"), "line 1: unterminated string");
+        assert_eq!(error("fn f() { \"\\q\" }"), "line 1: invalid escape `\\q`");
+        assert_eq!(error("fn f() {\n/* open"), "line 2: unterminated comment");
+        assert_eq!(error("fn f() { 1 $ 2 }"), "line 1: unexpected character `$`");
+        assert_eq!(error("fn f() { (1 + 2 }"), "line 1: expected `)`, found `}`");
+        assert_eq!(error("fn f() {\n  1\n"), "line 2: expected `}`");
+        assert_eq!(error("fn f() { 1 2 }"), "line 1: expected `;`");
+        assert_eq!(error("let x = 1;"), "line 1: expected `fn`, only function definitions are allowed at the top level");
+        assert_eq!(error("fn f() {}\nfn f() {}"), "line 2: function `f` is defined twice");
+        assert_eq!(error("fn f() { let if = 1; }"), "line 1: expected a name, found `if`");
+    }
+
+    #[test]
+    fn reports_runtime_errors() {
+        assert_eq!(run("x"), Err("undefined variable `x`".to_string()));
+        assert_eq!(run("1 / 0"), Err("division by zero".to_string()));
+        assert_eq!(run("[1][1]"), Err("index 1 is out of range".to_string()));
+        assert_eq!(run("if 1 { 2 }"), Err("expected a boolean condition, found number".to_string()));
+        assert_eq!(run("1 < \"a\""), Err("cannot compare number with string".to_string()));
+        assert_eq!(run("true - 1"), Err("cannot apply `-` to bool and number".to_string()));
+        assert_eq!(run("1.len()"), Err("number has no method `len`".to_string()));
+        assert_eq!(run("nope()"), Err("unknown function `nope`".to_string()));
+        assert_eq!(run("while true {}"), Err(format!("exceeded {} steps", MAX_STEPS)));
+    }
+
+    #[test]
+    fn hooks_see_the_result_fields() {
+        let path = std::env::temp_dir().join(format!("script-test-{}.rhai", std::process::id()));
+        std::fs::write(
+            &path,
+            "fn filter(domain) { !domain.contains(\"-\") }\n\
+             fn on_available(result) { \"可用 \" + result.label + \".\" + result.tld + \" \" + result.status }",
+        )
+        .unwrap();
+        let script = Script::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(script.filters());
+        assert!(script.keep("ab.li"));
+        assert!(!script.keep("a-b.li"));
+        let result: DomainResult = serde_json::from_value(serde_json::json!({
+            "domain": "ab.li",
+            "status": "Available",
+            "reply_code": 1,
+            "message": "",
+            "timestamp": "2024-01-01T00:00:00Z",
+        }))
+        .unwrap();
+        let name = path.file_name().unwrap().to_string_lossy();
+        assert_eq!(script.on_available(&result), [format!("[{}] 可用 ab.li available", name)]);
+    }
+}
@@ -0,0 +1,295 @@
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// 扫描过程中的统计信息
+pub struct ScanStats {
+    started: Instant,
+    inner: Mutex<StatsInner>,
+}
+
+#[derive(Default)]
+struct StatsInner {
+    latencies: LatencyHistogram,
+    queries: u64,
+    retries: u64,
+    rate_limited: u64,
+    errors: u64,
+    cache_hits: u64,
+    workers: BTreeMap<usize, WorkerSummary>,
+    concurrency: Option<ConcurrencySummary>,
+}
+
+/// 单个 worker 的统计
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct WorkerSummary {
+    pub worker: usize,
+    pub queries: u64,
+    pub errors: u64,
+    pub rate_limited: u64,
+}
+
+/// --workers auto 的并发上限变化
+#[derive(Debug, Clone, Serialize)]
+pub struct ConcurrencySummary {
+    /// 结束时的并发上限
+    pub limit: usize,
+    pub peak: usize,
+    /// 上限改变的次数
+    pub adjustments: u64,
+}
+
+/// 统计摘要，用于终端输出和 summary.json
+#[derive(Debug, Serialize)]
+pub struct StatsSummary {
+    pub queries: u64,
+    pub duration_secs: f64,
+    pub qps: f64,
+    pub latency_p50_ms: f64,
+    pub latency_p95_ms: f64,
+    pub latency_p99_ms: f64,
+    pub retries: u64,
+    pub rate_limited: u64,
+    pub errors: u64,
+    /// 使用 --cache-ttl 缓存而没有查询的次数
+    pub cache_hits: u64,
+    pub workers: Vec<WorkerSummary>,
+    /// 仅 --workers auto
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<ConcurrencySummary>,
+}
+
+impl ScanStats {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            inner: Mutex::new(StatsInner::default()),
+        }
+    }
+
+    /// 记录一次查询及其耗时
+    pub fn record_query(&self, worker: usize, latency: Duration) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.queries += 1;
+        inner.latencies.record(latency);
+        inner.worker(worker).queries += 1;
+    }
+
+    /// 记录一次失败的查询(连接错误或无法识别的响应)
+    pub fn record_error(&self, worker: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.errors += 1;
+        inner.worker(worker).errors += 1;
+    }
+
+    /// 已发送的查询次数(含重试)
+    pub fn queries(&self) -> u64 {
+        self.inner.lock().unwrap().queries
+    }
+
+    /// 记录一次重试
+    pub fn record_retry(&self) {
+        self.inner.lock().unwrap().retries += 1;
+    }
+
+    /// 记录一次限流响应(-95)
+    pub fn record_rate_limited(&self, worker: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.rate_limited += 1;
+        inner.worker(worker).rate_limited += 1;
+    }
+
+    /// 记录一次缓存命中
+    pub fn record_cache_hit(&self) {
+        self.inner.lock().unwrap().cache_hits += 1;
+    }
+
+    /// 记录 --workers auto 调整后的并发上限，第一次记录为初始上限
+    pub fn record_concurrency(&self, limit: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        match &mut inner.concurrency {
+            Some(concurrency) => {
+                concurrency.limit = limit;
+                concurrency.peak = concurrency.peak.max(limit);
+                concurrency.adjustments += 1;
+            }
+            None => {
+                inner.concurrency = Some(ConcurrencySummary {
+                    limit,
+                    peak: limit,
+                    adjustments: 0,
+                })
+            }
+        }
+    }
+
+    /// 生成统计摘要
+    pub fn summary(&self) -> StatsSummary {
+        let inner = self.inner.lock().unwrap();
+        let duration = self.started.elapsed().as_secs_f64();
+
+        StatsSummary {
+            queries: inner.queries,
+            duration_secs: duration,
+            qps: if duration > 0.0 { inner.queries as f64 / duration } else { 0.0 },
+            latency_p50_ms: inner.latencies.percentile_ms(0.50),
+            latency_p95_ms: inner.latencies.percentile_ms(0.95),
+            latency_p99_ms: inner.latencies.percentile_ms(0.99),
+            retries: inner.retries,
+            rate_limited: inner.rate_limited,
+            errors: inner.errors,
+            cache_hits: inner.cache_hits,
+            workers: inner.workers.values().cloned().collect(),
+            concurrency: inner.concurrency.clone(),
+        }
+    }
+}
+
+impl StatsInner {
+    fn worker(&mut self, worker: usize) -> &mut WorkerSummary {
+        self.workers.entry(worker).or_insert_with(|| WorkerSummary {
+            worker,
+            ..Default::default()
+        })
+    }
+}
+
+impl StatsSummary {
+    /// 终端汇总表中的运行统计
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        let mut rows = vec![
+            ("耗时", format!("{:.1} 秒", self.duration_secs)),
+            ("查询次数", self.queries.to_string()),
+            ("实际速率", format!("{:.2} 次/秒", self.qps)),
+            (
+                "延迟 p50 / p95 / p99",
+                format!(
+                    "{:.0}ms / {:.0}ms / {:.0}ms",
+                    self.latency_p50_ms, self.latency_p95_ms, self.latency_p99_ms
+                ),
+            ),
+            (
+                "重试 / 限流 / 错误",
+                format!("{} / {} / {}", self.retries, self.rate_limited, self.errors),
+            ),
+        ];
+        if self.cache_hits > 0 {
+            rows.push(("缓存命中", self.cache_hits.to_string()));
+        }
+        if let Some(concurrency) = &self.concurrency {
+            rows.push((
+                "自动并发",
+                format!(
+                    "结束时 {}, 最高 {}, 调整 {} 次",
+                    concurrency.limit, concurrency.peak, concurrency.adjustments
+                ),
+            ));
+        }
+        rows
+    }
+
+    /// 只列出遇到过限流或错误的 worker，完整明细见 summary.json
+    pub fn print_troubled_workers(&self) {
+        let troubled: Vec<_> = self
+            .workers
+            .iter()
+            .filter(|w| w.rate_limited > 0 || w.errors > 0)
+            .collect();
+        if !troubled.is_empty() {
+            println!("{}/{} 个 worker 遇到限流或错误:", troubled.len(), self.workers.len());
+            for w in troubled {
+                println!(
+                    "  worker {:>3}: 查询 {} 次, 限流 {} 次, 错误 {} 次",
+                    w.worker, w.queries, w.rate_limited, w.errors
+                );
+            }
+        }
+    }
+}
+
+/// 每个 2 的幂区间再分成的桶数，百分位的相对误差不超过约 9%
+const SUB_BUCKETS: f64 = 8.0;
+/// 覆盖 1µs 到 2^36µs(约 19 小时)，更长的延迟计入最后一个桶
+const BUCKETS: usize = 36 * SUB_BUCKETS as usize + 1;
+
+/// 查询延迟的对数直方图：全量扫描有上千万次查询，只按桶计数，内存占用固定
+struct LatencyHistogram {
+    counts: Vec<u64>,
+    total: u64,
+    max: Duration,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            counts: vec![0; BUCKETS],
+            total: 0,
+            max: Duration::ZERO,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    /// 不足 1µs 的延迟在第 0 个桶，第 i 个桶为 [2^((i-1)/8), 2^(i/8)) 微秒
+    fn bucket(latency: Duration) -> usize {
+        let micros = latency.as_secs_f64() * 1e6;
+        if micros < 1.0 {
+            return 0;
+        }
+        ((micros.log2() * SUB_BUCKETS) as usize + 1).min(BUCKETS - 1)
+    }
+
+    fn record(&mut self, latency: Duration) {
+        self.counts[Self::bucket(latency)] += 1;
+        self.total += 1;
+        self.max = self.max.max(latency);
+    }
+
+    /// 百分位(毫秒)：所在桶的上界，不超过记录到的最大延迟
+    fn percentile_ms(&self, p: f64) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let rank = ((self.total as f64 * p).ceil() as u64).clamp(1, self.total);
+        let mut seen = 0;
+        let bucket = self
+            .counts
+            .iter()
+            .position(|&count| {
+                seen += count;
+                seen >= rank
+            })
+            .unwrap_or(BUCKETS - 1);
+        let upper_micros = (bucket as f64 / SUB_BUCKETS).exp2();
+        (upper_micros / 1000.0).min(self.max.as_secs_f64() * 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_percentiles_stay_within_a_bucket() {
+        let mut histogram = LatencyHistogram::default();
+        assert_eq!(histogram.percentile_ms(0.5), 0.0);
+        // 1ms 到 1000ms 各一次
+        for ms in 1..=1000 {
+            histogram.record(Duration::from_millis(ms));
+        }
+        for (p, exact) in [(0.50, 500.0), (0.95, 950.0), (0.99, 990.0)] {
+            let estimate = histogram.percentile_ms(p);
+            assert!(estimate >= exact && estimate <= exact * 1.1, "p{}: {}", p, estimate);
+        }
+        assert_eq!(histogram.percentile_ms(1.0), 1000.0);
+
+        // 极短和极长的延迟不越界
+        histogram.record(Duration::ZERO);
+        histogram.record(Duration::from_secs(365 * 86400));
+        assert_eq!(histogram.total, 1002);
+        assert!(histogram.percentile_ms(0.0) <= 0.001);
+    }
+}
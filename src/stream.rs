@@ -0,0 +1,360 @@
+//! 持续运行的流式查询模式
+//!
+//! 从标准输入、命名管道或 unix socket 持续读取候选标签(或完整域名)，每行一个，可在逗号后
+//! 附加标记(见 [`crate::tags`])，经与扫描相同的限速和重试逻辑查询后，按完成顺序输出每行一条 JSON 的结果。
+//! 使用 unix socket 时每个连接的结果写回该连接，相当于一个本地的可用性查询服务。
+//! 标准输出只包含结果，提示信息写到标准错误。
+//!
+//! 收到 SIGTERM 或 Ctrl-C 时停止接收输入，等待进行中的查询完成后保存结果再退出；
+//! 可作为 systemd `Type=notify` 服务运行，见 [`crate::systemd`]。
+
+use anyhow::{Context, Result};
+use clap::Args as ClapArgs;
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    sync::mpsc,
+    task::JoinSet,
+};
+
+use crate::{
+    client::WhoisClient,
+    generate::{self, WithTlds},
+    idna, schema, systemd, tags, DomainResult, DomainStatus, ExitCondition, HeldConnection, LiDomainScanner,
+};
+
+/// stream 子命令参数
+#[derive(ClapArgs, Debug)]
+pub struct StreamArgs {
+    /// 从该文件或命名管道读取，命名管道的写入端关闭后重新打开继续等待输入
+    #[arg(long, value_name = "PATH", conflicts_with = "socket")]
+    input: Option<PathBuf>,
+
+    /// 在该路径监听 unix socket，每个连接的结果写回该连接
+    #[arg(long, value_name = "PATH")]
+    socket: Option<PathBuf>,
+}
+
+/// 交给 worker 的一次查询，结果写入 reply
+struct Job {
+    domain: String,
+    /// 输入行中给出的标记
+    tags: Vec<String>,
+    reply: mpsc::UnboundedSender<String>,
+}
+
+impl<C: WhoisClient> LiDomainScanner<C> {
+    /// 运行流式查询，输入结束(标准输入或普通文件读完)或收到终止信号后保存结果
+    pub(crate) async fn stream(&self, args: StreamArgs, skip_health_check: bool) -> Result<()> {
+        if !skip_health_check {
+            for server in self.servers.iter() {
+                eprintln!("预检查询服务 {}...", server.client.endpoint());
+                self.health_check(server).await?;
+            }
+        }
+
+        let (jobs, rx) = mpsc::channel::<Job>(self.workers);
+        let rx = Arc::new(tokio::sync::Mutex::new(rx));
+        // 停止后队列中尚未开始的查询直接丢弃，只等待进行中的查询
+        let stopping = Arc::new(AtomicBool::new(false));
+        let mut handles = Vec::with_capacity(self.workers);
+        for worker in 0..self.workers {
+            let rx = rx.clone();
+            let stopping = stopping.clone();
+            let scanner = self.clone();
+            handles.push(tokio::spawn(async move {
+                let mut conns: Vec<HeldConnection<C>> = scanner.servers.iter().map(|_| None).collect();
+                loop {
+                    let Some(job) = rx.lock().await.recv().await else {
+                        break;
+                    };
+                    if stopping.load(Ordering::Relaxed) {
+                        continue;
+                    }
+                    let mut result = scanner.check_with_retry(worker, &job.domain, &mut conns).await;
+                    tags::merge(&mut result.tags, job.tags);
+                    scanner.stream_result(result, &job.reply).await;
+                }
+            }));
+        }
+
+        let watchdog = systemd::spawn_watchdog();
+        let shutdown = systemd::shutdown_signal();
+        tokio::pin!(shutdown);
+        let mut writer = None;
+        let mut interrupted = false;
+        match &args.socket {
+            Some(path) => {
+                let mut connections = JoinSet::new();
+                tokio::select! {
+                    outcome = self.serve_socket(path, &jobs, &mut connections) => outcome?,
+                    _ = &mut shutdown => interrupted = true,
+                }
+                connections.abort_all();
+                let _ = std::fs::remove_file(path);
+            }
+            None => {
+                let (reply, output) = spawn_writer(tokio::io::stdout());
+                writer = Some(output);
+                systemd::notify("READY=1");
+                let read = async {
+                    match &args.input {
+                        Some(path) => self.read_input(path, &jobs, &reply).await,
+                        None => self.read_lines(tokio::io::stdin(), &jobs, &reply).await,
+                    }
+                };
+                tokio::select! {
+                    outcome = read => outcome?,
+                    _ = &mut shutdown => interrupted = true,
+                }
+            }
+        }
+
+        systemd::notify("STOPPING=1");
+        // 输入正常结束时仍查询队列中剩余的域名
+        stopping.store(interrupted, Ordering::Relaxed);
+        drop(jobs);
+        for handle in futures::future::join_all(handles).await {
+            handle.context("Worker task panicked")?;
+        }
+        if let Some(writer) = writer {
+            writer.await.context("Output task panicked")?;
+        }
+        if let Some(watchdog) = watchdog {
+            watchdog.abort();
+        }
+
+        if let Some(line) = self.finish_exec().await {
+            eprintln!("{}", line);
+        }
+        if interrupted {
+            self.set_exit(ExitCondition::Interrupted);
+        }
+        self.save_results().await?;
+        let available = self.available_domains.lock().await.len();
+        eprintln!(
+            "查询 {} 个域名，找到 {} 个可用域名，结果已保存到: {} (运行 ID: {})",
+            self.journal.count(),
+            available,
+            self.output_dir.display(),
+            self.run_id
+        );
+        Ok(())
+    }
+
+    /// 记录一条结果并写给请求方
+    async fn stream_result(&self, mut result: DomainResult, reply: &mpsc::UnboundedSender<String>) {
+        if let (Some(ct), DomainStatus::Available) = (&self.ct, &result.status) {
+            match ct.lookup(&result.domain).await {
+                Ok(history) => result.ct = Some(history),
+                Err(e) => eprintln!("CT 查询失败: {} - {:#}", result.domain, e),
+            }
+        }
+        #[cfg(feature = "registrar")]
+        if result.status == DomainStatus::Available {
+            if let Some(line) = self.try_register(&result.domain).await {
+                eprintln!("{}", line);
+            }
+        }
+        for line in self.plugin_results(&result).await {
+            eprintln!("{}", line);
+        }
+        self.run_exec(&result);
+        self.publish(&result).await;
+        if let Ok(line) = serde_json::to_string(&result) {
+            let _ = reply.send(line);
+        }
+        self.journal.append(&result);
+        self.record_postgres(&result);
+    }
+
+    /// 把一行输入转换为待查询的域名：标签转换为 A-label，不带点的标签加上所有规则允许的
+    /// TLD，完整域名的 TLD 需在 --tlds 中并符合该 TLD 的规则
+    pub(crate) fn stream_domains(&self, line: &str) -> Result<Vec<String>, String> {
+        let name = line.trim().trim_end_matches('.');
+        let (label, tld) = match name.split_once('.') {
+            Some((label, tld)) => (label, Some(tld.to_lowercase())),
+            None => (name, None),
+        };
+        let label = idna::to_ascii(label)?;
+        if !generate::is_valid_label(&label) {
+            return Err("invalid domain name".to_string());
+        }
+        let label = label.as_str();
+        let tld = tld.as_deref();
+        match tld {
+            Some(tld) if !self.tlds.iter().any(|known| known == tld) => {
+                Err(format!("TLD `{}` is not in --tlds", tld))
+            }
+            Some(tld) if !self.rules.allows_label(label, tld) => {
+                Err(format!("label is not allowed under .{}", tld))
+            }
+            Some(tld) => Ok(vec![format!("{}.{}", label, tld)]),
+            None => {
+                let domains: Vec<String> = WithTlds::new(std::iter::once(label.to_string()), self.tlds.clone())
+                    .filter(|domain| self.rules.allows(domain))
+                    .collect();
+                if domains.is_empty() {
+                    return Err("label is not allowed under any TLD in --tlds".to_string());
+                }
+                Ok(domains)
+            }
+        }
+    }
+
+    /// 逐行读取直到输入结束，空行和 # 开头的行被忽略
+    async fn read_lines(
+        &self,
+        input: impl AsyncRead + Unpin,
+        jobs: &mpsc::Sender<Job>,
+        reply: &mpsc::UnboundedSender<String>,
+    ) -> Result<()> {
+        let mut lines = BufReader::new(input).lines();
+        while let Some(line) = lines.next_line().await.context("Failed to read input")? {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, tags) = tags::split_line(line);
+            match self.stream_domains(name) {
+                Ok(domains) => {
+                    for domain in domains {
+                        let job = Job {
+                            domain,
+                            tags: tags.clone(),
+                            reply: reply.clone(),
+                        };
+                        jobs.send(job).await.context("All workers exited unexpectedly")?;
+                    }
+                }
+                Err(message) => {
+                    let result = DomainResult {
+                        schema_version: schema::SCHEMA_VERSION,
+                        domain: name.to_string(),
+                        unicode: None,
+                        status: DomainStatus::Error,
+                        reply_code: -99,
+                        message,
+                        timestamp: self.clock.now(),
+                        run_id: self.run_id.clone(),
+                        price: None,
+                        ct: None,
+                        http: None,
+                        whois: None,
+                        verification: None,
+                        tags,
+                        cached: false,
+                    };
+                    if let Ok(line) = serde_json::to_string(&result) {
+                        let _ = reply.send(line);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 读取文件；命名管道在写入端关闭后重新打开，持续等待新的写入者
+    async fn read_input(
+        &self,
+        path: &Path,
+        jobs: &mpsc::Sender<Job>,
+        reply: &mpsc::UnboundedSender<String>,
+    ) -> Result<()> {
+        loop {
+            let file = tokio::fs::File::open(path)
+                .await
+                .with_context(|| format!("Failed to open {}", path.display()))?;
+            #[cfg(unix)]
+            let is_fifo = {
+                use std::os::unix::fs::FileTypeExt;
+                file.metadata().await?.file_type().is_fifo()
+            };
+            #[cfg(not(unix))]
+            let is_fifo = false;
+
+            self.read_lines(file, jobs, reply).await?;
+            if !is_fifo {
+                return Ok(());
+            }
+        }
+    }
+
+    /// 在 unix socket 上接受连接，持续运行直到进程被终止
+    #[cfg(unix)]
+    async fn serve_socket(
+        &self,
+        path: &Path,
+        jobs: &mpsc::Sender<Job>,
+        connections: &mut JoinSet<()>,
+    ) -> Result<()> {
+        use std::os::unix::fs::FileTypeExt;
+
+        // 只清理上次运行遗留的 socket 文件，不覆盖其他类型的文件
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            anyhow::ensure!(
+                metadata.file_type().is_socket(),
+                "{} already exists and is not a socket",
+                path.display()
+            );
+            std::fs::remove_file(path)
+                .with_context(|| format!("Failed to remove stale socket {}", path.display()))?;
+        }
+        let listener = tokio::net::UnixListener::bind(path)
+            .with_context(|| format!("Failed to listen on {}", path.display()))?;
+        eprintln!("在 {} 上等待连接", path.display());
+        systemd::notify("READY=1");
+
+        loop {
+            let (stream, _) = listener.accept().await.context("Failed to accept connection")?;
+            // 回收已结束的连接任务
+            while connections.try_join_next().is_some() {}
+            let (reader, writer) = stream.into_split();
+            let scanner = self.clone();
+            let jobs = jobs.clone();
+            connections.spawn(async move {
+                let (reply, writer) = spawn_writer(writer);
+                if let Err(e) = scanner.read_lines(reader, &jobs, &reply).await {
+                    eprintln!("连接读取失败: {:#}", e);
+                }
+                // 等待该连接的全部查询完成后再关闭写入端
+                drop(reply);
+                let _ = writer.await;
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    async fn serve_socket(
+        &self,
+        _path: &Path,
+        _jobs: &mpsc::Sender<Job>,
+        _connections: &mut JoinSet<()>,
+    ) -> Result<()> {
+        anyhow::bail!("--socket is only supported on Unix")
+    }
+}
+
+/// 启动把结果逐行写出的任务，所有发送端关闭后结束
+fn spawn_writer(
+    mut output: impl AsyncWrite + Unpin + Send + 'static,
+) -> (mpsc::UnboundedSender<String>, tokio::task::JoinHandle<()>) {
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    let handle = tokio::spawn(async move {
+        while let Some(mut line) = rx.recv().await {
+            line.push('\n');
+            // 对方已断开时继续消耗剩余结果，结果仍会被保存
+            if output.write_all(line.as_bytes()).await.is_ok() {
+                let _ = output.flush().await;
+            }
+        }
+        let _ = output.shutdown().await;
+    });
+    (tx, handle)
+}
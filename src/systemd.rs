@@ -0,0 +1,76 @@
+//! systemd 服务集成
+//!
+//! 以 `Type=notify` 服务运行时通过 NOTIFY_SOCKET 报告就绪和停止状态，设置了
+//! `WatchdogSec` 时按间隔的一半发送看门狗心跳。不在 systemd 下运行时这些调用都不做任何事。
+
+use std::time::Duration;
+
+/// 向 systemd 发送状态通知，如 `READY=1`、`STOPPING=1`、`STATUS=...`
+#[cfg(unix)]
+pub fn notify(state: &str) {
+    use std::os::unix::{ffi::OsStrExt, net::UnixDatagram};
+
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    let Ok(socket) = UnixDatagram::unbound() else {
+        return;
+    };
+    let path = path.as_bytes();
+    // 以 @ 开头的是 Linux 抽象命名空间中的 socket
+    if let Some(name) = path.strip_prefix(b"@") {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+            if let Ok(addr) = SocketAddr::from_abstract_name(name) {
+                let _ = socket.send_to_addr(state.as_bytes(), &addr);
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = name;
+        return;
+    }
+    let _ = socket.send_to(state.as_bytes(), std::ffi::OsStr::from_bytes(path));
+}
+
+#[cfg(not(unix))]
+pub fn notify(_state: &str) {}
+
+/// systemd 要求的看门狗间隔，未启用看门狗或不是发给本进程时为 `None`
+fn watchdog_interval() -> Option<Duration> {
+    let usec: u64 = std::env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    if let Ok(pid) = std::env::var("WATCHDOG_PID") {
+        if pid.parse::<u32>().ok()? != std::process::id() {
+            return None;
+        }
+    }
+    (usec > 0).then(|| Duration::from_micros(usec))
+}
+
+/// 启用看门狗时启动心跳任务，运行时卡住时 systemd 会重启服务
+pub fn spawn_watchdog() -> Option<tokio::task::JoinHandle<()>> {
+    let interval = watchdog_interval()?;
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval / 2);
+        loop {
+            ticker.tick().await;
+            notify("WATCHDOG=1");
+        }
+    }))
+}
+
+/// 等待 SIGTERM 或 Ctrl-C
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = terminate.recv() => {}
+                _ = tokio::signal::ctrl_c() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
@@ -0,0 +1,147 @@
+//! 结果标记
+//!
+//! 词表的每行可以在标签后用逗号附加以分号分隔的标记，如 `swisscoin,brand;crypto`，流式查询的
+//! 输入行同样适用；`--tag FILE=TAGS` 给某个词表或短语文件产生的全部标签加上标记。标记保存在
+//! 每条结果的 `tags` 字段中，一次运行混合多个词表时可以看出每个结果来自哪里。同一标签出现在
+//! 多个来源中时合并各来源的标记。`--wordpack` 产生的标签以词包名称(如 `ch-places`)为标记。
+
+use anyhow::{Context, Result};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::PathBuf,
+};
+
+use crate::{
+    generate::{normalize_label, AcronymGen},
+    wordpack::Wordpack,
+};
+
+/// `--tag` 参数：一个词表或短语文件及其标记
+#[derive(Debug, Clone)]
+pub struct SourceTags {
+    path: PathBuf,
+    tags: Vec<String>,
+}
+
+/// 解析 `FILE=TAG1;TAG2`
+pub fn parse_source_tags(value: &str) -> Result<SourceTags, String> {
+    let (path, tags) = value
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected FILE=TAGS, got `{}`", value))?;
+    let tags = parse_tags(tags);
+    if path.is_empty() || tags.is_empty() {
+        return Err(format!("expected FILE=TAGS, got `{}`", value));
+    }
+    Ok(SourceTags {
+        path: PathBuf::from(path),
+        tags,
+    })
+}
+
+/// 拆分输入行：逗号之前为标签或域名，之后为以分号分隔的标记
+pub fn split_line(line: &str) -> (&str, Vec<String>) {
+    match line.split_once(',') {
+        Some((name, tags)) => (name.trim(), parse_tags(tags)),
+        None => (line.trim(), Vec::new()),
+    }
+}
+
+/// 以分号分隔的标记，去掉空白、空项和重复项
+fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    merge(&mut tags, text.split(';').map(str::trim).filter(|tag| !tag.is_empty()));
+    tags
+}
+
+/// 把 extra 中尚未出现的标记按顺序追加到 tags
+pub fn merge<S: AsRef<str>>(tags: &mut Vec<String>, extra: impl IntoIterator<Item = S>) {
+    for tag in extra {
+        let tag = tag.as_ref();
+        if !tags.iter().any(|existing| existing == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+}
+
+/// 各标签的标记，只记录有标记的标签
+#[derive(Debug, Default)]
+pub struct TagIndex {
+    labels: HashMap<String, Vec<String>>,
+}
+
+impl TagIndex {
+    /// 读取词表中每行的标记，给 --tag 指定的文件产生的标签加上对应的标记，给词包产生的标签加上词包名称
+    pub fn load(
+        wordlists: &[PathBuf],
+        acronyms: &[PathBuf],
+        wordpacks: &[Wordpack],
+        sources: &[SourceTags],
+    ) -> Result<Self> {
+        for source in sources {
+            anyhow::ensure!(
+                wordlists.contains(&source.path) || acronyms.contains(&source.path),
+                "--tag {} does not name a --wordlist or --acronyms file",
+                source.path.display()
+            );
+        }
+        let source_tags = |path: &PathBuf| -> Vec<String> {
+            let mut tags = Vec::new();
+            for source in sources.iter().filter(|source| &source.path == path) {
+                merge(&mut tags, &source.tags);
+            }
+            tags
+        };
+
+        let mut index = Self::default();
+        for path in wordlists {
+            let extra = source_tags(path);
+            let file = File::open(path).with_context(|| format!("Failed to open wordlist {}", path.display()))?;
+            for line in BufReader::new(file).lines() {
+                let line = line.with_context(|| format!("Failed to read wordlist {}", path.display()))?;
+                if line.trim().starts_with('#') {
+                    continue;
+                }
+                let (name, mut tags) = split_line(&line);
+                let Some(label) = normalize_label(name) else {
+                    continue;
+                };
+                merge(&mut tags, &extra);
+                index.add(label, tags);
+            }
+        }
+        for path in acronyms {
+            let extra = source_tags(path);
+            if extra.is_empty() {
+                continue;
+            }
+            for label in AcronymGen::open(path)? {
+                index.add(label, extra.clone());
+            }
+        }
+        for pack in wordpacks {
+            for label in pack.labels() {
+                index.add(label, vec![pack.name().to_string()]);
+            }
+        }
+        Ok(index)
+    }
+
+    fn add(&mut self, label: String, tags: Vec<String>) {
+        if !tags.is_empty() {
+            merge(self.labels.entry(label).or_default(), tags);
+        }
+    }
+
+    /// 域名(或标签)的标记，没有时为空
+    pub fn get(&self, domain: &str) -> Vec<String> {
+        let label = domain.split_once('.').map_or(domain, |(label, _)| label);
+        self.labels.get(label).cloned().unwrap_or_default()
+    }
+}
+
+/// 终端和表格中显示的标记
+pub fn display(tags: &[String]) -> String {
+    format!("[标记: {}]", tags.join(", "))
+}
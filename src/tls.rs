@@ -0,0 +1,155 @@
+//! 通过 TLS 访问查询服务
+//!
+//! 提供 TLS 端口的查询服务可以用 `--tls` 加密连接，协议与明文端口相同：每个查询一行，
+//! 每个响应一行。TLS 由 rustls 实现，不依赖外部程序：校验服务器证书链和主机名(地址为 IP 时
+//! 校验证书中的 IP)，并通过 SNI 发送主机名。默认信任系统证书，`--tls-ca-file` 指定 CA 文件时
+//! 只信任其中的证书。SWITCH 公共查询服务只提供明文端口，默认不变。EPP 查询使用同样的
+//! 证书配置，另可提供客户端证书。
+
+use anyhow::{Context, Result};
+use rustls::{
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, ServerName},
+    ClientConfig, RootCertStore,
+};
+use std::{path::Path, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+use tokio_rustls::{client::TlsStream, TlsConnector};
+
+use crate::client::WhoisClient;
+
+/// 建立 TCP 连接并完成 TLS 握手的超时时间
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 一条 TLS 连接
+pub struct TlsConnection {
+    stream: BufReader<TlsStream<TcpStream>>,
+}
+
+/// 通过 TLS 访问查询服务的客户端
+pub struct TlsWhoisClient {
+    host: String,
+    port: u16,
+    server_name: ServerName<'static>,
+    connector: TlsConnector,
+}
+
+impl TlsWhoisClient {
+    /// 从 `HOST:PORT` 形式的地址创建客户端，ca_file 为校验服务器证书使用的 CA 文件，默认使用系统证书
+    pub fn from_endpoint(endpoint: &str, ca_file: Option<&Path>) -> Result<Self> {
+        let (host, port) = endpoint
+            .rsplit_once(':')
+            .with_context(|| format!("Invalid server address `{}`, expected HOST:PORT", endpoint))?;
+        let port = port
+            .parse()
+            .with_context(|| format!("Invalid port in server address `{}`", endpoint))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']').to_string();
+        let server_name = server_name(&host)?;
+        let connector = connector(ca_file, None).map_err(|e| match ca_file {
+            None => e.context("Use --tls-ca-file to specify the CA certificates"),
+            Some(_) => e,
+        })?;
+        Ok(Self {
+            host,
+            port,
+            server_name,
+            connector,
+        })
+    }
+}
+
+/// 主机名或 IP 地址形式的服务器名称，用于 SNI 和证书校验
+pub fn server_name(host: &str) -> Result<ServerName<'static>> {
+    ServerName::try_from(host.to_string()).with_context(|| format!("Invalid server name `{}`", host))
+}
+
+/// 创建 TLS 连接器：ca_file 为校验服务器证书使用的 CA 文件，默认使用系统证书；
+/// client_auth 为客户端证书链和私钥的 PEM 文件
+pub fn connector(ca_file: Option<&Path>, client_auth: Option<(&Path, &Path)>) -> Result<TlsConnector> {
+    let mut roots = RootCertStore::empty();
+    match ca_file {
+        Some(path) => {
+            let certs = read_certs(path, "CA file")?;
+            for cert in certs {
+                roots
+                    .add(cert)
+                    .with_context(|| format!("Invalid certificate in CA file {}", path.display()))?;
+            }
+        }
+        None => {
+            let (added, _) = roots.add_parsable_certificates(rustls_native_certs::load_native_certs().certs);
+            anyhow::ensure!(added > 0, "No system CA certificates found");
+        }
+    }
+    let builder = ClientConfig::builder().with_root_certificates(roots);
+    let config = match client_auth {
+        Some((cert, key)) => {
+            let certs = read_certs(cert, "Certificate file")?;
+            let key = PrivateKeyDer::from_pem_file(key)
+                .with_context(|| format!("Failed to read private key {}", key.display()))?;
+            builder
+                .with_client_auth_cert(certs, key)
+                .context("Client certificate does not match the private key")?
+        }
+        None => builder.with_no_client_auth(),
+    };
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+/// 读取 PEM 文件中的全部证书，kind 用于错误信息
+fn read_certs(path: &Path, kind: &str) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("Failed to read {} {}", kind, path.display()))?;
+    anyhow::ensure!(!certs.is_empty(), "{} {} contains no certificates", kind, path.display());
+    Ok(certs)
+}
+
+impl WhoisClient for TlsWhoisClient {
+    type Connection = TlsConnection;
+
+    fn endpoint(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    /// 建立连接并等待握手和证书校验完成
+    async fn connect(&self) -> Result<Self::Connection> {
+        let endpoint = self.endpoint();
+        let handshake = async {
+            let tcp = TcpStream::connect((self.host.as_str(), self.port)).await?;
+            tcp.set_nodelay(true)?;
+            self.connector.connect(self.server_name.clone(), tcp).await
+        };
+        match tokio::time::timeout(CONNECT_TIMEOUT, handshake).await {
+            Ok(Ok(stream)) => Ok(TlsConnection {
+                stream: BufReader::new(stream),
+            }),
+            Ok(Err(e)) => anyhow::bail!("TLS connection to {} failed: {}", endpoint, e),
+            Err(_) => anyhow::bail!(
+                "TLS handshake with {} did not complete within {}s",
+                endpoint,
+                CONNECT_TIMEOUT.as_secs()
+            ),
+        }
+    }
+
+    async fn query(&self, conn: &mut Self::Connection, domain: &str) -> Result<Option<String>> {
+        let query = format!("{}\n", domain);
+        if conn.stream.write_all(query.as_bytes()).await.is_err() || conn.stream.flush().await.is_err() {
+            return Ok(None);
+        }
+
+        let mut line = String::new();
+        match conn.stream.read_line(&mut line).await {
+            Ok(0) | Err(_) => Ok(None),
+            Ok(_) => Ok(Some(line)),
+        }
+    }
+
+    /// 没有未读的多余数据
+    fn is_alive(&self, conn: &Self::Connection) -> bool {
+        conn.stream.buffer().is_empty()
+    }
+}
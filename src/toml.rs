@@ -0,0 +1,607 @@
+//! TOML 子集解析器
+//!
+//! 支持注释、`[表]`、`[[表数组]]`、点分键、基本/字面字符串、整数、浮点数、布尔值、
+//! 数组(可跨行)和内联表，不支持日期时间和多行字符串。解析结果为 `serde_json::Value`，
+//! 再由 serde 反序列化为具体的配置结构。[`to_string`] 把 JSON 值写成同一子集的 TOML，
+//! 用于输出目录中的 manifest.toml。
+
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Number, Value};
+use std::{fs, path::Path};
+
+/// 读取并解析 TOML 文件，反序列化为指定类型
+pub fn from_file<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// 解析 TOML 文本，反序列化为指定类型
+pub fn from_str<T: DeserializeOwned>(input: &str) -> Result<T> {
+    Ok(serde_json::from_value(parse(input)?)?)
+}
+
+/// 解析 TOML 文本为 JSON 值(顶层总是对象)
+pub fn parse(input: &str) -> Result<Value> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+    };
+    let mut root = Map::new();
+    let mut current: Vec<String> = Vec::new();
+
+    loop {
+        parser.skip_blank();
+        let Some(c) = parser.peek() else {
+            break;
+        };
+
+        let start = parser.pos;
+        if c == '[' {
+            let array = parser.peek_at(1) == Some('[');
+            parser.pos += if array { 2 } else { 1 };
+            parser.skip_ws();
+            let path = parser.key_path()?;
+            parser.skip_ws();
+            parser.expect(']')?;
+            if array {
+                parser.expect(']')?;
+            }
+            parser.end_of_line()?;
+
+            let result = if array {
+                push_array_table(&mut root, &path)
+            } else {
+                table_at(&mut root, &path).map(|_| ())
+            };
+            result.map_err(|e| parser.error_at(start, &e.to_string()))?;
+            current = path;
+        } else {
+            let key = parser.key_path()?;
+            parser.skip_ws();
+            parser.expect('=')?;
+            parser.skip_ws();
+            let value = parser.value()?;
+            parser.end_of_line()?;
+
+            table_at(&mut root, &current)
+                .and_then(|table| insert(table, &key, value))
+                .map_err(|e| parser.error_at(start, &e.to_string()))?;
+        }
+    }
+
+    Ok(Value::Object(root))
+}
+
+/// 沿路径找到(必要时创建)表，路径上的表数组取最后一个元素
+fn table_at<'a>(root: &'a mut Map<String, Value>, path: &[String]) -> Result<&'a mut Map<String, Value>> {
+    let mut table = root;
+    for key in path {
+        let entry = table
+            .entry(key.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        table = match entry {
+            Value::Object(map) => map,
+            Value::Array(items) => match items.last_mut() {
+                Some(Value::Object(map)) => map,
+                _ => bail!("key `{}` is not a table", key),
+            },
+            _ => bail!("key `{}` is not a table", key),
+        };
+    }
+    Ok(table)
+}
+
+/// 在表数组末尾追加一个新表
+fn push_array_table(root: &mut Map<String, Value>, path: &[String]) -> Result<()> {
+    let (last, parent) = path.split_last().context("empty table name")?;
+    let parent = table_at(root, parent)?;
+    match parent
+        .entry(last.clone())
+        .or_insert_with(|| Value::Array(Vec::new()))
+    {
+        Value::Array(items) => {
+            items.push(Value::Object(Map::new()));
+            Ok(())
+        }
+        _ => bail!("key `{}` is not an array of tables", last),
+    }
+}
+
+/// 按点分键插入值，键已存在时报错
+fn insert(table: &mut Map<String, Value>, key: &[String], value: Value) -> Result<()> {
+    let (last, parent) = key.split_last().context("empty key")?;
+    let table = table_at(table, parent)?;
+    if table.contains_key(last) {
+        bail!("duplicate key `{}`", last);
+    }
+    table.insert(last.clone(), value);
+    Ok(())
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        if c.is_some() {
+            self.pos += 1;
+        }
+        c
+    }
+
+    /// 附带当前行号的错误
+    fn error(&self, message: &str) -> anyhow::Error {
+        self.error_at(self.pos, message)
+    }
+
+    /// 附带指定位置所在行号的错误
+    fn error_at(&self, pos: usize, message: &str) -> anyhow::Error {
+        let line = self.chars[..pos.min(self.chars.len())]
+            .iter()
+            .filter(|&&c| c == '\n')
+            .count()
+            + 1;
+        anyhow::anyhow!("line {}: {}", line, message)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some('\n' | '\r') => Err(self.error(&format!("expected `{}`, found end of line", expected))),
+            Some(c) => Err(self.error(&format!("expected `{}`, found `{}`", expected, c))),
+            None => Err(self.error(&format!("expected `{}`, found end of input", expected))),
+        }
+    }
+
+    /// 跳过行内空白
+    fn skip_ws(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// 跳过空白、换行和注释
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_ws();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n' | '\r') => self.pos += 1,
+                _ => return,
+            }
+        }
+    }
+
+    /// 键值对或表头之后只允许注释和换行
+    fn end_of_line(&mut self) -> Result<()> {
+        self.skip_ws();
+        self.skip_comment();
+        if self.peek() == Some('\r') {
+            self.pos += 1;
+        }
+        match self.next() {
+            None | Some('\n') => Ok(()),
+            Some(c) => Err(self.error(&format!("unexpected `{}` after value", c))),
+        }
+    }
+
+    fn key_path(&mut self) -> Result<Vec<String>> {
+        let mut path = vec![self.key()?];
+        loop {
+            self.skip_ws();
+            if self.peek() != Some('.') {
+                return Ok(path);
+            }
+            self.pos += 1;
+            self.skip_ws();
+            path.push(self.key()?);
+        }
+    }
+
+    fn key(&mut self) -> Result<String> {
+        match self.peek() {
+            Some('"') => self.basic_string(),
+            Some('\'') => self.literal_string(),
+            _ => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                {
+                    self.pos += 1;
+                }
+                if start == self.pos {
+                    return Err(self.error("expected a key"));
+                }
+                Ok(self.chars[start..self.pos].iter().collect())
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        match self.peek() {
+            Some('"') => Ok(Value::String(self.basic_string()?)),
+            Some('\'') => Ok(Value::String(self.literal_string()?)),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some(_) => self.scalar(),
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String> {
+        let start = self.pos;
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(out),
+                Some('\\') => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some(kind @ ('u' | 'U')) => {
+                            let len = if kind == 'u' { 4 } else { 8 };
+                            let hex: String = (0..len).filter_map(|_| self.next()).collect();
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error(&format!("invalid unicode escape `{}`", hex)))?
+                        }
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    out.push(escaped);
+                }
+                Some('\n') | None => return Err(self.error_at(start, "unterminated string")),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String> {
+        let start = self.pos;
+        self.expect('\'')?;
+        let mut out = String::new();
+        loop {
+            match self.next() {
+                Some('\'') => return Ok(out),
+                Some('\n') | None => return Err(self.error_at(start, "unterminated string")),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_blank();
+            match self.next() {
+                Some(',') => {}
+                Some(']') => return Ok(Value::Array(items)),
+                _ => return Err(self.error("expected `,` or `]` in array")),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value> {
+        self.expect('{')?;
+        let mut table = Map::new();
+        self.skip_ws();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Value::Object(table));
+        }
+        loop {
+            self.skip_ws();
+            let key = self.key_path()?;
+            self.skip_ws();
+            self.expect('=')?;
+            self.skip_ws();
+            let value = self.value()?;
+            insert(&mut table, &key, value).map_err(|e| self.error(&e.to_string()))?;
+            self.skip_ws();
+            match self.next() {
+                Some(',') => {}
+                Some('}') => return Ok(Value::Object(table)),
+                _ => return Err(self.error("expected `,` or `}` in inline table")),
+            }
+        }
+    }
+
+    /// 布尔值、整数或浮点数
+    fn scalar(&mut self) -> Result<Value> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.' | '_'))
+        {
+            self.pos += 1;
+        }
+        let token: String = self.chars[start..self.pos].iter().collect();
+        match token.as_str() {
+            "true" => return Ok(Value::Bool(true)),
+            "false" => return Ok(Value::Bool(false)),
+            "" => return Err(self.error("expected a value")),
+            _ => {}
+        }
+
+        let digits = token.replace('_', "");
+        let (sign, unsigned) = match digits.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, digits.strip_prefix('+').unwrap_or(&digits)),
+        };
+        let radix = match unsigned.get(..2) {
+            Some("0x") => Some(16),
+            Some("0o") => Some(8),
+            Some("0b") => Some(2),
+            _ => None,
+        };
+        let integer = match radix {
+            Some(radix) => i64::from_str_radix(&unsigned[2..], radix).ok().map(|n| sign * n),
+            None => digits.parse::<i64>().ok(),
+        };
+        if let Some(n) = integer {
+            return Ok(Value::Number(n.into()));
+        }
+        digits
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| self.error(&format!("invalid value `{}`", token)))
+    }
+}
+
+/// 把 JSON 对象写成 TOML 文本：标量和数组在前，子表写为 `[表]`，元素全为对象的数组写为
+/// `[[表数组]]`，null 省略
+pub fn to_string(value: &Value) -> String {
+    let mut out = String::new();
+    if let Value::Object(table) = value {
+        write_table(&mut out, &mut Vec::new(), table);
+    }
+    out
+}
+
+fn write_table(out: &mut String, path: &mut Vec<String>, table: &Map<String, Value>) {
+    let is_table_array =
+        |value: &Value| matches!(value, Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_object));
+    for (key, value) in table {
+        if value.is_null() || value.is_object() || is_table_array(value) {
+            continue;
+        }
+        out.push_str(&format!("{} = {}\n", format_key(key), format_value(value)));
+    }
+    for (key, value) in table {
+        match value {
+            Value::Object(child) => {
+                path.push(key.clone());
+                out.push_str(&format!("\n[{}]\n", format_path(path)));
+                write_table(out, path, child);
+                path.pop();
+            }
+            Value::Array(items) if is_table_array(value) => {
+                path.push(key.clone());
+                for item in items {
+                    out.push_str(&format!("\n[[{}]]\n", format_path(path)));
+                    if let Value::Object(child) = item {
+                        write_table(out, path, child);
+                    }
+                }
+                path.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn format_path(path: &[String]) -> String {
+    path.iter().map(|key| format_key(key)).collect::<Vec<_>>().join(".")
+}
+
+/// 只含字母、数字、`_` 和 `-` 的键不加引号
+fn format_key(key: &str) -> String {
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        key.to_string()
+    } else {
+        format_string(key)
+    }
+}
+
+/// 行内的值，嵌套的对象写为内联表
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => format_string(text),
+        Value::Number(number) if number.is_f64() => {
+            let text = number.to_string();
+            match text.contains(['.', 'e', 'E']) {
+                true => text,
+                false => format!("{}.0", text),
+            }
+        }
+        Value::Number(number) => number.to_string(),
+        Value::Bool(flag) => flag.to_string(),
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().filter(|item| !item.is_null()).map(format_value).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(table) => {
+            let entries: Vec<String> = table
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| format!("{} = {}", format_key(key), format_value(value)))
+                .collect();
+            format!("{{ {} }}", entries.join(", "))
+        }
+        Value::Null => String::new(),
+    }
+}
+
+/// 基本字符串，转义引号、反斜杠和控制字符
+fn format_string(text: &str) -> String {
+    let mut out = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_literals_and_escapes() {
+        let value = parse(
+            r#"
+# 注释
+basic = "a\"b\\c\n\t\u00e9\U0001F600"
+literal = 'C:\path' # 行尾注释
+int = 1_000
+negative = -42
+hex = 0xff
+octal = 0o17
+binary = 0b101
+float = 3.5
+exponent = -1e3
+flag = true
+off = false
+"quoted key" = 1
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "basic": "a\"b\\c\n\té😀",
+                "literal": "C:\\path",
+                "int": 1000,
+                "negative": -42,
+                "hex": 255,
+                "octal": 15,
+                "binary": 5,
+                "float": 3.5,
+                "exponent": -1000.0,
+                "flag": true,
+                "off": false,
+                "quoted key": 1,
+            })
+        );
+    }
+
+    #[test]
+    fn parses_tables_and_arrays() {
+        let value = parse(
+            "name = \"scan\"\n\
+             ports = [\n  43,\n  4343, # 注释\n]\n\
+             point = { x = 1, y.z = \"a\" }\n\
+             empty = {}\n\
+             \n\
+             [server.primary]\n\
+             host = \"whois.nic.li\"\n\
+             tls.enabled = false\n\
+             \n\
+             [[rule]]\n\
+             name = \"a\"\n\
+             [[rule]]\n\
+             name = \"b\"\n\
+             [rule.extra]\n\
+             weight = 2\n",
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "name": "scan",
+                "ports": [43, 4343],
+                "point": {"x": 1, "y": {"z": "a"}},
+                "empty": {},
+                "server": {"primary": {"host": "whois.nic.li", "tls": {"enabled": false}}},
+                "rule": [{"name": "a"}, {"name": "b", "extra": {"weight": 2}}],
+            })
+        );
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        let error = |input: &str| parse(input).unwrap_err().to_string();
+        assert_eq!(error("a = 1\na = 2\n"), "line 2: duplicate key `a`");
+        assert_eq!(error("a = \"open\nb = 1\n"), "line 1: unterminated string");
+        assert_eq!(error("a = 'open"), "line 1: unterminated string");
+        assert_eq!(error("a = \"\\q\""), "line 1: invalid escape sequence");
+        assert_eq!(error("a = \"\\uZZZZ\""), "line 1: invalid unicode escape `ZZZZ`");
+        assert_eq!(error("a = 1 2"), "line 1: unexpected `2` after value");
+        assert_eq!(error("a = [1 2]"), "line 1: expected `,` or `]` in array");
+        assert_eq!(error("a = { b = 1"), "line 1: expected `,` or `}` in inline table");
+        assert_eq!(error("a = nope"), "line 1: invalid value `nope`");
+        assert_eq!(error("a ="), "line 1: expected a value");
+        assert_eq!(error("= 1"), "line 1: expected a key");
+        assert_eq!(error("[a\n"), "line 1: expected `]`, found end of line");
+        assert_eq!(error("a = 1\n[a]\n"), "line 2: key `a` is not a table");
+        assert_eq!(error("[a]\n[[a]]\n"), "line 2: key `a` is not an array of tables");
+    }
+
+    #[test]
+    fn to_string_round_trips() {
+        let value = json!({
+            "name": "quote \" and \\ and\nnewline",
+            "count": 3,
+            "ratio": 2.0,
+            "skip": null,
+            "tags": ["a", "b"],
+            "inline": [{"x": 1}, 2],
+            "weird key": true,
+            "server": {"host": "whois.nic.li", "port": 43},
+            "rule": [{"name": "a"}, {"name": "b", "extra": {"weight": 2}}],
+        });
+        let text = to_string(&value);
+        let mut expected = value.clone();
+        expected.as_object_mut().unwrap().remove("skip");
+        assert_eq!(parse(&text).unwrap(), expected);
+    }
+}
@@ -0,0 +1,175 @@
+//! 通过 Tor 查询
+//!
+//! `--tor` 经由本机 Tor 客户端(arti 或 tor)的 SOCKS5 端口连接查询服务，域名由 Tor 出口
+//! 解析，不经过本机 DNS。Tor 按 SOCKS 用户名和密码隔离线路，设置 `--tor-rotate N` 时每 N 次
+//! 查询换一组凭据，之后的查询使用新线路，仍在旧线路上的连接在下次查询前重新建立。
+
+use anyhow::{Context, Result};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+use crate::{client::WhoisClient, rng};
+
+const SOCKS_VERSION: u8 = 5;
+/// 用户名/密码认证(RFC 1929)
+const AUTH_USER_PASSWORD: u8 = 2;
+const COMMAND_CONNECT: u8 = 1;
+const ADDRESS_IPV4: u8 = 1;
+const ADDRESS_DOMAIN: u8 = 3;
+const ADDRESS_IPV6: u8 = 4;
+
+/// 经由 Tor 的一条连接及其所在线路的编号
+pub struct TorConnection {
+    stream: BufReader<TcpStream>,
+    circuit: usize,
+}
+
+/// 经由 Tor SOCKS5 端口访问查询服务的客户端
+pub struct TorWhoisClient {
+    proxy: String,
+    host: String,
+    port: u16,
+    /// 每个线路承担的查询数，None 表示不主动更换线路
+    rotate: Option<usize>,
+    /// 本次运行的凭据前缀，避免与其他运行共用线路
+    session: u64,
+    queries: AtomicUsize,
+}
+
+impl TorWhoisClient {
+    /// 创建经由 proxy(Tor 的 SOCKS5 地址)访问 endpoint 的客户端
+    pub fn new(proxy: &str, endpoint: &str, rotate: Option<usize>) -> Result<Self> {
+        let (host, port) = endpoint
+            .rsplit_once(':')
+            .with_context(|| format!("Invalid server address `{}`, expected HOST:PORT", endpoint))?;
+        let port = port
+            .parse()
+            .with_context(|| format!("Invalid port in server address `{}`", endpoint))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']').to_string();
+        anyhow::ensure!(host.len() <= 255, "Server host `{}` is too long for SOCKS5", host);
+        Ok(Self {
+            proxy: proxy.to_string(),
+            host,
+            port,
+            rotate,
+            session: rng::next_u64(),
+            queries: AtomicUsize::new(0),
+        })
+    }
+
+    /// 当前查询所在的线路编号
+    fn circuit(&self) -> usize {
+        match self.rotate {
+            Some(rotate) => self.queries.load(Ordering::Relaxed) / rotate,
+            None => 0,
+        }
+    }
+
+    /// 经由代理在指定线路上连接查询服务
+    async fn connect_on(&self, circuit: usize) -> Result<TorConnection> {
+        let mut stream = TcpStream::connect(&self.proxy)
+            .await
+            .with_context(|| format!("Failed to connect to the Tor SOCKS port {}", self.proxy))?;
+        stream.set_nodelay(true)?;
+
+        stream.write_all(&[SOCKS_VERSION, 1, AUTH_USER_PASSWORD]).await?;
+        let mut reply = [0u8; 2];
+        stream.read_exact(&mut reply).await.context("Tor SOCKS port closed the connection")?;
+        anyhow::ensure!(reply[0] == SOCKS_VERSION, "{} is not a SOCKS5 proxy", self.proxy);
+        anyhow::ensure!(
+            reply[1] == AUTH_USER_PASSWORD,
+            "SOCKS5 proxy {} does not accept the username/password authentication used for circuit isolation",
+            self.proxy
+        );
+
+        // Tor 不校验凭据，只用它区分线路
+        let username = format!("ldc-{:016x}", self.session);
+        let password = circuit.to_string();
+        let mut auth = vec![1, username.len() as u8];
+        auth.extend_from_slice(username.as_bytes());
+        auth.push(password.len() as u8);
+        auth.extend_from_slice(password.as_bytes());
+        stream.write_all(&auth).await?;
+        stream.read_exact(&mut reply).await.context("Tor SOCKS port closed the connection")?;
+        anyhow::ensure!(reply[1] == 0, "SOCKS5 authentication with {} failed", self.proxy);
+
+        let mut request = vec![SOCKS_VERSION, COMMAND_CONNECT, 0, ADDRESS_DOMAIN, self.host.len() as u8];
+        request.extend_from_slice(self.host.as_bytes());
+        request.extend_from_slice(&self.port.to_be_bytes());
+        stream.write_all(&request).await?;
+        let mut header = [0u8; 4];
+        stream.read_exact(&mut header).await.context("Tor SOCKS port closed the connection")?;
+        anyhow::ensure!(
+            header[1] == 0,
+            "Tor could not connect to {}: {}",
+            self.endpoint(),
+            socks_error(header[1])
+        );
+        // 跳过代理回报的绑定地址和端口
+        let address_len = match header[3] {
+            ADDRESS_IPV4 => 4,
+            ADDRESS_IPV6 => 16,
+            ADDRESS_DOMAIN => stream.read_u8().await? as usize,
+            other => anyhow::bail!("Unexpected SOCKS5 address type {}", other),
+        };
+        let mut bound = vec![0u8; address_len + 2];
+        stream.read_exact(&mut bound).await?;
+
+        Ok(TorConnection {
+            stream: BufReader::new(stream),
+            circuit,
+        })
+    }
+}
+
+/// SOCKS5 应答码的含义
+fn socks_error(code: u8) -> &'static str {
+    match code {
+        1 => "general failure",
+        2 => "connection not allowed by the exit policy",
+        3 => "network unreachable",
+        4 => "host unreachable",
+        5 => "connection refused",
+        6 => "TTL expired",
+        _ => "unknown error",
+    }
+}
+
+impl WhoisClient for TorWhoisClient {
+    type Connection = TorConnection;
+
+    fn endpoint(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    async fn connect(&self) -> Result<Self::Connection> {
+        self.connect_on(self.circuit()).await
+    }
+
+    /// 连接所在的线路已轮换时先在新线路上重新连接
+    async fn query(&self, conn: &mut Self::Connection, domain: &str) -> Result<Option<String>> {
+        let circuit = self.circuit();
+        if conn.circuit != circuit {
+            *conn = self.connect_on(circuit).await?;
+        }
+        self.queries.fetch_add(1, Ordering::Relaxed);
+
+        let query = format!("{}\n", domain);
+        if conn.stream.get_mut().write_all(query.as_bytes()).await.is_err() {
+            return Ok(None);
+        }
+        let mut line = String::new();
+        match conn.stream.read_line(&mut line).await {
+            Ok(0) | Err(_) => Ok(None),
+            Ok(_) => Ok(Some(line)),
+        }
+    }
+
+    /// 没有未读的多余数据且仍在当前线路上
+    fn is_alive(&self, conn: &Self::Connection) -> bool {
+        conn.stream.buffer().is_empty() && conn.circuit == self.circuit()
+    }
+}
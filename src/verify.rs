@@ -0,0 +1,171 @@
+//! 可用域名的复核
+//!
+//! 使用 `--verify-available` 时，扫描结束后对每个可用域名在随机的短暂等待后再查询一次，
+//! 使用 `--verify-dns` 时同时查询该域名是否有 NS 委派。第二次查询仍为可用(且没有委派)时
+//! 确认可用；第二次查询为已注册或有委派时视为前后不一致，可能是限流或查询服务的抖动造成的
+//! 误报；第二次查询被限流或出错时无法复核。只有确认可用的域名写入 available_domains.txt，
+//! 复核结果记录在每条可用结果的 `verification` 字段中。
+//!
+//! DNS 查询直接向 /etc/resolv.conf 中的第一个 nameserver 发送 UDP 请求，不依赖外部程序。
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+use tokio::{net::UdpSocket, time::timeout};
+
+use crate::{rng, DomainStatus};
+
+/// DNS 查询的超时时间，超时后再试一次
+const DNS_TIMEOUT: Duration = Duration::from_secs(3);
+const DNS_ATTEMPTS: usize = 2;
+/// DNS 记录类型 NS
+const TYPE_NS: u16 = 2;
+
+/// 复核的结论
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// 第二次查询仍为可用，且没有 DNS 委派
+    Confirmed,
+    /// 第二次查询为已注册，或域名有 DNS 委派
+    Inconsistent,
+    /// 第二次查询被限流或出错，或 DNS 查询失败
+    Unverified,
+}
+
+impl Outcome {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Confirmed => "confirmed",
+            Self::Inconsistent => "inconsistent",
+            Self::Unverified => "unverified",
+        }
+    }
+}
+
+/// DNS 查询结果
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DnsCheck {
+    /// 域名不存在
+    Nxdomain,
+    /// 域名存在但没有 NS 记录
+    NoDelegation,
+    /// 有 NS 记录，域名已委派给某个 DNS 服务
+    Delegated,
+    /// 查询超时或出错
+    Failed,
+}
+
+/// 可用结果的复核记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Verification {
+    pub outcome: Outcome,
+    /// 第二次查询的状态
+    pub status: DomainStatus,
+    /// 第二次查询的响应
+    pub message: String,
+    pub checked_at: chrono::DateTime<chrono::FixedOffset>,
+    /// 仅 --verify-dns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns: Option<DnsCheck>,
+}
+
+impl Verification {
+    pub fn new(
+        status: DomainStatus,
+        message: String,
+        checked_at: chrono::DateTime<chrono::FixedOffset>,
+        dns: Option<DnsCheck>,
+    ) -> Self {
+        let outcome = match (&status, dns) {
+            (DomainStatus::Registered, _) | (_, Some(DnsCheck::Delegated)) => Outcome::Inconsistent,
+            (DomainStatus::Available, None | Some(DnsCheck::Nxdomain | DnsCheck::NoDelegation)) => Outcome::Confirmed,
+            _ => Outcome::Unverified,
+        };
+        Self {
+            outcome,
+            status,
+            message,
+            checked_at,
+            dns,
+        }
+    }
+
+    /// 终端中显示的未通过复核的原因
+    pub fn reason(&self) -> String {
+        match (self.outcome, self.dns) {
+            (Outcome::Confirmed, _) => "确认可用".to_string(),
+            (Outcome::Inconsistent, _) if self.status == DomainStatus::Registered => "第二次查询为已注册".to_string(),
+            (Outcome::Inconsistent, _) => "域名有 DNS 委派".to_string(),
+            (Outcome::Unverified, Some(DnsCheck::Failed)) => "DNS 查询失败".to_string(),
+            (Outcome::Unverified, _) => format!("第二次查询失败: {}", self.message),
+        }
+    }
+}
+
+/// 解析 /etc/resolv.conf 中的第一个 nameserver
+pub fn system_resolver() -> Result<SocketAddr> {
+    let content = fs::read_to_string("/etc/resolv.conf").context("Failed to read /etc/resolv.conf")?;
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .filter_map(|address| address.trim().parse::<IpAddr>().ok())
+        .map(|ip| SocketAddr::new(ip, 53))
+        .next()
+        .context("--verify-dns requires a nameserver in /etc/resolv.conf")
+}
+
+/// 查询域名的 NS 记录，出错时返回 Failed
+pub async fn check_dns(resolver: SocketAddr, domain: &str) -> DnsCheck {
+    for _ in 0..DNS_ATTEMPTS {
+        if let Ok(Ok(check)) = timeout(DNS_TIMEOUT, query_ns(resolver, domain)).await {
+            return check;
+        }
+    }
+    DnsCheck::Failed
+}
+
+async fn query_ns(resolver: SocketAddr, domain: &str) -> Result<DnsCheck> {
+    let id = rng::next_u64() as u16;
+    // 请求头：ID、RD 标志、一个问题
+    let mut request = Vec::with_capacity(domain.len() + 18);
+    request.extend_from_slice(&id.to_be_bytes());
+    request.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in domain.trim_end_matches('.').split('.') {
+        anyhow::ensure!(!label.is_empty() && label.len() < 64, "Invalid domain name {}", domain);
+        request.push(label.len() as u8);
+        request.extend_from_slice(label.as_bytes());
+    }
+    request.push(0);
+    request.extend_from_slice(&TYPE_NS.to_be_bytes());
+    request.extend_from_slice(&1u16.to_be_bytes());
+
+    let local: SocketAddr = match resolver {
+        SocketAddr::V4(_) => "0.0.0.0:0".parse()?,
+        SocketAddr::V6(_) => "[::]:0".parse()?,
+    };
+    let socket = UdpSocket::bind(local).await.context("Failed to open UDP socket")?;
+    socket.connect(resolver).await.context("Failed to reach the DNS resolver")?;
+    socket.send(&request).await.context("Failed to send DNS query")?;
+    let mut response = [0u8; 1500];
+    loop {
+        let len = socket.recv(&mut response).await.context("Failed to read DNS response")?;
+        let response = &response[..len];
+        // 忽略不属于本次查询的响应
+        if len < 12 || u16::from_be_bytes([response[0], response[1]]) != id || response[2] & 0x80 == 0 {
+            continue;
+        }
+        let answers = u16::from_be_bytes([response[6], response[7]]);
+        return match response[3] & 0x0f {
+            0 if answers > 0 => Ok(DnsCheck::Delegated),
+            0 => Ok(DnsCheck::NoDelegation),
+            3 => Ok(DnsCheck::Nxdomain),
+            code => bail!("DNS resolver returned rcode {}", code),
+        };
+    }
+}
@@ -0,0 +1,265 @@
+//! 按分级间隔监视域名
+//!
+//! `watch <FILE>` 在一个进程中持续查询监视列表中的域名，每个域名属于一个级别，各级别有自己的
+//! 查询间隔：即将释放的少数域名可以每分钟查询，其余的每天查询一次。监视列表为 TOML 文件：
+//!
+//! ```toml
+//! hot = ["soon.li"]
+//! normal = ["nice.li", "other"]
+//! cold = ["long-tail.ch"]
+//!
+//! [intervals]
+//! hot = "1m"
+//! ```
+//!
+//! 不带 TLD 的标签在 --tlds 的每个 TLD 下监视。`[intervals]` 中未设置的级别使用默认间隔
+//! (hot 1m、normal 1h、cold 24h)。域名状态改变时打印一行，变为可用时突出显示；指定
+//! `--notify-if` 时只打印满足条件(语法与 query 子命令相同)的变化并只对它们运行 --exec。
+//! 收到 SIGTERM 或 Ctrl-C 时停止，已得出的结果照常保存到输出目录。
+
+use anyhow::{Context, Result};
+use clap::Args as ClapArgs;
+use console::style;
+use serde::Deserialize;
+use std::{collections::HashMap, path::PathBuf};
+use tokio::time::Instant;
+
+use crate::{
+    client::WhoisClient,
+    filter::{status_name, Query},
+    monitor::{display_interval, parse_interval},
+    systemd, toml, DomainStatus, ExitCondition, LiDomainScanner,
+};
+
+/// watch 子命令参数
+#[derive(ClapArgs, Debug)]
+pub struct WatchArgs {
+    /// 监视列表(TOML)，按级别列出域名
+    #[arg(value_name = "FILE")]
+    watchlist: PathBuf,
+
+    /// 只打印满足条件的状态变化并只对它们运行 --exec，语法与 query 子命令相同(不带 ORDER BY 和 LIMIT)
+    #[arg(long, value_name = "CONDITION")]
+    notify_if: Option<String>,
+}
+
+/// 域名的监视级别，从高到低
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Tier {
+    Hot,
+    Normal,
+    Cold,
+}
+
+impl Tier {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Hot => "hot",
+            Self::Normal => "normal",
+            Self::Cold => "cold",
+        }
+    }
+}
+
+/// 监视列表文件的内容
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Watchlist {
+    hot: Vec<String>,
+    normal: Vec<String>,
+    cold: Vec<String>,
+    intervals: Intervals,
+}
+
+/// 各级别的查询间隔
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Intervals {
+    hot: String,
+    normal: String,
+    cold: String,
+}
+
+impl Default for Intervals {
+    fn default() -> Self {
+        Self {
+            hot: "1m".to_string(),
+            normal: "1h".to_string(),
+            cold: "24h".to_string(),
+        }
+    }
+}
+
+/// 一个被监视的域名
+struct Watched {
+    domain: String,
+    tier: Tier,
+    /// 下次查询的时间
+    due: Instant,
+    /// 最近一次得出的可用或已注册状态
+    status: Option<DomainStatus>,
+}
+
+impl<C: WhoisClient> LiDomainScanner<C> {
+    /// 按各级别的间隔持续查询监视列表中的域名，直到收到终止信号后保存结果退出
+    pub(crate) async fn watch(&self, args: WatchArgs, skip_health_check: bool) -> Result<()> {
+        let watchlist: Watchlist = toml::from_file(&args.watchlist)?;
+        let notify_if = args
+            .notify_if
+            .as_deref()
+            .map(Query::parse_condition)
+            .transpose()
+            .context("Invalid --notify-if expression")?;
+        let interval = |tier: &str, text: &str| {
+            parse_interval(text)
+                .map_err(|e| anyhow::anyhow!(e))
+                .with_context(|| format!("intervals.{} in {}", tier, args.watchlist.display()))
+        };
+        let intervals = HashMap::from([
+            (Tier::Hot, interval("hot", &watchlist.intervals.hot)?),
+            (Tier::Normal, interval("normal", &watchlist.intervals.normal)?),
+            (Tier::Cold, interval("cold", &watchlist.intervals.cold)?),
+        ]);
+
+        // 同一域名出现在多个级别时取最高的级别
+        let mut tiers: HashMap<String, Tier> = HashMap::new();
+        let mut order = Vec::new();
+        for (tier, entries) in [
+            (Tier::Hot, &watchlist.hot),
+            (Tier::Normal, &watchlist.normal),
+            (Tier::Cold, &watchlist.cold),
+        ] {
+            for entry in entries {
+                let domains = self
+                    .stream_domains(entry)
+                    .map_err(|e| anyhow::anyhow!("{} in {}: {}", entry, args.watchlist.display(), e))?;
+                for domain in domains {
+                    if tiers.contains_key(&domain) {
+                        continue;
+                    }
+                    tiers.insert(domain.clone(), tier);
+                    order.push(domain);
+                }
+            }
+        }
+        anyhow::ensure!(!order.is_empty(), "{} lists no domains", args.watchlist.display());
+
+        if !skip_health_check {
+            for server in self.servers.iter() {
+                println!("预检查询服务 {}...", server.client.endpoint());
+                self.health_check(server).await?;
+            }
+        }
+        for tier in [Tier::Hot, Tier::Normal, Tier::Cold] {
+            let count = tiers.values().filter(|&&t| t == tier).count();
+            if count > 0 {
+                println!(
+                    "{}: {} 个域名，每 {} 查询一次",
+                    tier.name(),
+                    count,
+                    display_interval(intervals[&tier])
+                );
+            }
+        }
+
+        let start = Instant::now();
+        let mut watched: Vec<Watched> = order
+            .into_iter()
+            .map(|domain| Watched {
+                tier: tiers[&domain],
+                domain,
+                due: start,
+                status: None,
+            })
+            .collect();
+
+        // 以 Type=notify 运行时，预检和列出监视范围之后才算启动完成
+        systemd::notify("READY=1");
+        let watchdog = systemd::spawn_watchdog();
+        let shutdown = systemd::shutdown_signal();
+        tokio::pin!(shutdown);
+        loop {
+            let next = watched.iter().map(|entry| entry.due).min().expect("watchlist is not empty");
+            tokio::select! {
+                _ = tokio::time::sleep_until(next) => {}
+                _ = &mut shutdown => {
+                    self.set_exit(ExitCondition::Interrupted);
+                    break;
+                }
+            }
+
+            // 先安排下次查询，查询失败的域名也按间隔重试
+            let now = Instant::now();
+            let mut due = Vec::new();
+            for entry in watched.iter_mut().filter(|entry| entry.due <= now) {
+                entry.due = now + intervals[&entry.tier];
+                due.push(entry.domain.clone());
+            }
+            let results = tokio::select! {
+                results = self.check_domains(due) => results,
+                _ = &mut shutdown => {
+                    self.set_exit(ExitCondition::Interrupted);
+                    break;
+                }
+            };
+            let index: HashMap<String, usize> = watched
+                .iter()
+                .enumerate()
+                .map(|(position, entry)| (entry.domain.clone(), position))
+                .collect();
+            for result in results {
+                let Some(&position) = index.get(&result.domain) else {
+                    continue;
+                };
+                let entry = &mut watched[position];
+                self.publish(&result).await;
+                if !matches!(result.status, DomainStatus::Available | DomainStatus::Registered) {
+                    println!(
+                        "{}",
+                        style(format!("! 错误: {} - {} (下次照常查询)", result.display_name(), result.message)).yellow()
+                    );
+                    continue;
+                }
+                let previous = entry.status.replace(result.status.clone());
+                if previous.as_ref() == Some(&result.status)
+                    || !notify_if.as_ref().is_none_or(|query| query.matches(&result))
+                {
+                    continue;
+                }
+                // 只在变为可用时运行 --exec，而不是每次查询到可用时
+                self.run_exec(&result);
+                let line = match previous {
+                    Some(previous) => format!(
+                        "{} [{}]: {} → {}",
+                        result.display_name(),
+                        entry.tier.name(),
+                        status_name(&previous),
+                        status_name(&result.status)
+                    ),
+                    None => format!(
+                        "{} [{}]: {}",
+                        result.display_name(),
+                        entry.tier.name(),
+                        status_name(&result.status)
+                    ),
+                };
+                let line = format!("[{}] {}", self.clock.display(&self.clock.now()), line);
+                match result.status {
+                    DomainStatus::Available => println!("{}", style(format!("✓ {}", line)).green().bold()),
+                    _ => println!("{}", line),
+                }
+            }
+        }
+
+        systemd::notify("STOPPING=1");
+        if let Some(watchdog) = watchdog {
+            watchdog.abort();
+        }
+        if let Some(line) = self.finish_exec().await {
+            println!("{}", line);
+        }
+        self.save_results().await?;
+        println!("结果已保存到: {} (运行 ID: {})", self.output_dir.display(), self.run_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+//! 已注册域名的完整 whois 查询
+//!
+//! 可用性查询服务只回答是否已注册。完整的 whois 服务(SWITCH 为 whois.nic.ch:43)
+//! 还给出注册商和首次注册日期，用于统计已注册域名的注册商分布和注册时间分布；公开持有人
+//! 信息时(如 `Holder of domain name:` 块或 `Registrant: ...`)也记录持有人名称。
+//! 该服务的限流比可用性查询严格得多，查询按单独的间隔依次进行。
+//!
+//! SWITCH 的响应按块组织，标题行以冒号结尾，其后各行为内容，空行分隔：
+//!
+//! ```text
+//! Registrar:
+//! Example Registrar AG
+//!
+//! First registration date:
+//! 1996-01-23
+//! ```
+//!
+//! 也接受其他注册局常见的 `Registrar: ...` / `Creation Date: ...` 单行格式。
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::timeout,
+};
+
+use crate::limiter::RateLimiter;
+
+/// 默认的完整 whois 服务
+pub const DEFAULT_WHOIS_SERVER: &str = "whois.nic.ch:43";
+
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_ATTEMPTS: u32 = 3;
+
+/// 结果中附带的 whois 信息
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WhoisDetails {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registrar: Option<String>,
+    /// 首次注册日期(YYYY-MM-DD)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registered: Option<String>,
+    /// 持有人名称，只有 whois 服务公开时才有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub holder: Option<String>,
+}
+
+/// 完整 whois 查询
+pub struct DetailLookup {
+    endpoint: String,
+    limiter: RateLimiter,
+}
+
+impl DetailLookup {
+    pub fn new(endpoint: &str, delay: f64) -> Result<Self> {
+        anyhow::ensure!(
+            endpoint.rsplit_once(':').is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok()),
+            "--whois-server must be HOST:PORT"
+        );
+        anyhow::ensure!(delay >= 0.0, "--whois-delay must not be negative");
+        Ok(Self {
+            endpoint: endpoint.to_string(),
+            limiter: RateLimiter::new(Duration::from_secs_f64(delay), 0.0),
+        })
+    }
+
+    /// 查询域名的注册商和首次注册日期，响应中两者都没有时视为被拒绝或限流并重试
+    pub async fn lookup(&self, domain: &str) -> Result<WhoisDetails> {
+        let mut attempt = 1;
+        loop {
+            self.limiter.wait().await;
+            let response = timeout(LOOKUP_TIMEOUT, self.query(domain))
+                .await
+                .map_err(|_| anyhow::anyhow!("{} did not answer within {}s", self.endpoint, LOOKUP_TIMEOUT.as_secs()))??;
+            let details = parse(&response);
+            if details.registrar.is_some() || details.registered.is_some() {
+                self.limiter.on_success();
+                return Ok(details);
+            }
+            if attempt >= MAX_ATTEMPTS {
+                let first_line = response.lines().map(str::trim).find(|line| !line.is_empty());
+                anyhow::bail!(
+                    "No registrar or registration date in the reply: {}",
+                    first_line.unwrap_or("empty reply")
+                );
+            }
+            self.limiter.on_rate_limited();
+            attempt += 1;
+        }
+    }
+
+    async fn query(&self, domain: &str) -> Result<String> {
+        let mut stream = TcpStream::connect(&self.endpoint)
+            .await
+            .with_context(|| format!("Failed to connect to {}", self.endpoint))?;
+        stream.write_all(format!("{}\r\n", domain).as_bytes()).await?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+        Ok(String::from_utf8_lossy(&response).into_owned())
+    }
+}
+
+/// 从响应中取出注册商、首次注册日期和持有人
+fn parse(response: &str) -> WhoisDetails {
+    let mut details = WhoisDetails {
+        registrar: None,
+        registered: None,
+        holder: None,
+    };
+    let mut heading: Option<String> = None;
+    for line in response.lines().map(str::trim) {
+        if line.is_empty() {
+            heading = None;
+            continue;
+        }
+        // 块内容：标题的下一行
+        if let Some(key) = heading.take() {
+            assign(&mut details, &key, line);
+            continue;
+        }
+        match line.split_once(':') {
+            Some((key, "")) => heading = Some(key.trim().to_ascii_lowercase()),
+            Some((key, value)) => assign(&mut details, &key.trim().to_ascii_lowercase(), value.trim()),
+            None => {}
+        }
+    }
+    details
+}
+
+fn assign(details: &mut WhoisDetails, key: &str, value: &str) {
+    match key {
+        "registrar" if details.registrar.is_none() => details.registrar = Some(value.to_string()),
+        // 块格式的持有人信息第一行为名称，其后为地址
+        "holder of domain name" | "holder" | "registrant" | "registrant name" | "registrant organization"
+            if details.holder.is_none() =>
+        {
+            details.holder = Some(value.to_string())
+        }
+        "first registration date" | "creation date" | "created" | "registered"
+            if details.registered.is_none() =>
+        {
+            // 只保留日期部分，如 2001-02-03T00:00:00Z
+            let date = value.get(..10).filter(|date| {
+                chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok()
+            });
+            details.registered = Some(date.unwrap_or(value).to_string());
+        }
+        _ => {}
+    }
+}
+
+impl WhoisDetails {
+    /// 终端中显示的说明，各项都没有时为 `None`
+    pub fn display(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(holder) = &self.holder {
+            parts.push(format!("持有人 {}", holder));
+        }
+        if let Some(registrar) = &self.registrar {
+            parts.push(registrar.clone());
+        }
+        if let Some(registered) = &self.registered {
+            parts.push(format!("注册于 {}", registered));
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
@@ -0,0 +1,80 @@
+//! 随程序发布的候选词包
+//!
+//! `--wordpack` 按名称选择 wordpacks/ 目录中编译进程序的词表：瑞士和列支敦士登的地名、常见姓氏
+//! 和瑞士各州的缩写。词包中的名称按当地的拼写收录(如 Zürich、St. Gallen、Val d'Anniviers)，
+//! 含空格、点或撇号的名称分别产生以连字符连接和直接相连的两种标签(st-gallen、stgallen)，
+//! 之后与 `--wordlist` 一样经 IDN 处理转换为小写的 A-label，不合法的标签被跳过。
+
+use clap::ValueEnum;
+use std::collections::HashSet;
+
+use crate::generate::normalize_label;
+
+/// 内置的词包
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Wordpack {
+    /// 瑞士的城镇、地区、山峰和湖泊
+    ChPlaces,
+    /// 列支敦士登的市镇、村落和山峰
+    LiPlaces,
+    /// 瑞士常见的姓氏
+    ChSurnames,
+    /// 列支敦士登常见的姓氏
+    LiSurnames,
+    /// 瑞士各州的缩写(zh、be、ti 等)
+    ChCantons,
+}
+
+impl Wordpack {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ChPlaces => "ch-places",
+            Self::LiPlaces => "li-places",
+            Self::ChSurnames => "ch-surnames",
+            Self::LiSurnames => "li-surnames",
+            Self::ChCantons => "ch-cantons",
+        }
+    }
+
+    fn content(self) -> &'static str {
+        match self {
+            Self::ChPlaces => include_str!("../wordpacks/ch-places.txt"),
+            Self::LiPlaces => include_str!("../wordpacks/li-places.txt"),
+            Self::ChSurnames => include_str!("../wordpacks/ch-surnames.txt"),
+            Self::LiSurnames => include_str!("../wordpacks/li-surnames.txt"),
+            Self::ChCantons => include_str!("../wordpacks/ch-cantons.txt"),
+        }
+    }
+
+    /// 词包产生的标签，按词包中的顺序，去除重复
+    pub fn labels(self) -> std::vec::IntoIter<String> {
+        let mut seen = HashSet::new();
+        let mut labels = Vec::new();
+        for line in self.content().lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            for label in variants(line).iter().filter_map(|variant| normalize_label(variant)) {
+                if seen.insert(label.clone()) {
+                    labels.push(label);
+                }
+            }
+        }
+        labels.into_iter()
+    }
+}
+
+/// 名称中的空格、点和撇号换为连字符的形式，以及去掉这些字符和连字符后直接相连的形式
+fn variants(name: &str) -> Vec<String> {
+    let words: Vec<&str> = name
+        .split(|c: char| c.is_whitespace() || matches!(c, '.' | '\'' | '’'))
+        .filter(|word| !word.is_empty())
+        .collect();
+    let hyphenated = words.join("-");
+    let joined: String = words.concat().chars().filter(|&c| c != '-').collect();
+    if hyphenated == joined {
+        vec![hyphenated]
+    } else {
+        vec![hyphenated, joined]
+    }
+}
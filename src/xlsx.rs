@@ -0,0 +1,679 @@
+//! 最小的 XLSX 写入
+//!
+//! XLSX 是由若干 XML 文件组成的 ZIP 包。这里只实现导出报告需要的部分：文本和数字单元格、
+//! 加粗并冻结的表头、自动筛选、按内容估算的列宽，以及引用工作表数据的柱形图。ZIP 条目不压缩
+//! (存储方式)，不依赖外部程序。工作表逐行写入文件，不在内存中生成整个工作簿；归档超过 4 GiB
+//! 时中央目录使用 ZIP64 记录。每个工作表最多 [`MAX_ROWS`] 行，由调用方拆分更多的行。
+
+use anyhow::{Context, Result};
+use console::measure_text_width;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::atomic;
+
+/// Excel 工作表的最大行数(含表头)
+pub const MAX_ROWS: usize = 1_048_576;
+
+/// 单元格
+#[derive(Debug, Clone)]
+pub enum Cell {
+    Empty,
+    Text(String),
+    Number(f64),
+    /// 以百分比格式显示的比例(0.5 显示为 50.0%)
+    Percent(f64),
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<Option<String>> for Cell {
+    fn from(text: Option<String>) -> Self {
+        text.map_or(Self::Empty, Self::Text)
+    }
+}
+
+/// 柱形图，类别和数值取自所在工作表同一行范围内的两列
+#[derive(Debug, Clone)]
+pub struct Chart {
+    pub title: String,
+    /// 数据所在的行(从 0 开始，含两端)
+    pub rows: (usize, usize),
+    pub category_column: usize,
+    pub value_column: usize,
+    /// 图表左上角所在的列和行
+    pub anchor: (usize, usize),
+}
+
+/// 工作表，第一行为冻结的表头，之后可以再加入其他表格的表头
+#[derive(Debug, Clone)]
+pub struct Sheet {
+    name: String,
+    rows: Vec<Vec<Cell>>,
+    /// 加粗显示的表头行
+    header_rows: Vec<usize>,
+    charts: Vec<Chart>,
+}
+
+impl Sheet {
+    pub fn new(name: &str, headers: &[&str]) -> Self {
+        let mut sheet = Self {
+            name: name.to_string(),
+            rows: Vec::new(),
+            header_rows: Vec::new(),
+            charts: Vec::new(),
+        };
+        sheet.header(headers);
+        sheet
+    }
+
+    /// 加入一行表头
+    pub fn header(&mut self, headers: &[&str]) {
+        self.header_rows.push(self.rows.len());
+        self.rows.push(headers.iter().map(|&header| Cell::from(header)).collect());
+    }
+
+    pub fn row(&mut self, cells: Vec<Cell>) {
+        self.rows.push(cells);
+    }
+
+    /// 下一行的行号(从 0 开始)，供图表引用
+    pub fn next_row(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn chart(&mut self, chart: Chart) {
+        self.charts.push(chart);
+    }
+}
+
+/// 边写入边打包的工作簿，调用 [`Workbook::finish`] 后才替换目标文件
+pub struct Workbook {
+    zip: ZipWriter<BufWriter<File>>,
+    tmp: PathBuf,
+    path: PathBuf,
+    sheets: Vec<String>,
+    overrides: Vec<(String, &'static str)>,
+    chart_count: usize,
+}
+
+impl Workbook {
+    pub fn create(path: &Path) -> Result<Self> {
+        let tmp = atomic::temp_path(path);
+        let file = File::create(&tmp).with_context(|| format!("Failed to create {}", tmp.display()))?;
+        Ok(Self {
+            zip: ZipWriter::new(BufWriter::new(file)),
+            tmp,
+            path: path.to_path_buf(),
+            sheets: Vec::new(),
+            overrides: vec![
+                ("/xl/workbook.xml".to_string(), "spreadsheetml.sheet.main+xml"),
+                ("/xl/styles.xml".to_string(), "spreadsheetml.styles+xml"),
+            ],
+            chart_count: 0,
+        })
+    }
+
+    /// 写入内存中的工作表及其图表，第一个写入的工作表为打开时显示的表
+    pub fn add(&mut self, sheet: &Sheet) -> Result<()> {
+        let drawing = !sheet.charts.is_empty();
+        let number = self.write_sheet(&sheet.name, sheet.rows.iter().cloned(), &sheet.header_rows, drawing)?;
+        if !drawing {
+            return Ok(());
+        }
+        let mut anchors = String::new();
+        let mut drawing_rels = String::new();
+        for (position, chart) in sheet.charts.iter().enumerate() {
+            self.chart_count += 1;
+            anchors.push_str(&anchor(chart, position + 1));
+            drawing_rels.push_str(&relationship(
+                position + 1,
+                "chart",
+                &format!("../charts/chart{}.xml", self.chart_count),
+            ));
+            self.overrides
+                .push((format!("/xl/charts/chart{}.xml", self.chart_count), "drawingml.chart+xml"));
+            self.zip
+                .text(&format!("xl/charts/chart{}.xml", self.chart_count), &chart_xml(sheet, chart))?;
+        }
+        self.overrides.push((format!("/xl/drawings/drawing{}.xml", number), "drawing+xml"));
+        self.zip.text(
+            &format!("xl/drawings/drawing{}.xml", number),
+            &format!(
+                r#"{}<xdr:wsDr xmlns:xdr="{}" xmlns:a="{}" xmlns:r="{}" xmlns:c="{}">{}</xdr:wsDr>"#,
+                XML_HEADER, NS_DRAWING, NS_MAIN_DRAWING, NS_RELATIONSHIPS, NS_CHART, anchors
+            ),
+        )?;
+        self.zip
+            .text(&format!("xl/drawings/_rels/drawing{}.xml.rels", number), &relationships(&drawing_rels))?;
+        self.zip.text(
+            &format!("xl/worksheets/_rels/sheet{}.xml.rels", number),
+            &relationships(&relationship(1, "drawing", &format!("../drawings/drawing{}.xml", number))),
+        )
+    }
+
+    /// 逐行写入只有一行表头的工作表，`rows` 会被遍历两次(第一次估算列宽)
+    pub fn stream<I>(&mut self, name: &str, headers: &[&str], rows: I) -> Result<()>
+    where
+        I: Iterator<Item = Vec<Cell>> + Clone,
+    {
+        let header: Vec<Cell> = headers.iter().map(|&header| Cell::from(header)).collect();
+        self.write_sheet(name, std::iter::once(header).chain(rows), &[0], false)?;
+        Ok(())
+    }
+
+    /// 写入工作簿的其余部分和 ZIP 目录，替换目标文件
+    pub fn finish(self) -> Result<()> {
+        let Self {
+            mut zip,
+            tmp,
+            path,
+            sheets,
+            overrides,
+            ..
+        } = self;
+        let mut workbook_sheets = String::new();
+        let mut workbook_rels = String::new();
+        for (index, name) in sheets.iter().enumerate() {
+            let number = index + 1;
+            workbook_sheets.push_str(&format!(
+                r#"<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#,
+                escape(name),
+                number,
+                number
+            ));
+            workbook_rels.push_str(&relationship(number, "worksheet", &format!("worksheets/sheet{}.xml", number)));
+        }
+        let overrides: String = overrides
+            .iter()
+            .map(|(part, kind)| {
+                format!(
+                    r#"<Override PartName="{}" ContentType="application/vnd.openxmlformats-officedocument.{}"/>"#,
+                    part, kind
+                )
+            })
+            .collect();
+        let styles_rel = relationship(sheets.len() + 1, "styles", "styles.xml");
+        zip.text(
+            "[Content_Types].xml",
+            &format!(
+                r#"{}<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/>{}</Types>"#,
+                XML_HEADER, overrides
+            ),
+        )?;
+        zip.text("_rels/.rels", &relationships(&relationship(1, "officeDocument", "xl/workbook.xml")))?;
+        zip.text(
+            "xl/workbook.xml",
+            &format!(
+                r#"{}<workbook xmlns="{}" xmlns:r="{}"><sheets>{}</sheets></workbook>"#,
+                XML_HEADER, NS_SPREADSHEET, NS_RELATIONSHIPS, workbook_sheets
+            ),
+        )?;
+        zip.text(
+            "xl/_rels/workbook.xml.rels",
+            &relationships(&format!("{}{}", workbook_rels, styles_rel)),
+        )?;
+        zip.text("xl/styles.xml", STYLES)?;
+        zip.finish()
+            .and_then(|writer| writer.into_inner().map_err(|e| e.into_error()))
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        atomic::commit(&tmp, &path)
+    }
+
+    /// 写入一个工作表，返回工作表的编号(从 1 开始)
+    fn write_sheet<I>(&mut self, name: &str, rows: I, header_rows: &[usize], drawing: bool) -> Result<usize>
+    where
+        I: Iterator<Item = Vec<Cell>> + Clone,
+    {
+        // 列宽按最长内容估算，中文按两个字符计算；<cols> 在数据之前，需要先遍历一次
+        let mut widths: Vec<usize> = Vec::new();
+        let mut count = 0;
+        for row in rows.clone() {
+            count += 1;
+            if widths.len() < row.len() {
+                widths.resize(row.len(), 0);
+            }
+            for (width, cell) in widths.iter_mut().zip(&row) {
+                *width = (*width).max(cell_width(cell));
+            }
+        }
+        anyhow::ensure!(
+            count <= MAX_ROWS,
+            "Sheet '{}' has {} rows, Excel supports at most {}",
+            name,
+            count,
+            MAX_ROWS
+        );
+        self.sheets.push(name.to_string());
+        let number = self.sheets.len();
+        self.overrides
+            .push((format!("/xl/worksheets/sheet{}.xml", number), "spreadsheetml.worksheet+xml"));
+        self.zip.entry(&format!("xl/worksheets/sheet{}.xml", number), |out| {
+            write_worksheet(out, rows, &widths, count, header_rows, drawing)
+        })?;
+        Ok(number)
+    }
+}
+
+const XML_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#;
+const NS_SPREADSHEET: &str = "http://schemas.openxmlformats.org/spreadsheetml/2006/main";
+const NS_RELATIONSHIPS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+const NS_DRAWING: &str = "http://schemas.openxmlformats.org/drawingml/2006/spreadsheetDrawing";
+const NS_MAIN_DRAWING: &str = "http://schemas.openxmlformats.org/drawingml/2006/main";
+const NS_CHART: &str = "http://schemas.openxmlformats.org/drawingml/2006/chart";
+
+/// 样式 0 为默认，1 为加粗的表头，2 为百分比
+const STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><fonts count="2"><font><sz val="11"/><name val="Calibri"/></font><font><b/><sz val="11"/><name val="Calibri"/></font></fonts><fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills><borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders><cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs><cellXfs count="3"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/><xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/><xf numFmtId="10" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/></cellXfs><cellStyles count="1"><cellStyle name="Normal" xfId="0" builtinId="0"/></cellStyles></styleSheet>"#;
+
+fn relationship(id: usize, kind: &str, target: &str) -> String {
+    format!(
+        r#"<Relationship Id="rId{}" Type="{}/{}" Target="{}"/>"#,
+        id, NS_RELATIONSHIPS, kind, target
+    )
+}
+
+fn relationships(content: &str) -> String {
+    format!(
+        r#"{}<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{}</Relationships>"#,
+        XML_HEADER, content
+    )
+}
+
+fn cell_width(cell: &Cell) -> usize {
+    match cell {
+        Cell::Text(text) => measure_text_width(text),
+        Cell::Number(number) => number.to_string().len(),
+        Cell::Percent(_) => 6,
+        Cell::Empty => 0,
+    }
+}
+
+fn write_worksheet(
+    out: &mut impl Write,
+    rows: impl Iterator<Item = Vec<Cell>>,
+    widths: &[usize],
+    count: usize,
+    header_rows: &[usize],
+    drawing: bool,
+) -> io::Result<()> {
+    write!(
+        out,
+        r#"{}<worksheet xmlns="{}" xmlns:r="{}"><sheetViews><sheetView workbookViewId="0"><pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/></sheetView></sheetViews><cols>"#,
+        XML_HEADER, NS_SPREADSHEET, NS_RELATIONSHIPS
+    )?;
+    for (column, width) in widths.iter().enumerate() {
+        write!(
+            out,
+            r#"<col min="{}" max="{}" width="{}" customWidth="1"/>"#,
+            column + 1,
+            column + 1,
+            width.clamp(&6, &60) + 2
+        )?;
+    }
+    out.write_all(b"</cols><sheetData>")?;
+
+    for (row_index, row) in rows.enumerate() {
+        write!(out, r#"<row r="{}">"#, row_index + 1)?;
+        for (column, cell) in row.iter().enumerate() {
+            let reference = cell_reference(column, row_index);
+            let header = if header_rows.contains(&row_index) { r#" s="1""# } else { "" };
+            match cell {
+                Cell::Empty => {}
+                Cell::Text(text) => write!(
+                    out,
+                    r#"<c r="{}" t="inlineStr"{}><is><t xml:space="preserve">{}</t></is></c>"#,
+                    reference,
+                    header,
+                    escape(text)
+                )?,
+                Cell::Number(number) => write!(out, r#"<c r="{}"{}><v>{}</v></c>"#, reference, header, number)?,
+                Cell::Percent(ratio) => write!(out, r#"<c r="{}" s="2"><v>{}</v></c>"#, reference, ratio)?,
+            }
+        }
+        out.write_all(b"</row>")?;
+    }
+    out.write_all(b"</sheetData>")?;
+
+    if count > 1 && !widths.is_empty() && !drawing {
+        write!(out, r#"<autoFilter ref="A1:{}"/>"#, cell_reference(widths.len() - 1, count - 1))?;
+    }
+    if drawing {
+        out.write_all(br#"<drawing r:id="rId1"/>"#)?;
+    }
+    out.write_all(b"</worksheet>")
+}
+
+/// 图表在工作表中占据的位置，宽 8 列、高 15 行
+fn anchor(chart: &Chart, id: usize) -> String {
+    let (column, row) = chart.anchor;
+    let marker = |column: usize, row: usize| {
+        format!(
+            "<xdr:col>{}</xdr:col><xdr:colOff>0</xdr:colOff><xdr:row>{}</xdr:row><xdr:rowOff>0</xdr:rowOff>",
+            column, row
+        )
+    };
+    format!(
+        r#"<xdr:twoCellAnchor><xdr:from>{}</xdr:from><xdr:to>{}</xdr:to><xdr:graphicFrame macro=""><xdr:nvGraphicFramePr><xdr:cNvPr id="{}" name="Chart {}"/><xdr:cNvGraphicFramePr/></xdr:nvGraphicFramePr><xdr:xfrm><a:off x="0" y="0"/><a:ext cx="0" cy="0"/></xdr:xfrm><a:graphic><a:graphicData uri="{}"><c:chart r:id="rId{}"/></a:graphicData></a:graphic></xdr:graphicFrame><xdr:clientData/></xdr:twoCellAnchor>"#,
+        marker(column, row),
+        marker(column + 8, row + 15),
+        id + 1,
+        id,
+        NS_CHART,
+        id
+    )
+}
+
+fn chart_xml(sheet: &Sheet, chart: &Chart) -> String {
+    let (first, last) = chart.rows;
+    let range = |column: usize| {
+        format!(
+            "'{}'!${}${}:${}${}",
+            escape(&sheet.name.replace('\'', "''")),
+            column_name(column),
+            first + 1,
+            column_name(column),
+            last + 1
+        )
+    };
+    let cells = |column: usize| (first..=last).map(move |row| sheet.rows.get(row).and_then(|row| row.get(column)));
+    let mut categories = String::new();
+    for (index, cell) in cells(chart.category_column).enumerate() {
+        let text = match cell {
+            Some(Cell::Text(text)) => text.clone(),
+            Some(Cell::Number(number)) => number.to_string(),
+            _ => String::new(),
+        };
+        categories.push_str(&format!(r#"<c:pt idx="{}"><c:v>{}</c:v></c:pt>"#, index, escape(&text)));
+    }
+    let mut values = String::new();
+    for (index, cell) in cells(chart.value_column).enumerate() {
+        if let Some(Cell::Number(value) | Cell::Percent(value)) = cell {
+            values.push_str(&format!(r#"<c:pt idx="{}"><c:v>{}</c:v></c:pt>"#, index, value));
+        }
+    }
+    let count = last + 1 - first;
+    let format = match cells(chart.value_column).next().flatten() {
+        Some(Cell::Percent(_)) => "0.0%",
+        _ => "General",
+    };
+    // 系列名称取数据上方的表头
+    let series_name = match first.checked_sub(1).and_then(|row| sheet.rows.get(row)?.get(chart.value_column)) {
+        Some(Cell::Text(text)) => text.clone(),
+        _ => String::new(),
+    };
+    format!(
+        r#"{header}<c:chartSpace xmlns:c="{c}" xmlns:a="{a}" xmlns:r="{r}"><c:chart><c:title><c:tx><c:rich><a:bodyPr/><a:p><a:r><a:t>{title}</a:t></a:r></a:p></c:rich></c:tx><c:overlay val="0"/></c:title><c:autoTitleDeleted val="0"/><c:plotArea><c:layout/><c:barChart><c:barDir val="col"/><c:grouping val="clustered"/><c:varyColors val="0"/><c:ser><c:idx val="0"/><c:order val="0"/><c:tx><c:v>{series}</c:v></c:tx><c:cat><c:strRef><c:f>{category_range}</c:f><c:strCache><c:ptCount val="{count}"/>{categories}</c:strCache></c:strRef></c:cat><c:val><c:numRef><c:f>{value_range}</c:f><c:numCache><c:formatCode>{format}</c:formatCode><c:ptCount val="{count}"/>{values}</c:numCache></c:numRef></c:val></c:ser><c:gapWidth val="80"/><c:axId val="1"/><c:axId val="2"/></c:barChart><c:catAx><c:axId val="1"/><c:scaling><c:orientation val="minMax"/></c:scaling><c:delete val="0"/><c:axPos val="b"/><c:crossAx val="2"/><c:crosses val="autoZero"/></c:catAx><c:valAx><c:axId val="2"/><c:scaling><c:orientation val="minMax"/></c:scaling><c:delete val="0"/><c:axPos val="l"/><c:majorGridlines/><c:numFmt formatCode="{format}" sourceLinked="1"/><c:crossAx val="1"/><c:crosses val="autoZero"/></c:valAx></c:plotArea><c:plotVisOnly val="1"/></c:chart></c:chartSpace>"#,
+        header = XML_HEADER,
+        c = NS_CHART,
+        a = NS_MAIN_DRAWING,
+        r = NS_RELATIONSHIPS,
+        title = escape(&chart.title),
+        series = escape(&series_name),
+        category_range = range(chart.category_column),
+        value_range = range(chart.value_column),
+        count = count,
+        categories = categories,
+        values = values,
+        format = format,
+    )
+}
+
+/// 列号(从 0 开始)对应的列名：A、B、...、Z、AA
+fn column_name(mut column: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (column % 26) as u8);
+        if column < 26 {
+            break;
+        }
+        column = column / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).expect("column names are ASCII")
+}
+
+fn cell_reference(column: usize, row: usize) -> String {
+    format!("{}{}", column_name(column), row + 1)
+}
+
+/// 转义 XML 特殊字符，去掉 XML 中不允许的控制字符
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// 以存储方式(不压缩)逐个条目写入 ZIP，时间戳固定为 1980-01-01
+struct ZipWriter<W: Write + Seek> {
+    out: W,
+    entries: Vec<ZipEntry>,
+}
+
+struct ZipEntry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u64,
+}
+
+const DOS_DATE: u16 = (1 << 5) | 1;
+// 文件名为 UTF-8
+const FLAGS: u16 = 0x0800;
+
+impl<W: Write + Seek> ZipWriter<W> {
+    fn new(out: W) -> Self {
+        Self { out, entries: Vec::new() }
+    }
+
+    fn text(&mut self, name: &str, content: &str) -> Result<()> {
+        self.entry(name, |out| out.write_all(content.as_bytes()))
+    }
+
+    /// 写入一个条目：先写占位的文件头，写完数据后回到文件头补写校验和与长度
+    fn entry(&mut self, name: &str, write: impl FnOnce(&mut Checksum<&mut W>) -> io::Result<()>) -> Result<()> {
+        let offset = self.out.stream_position()?;
+        self.out.write_all(&local_header(name, 0, 0))?;
+        let mut data = Checksum::new(&mut self.out);
+        write(&mut data)?;
+        let (crc, size) = data.finish();
+        let size = u32::try_from(size).map_err(|_| anyhow::anyhow!("{} is larger than 4 GiB", name))?;
+        let end = self.out.stream_position()?;
+        self.out.seek(SeekFrom::Start(offset))?;
+        self.out.write_all(&local_header(name, crc, size))?;
+        self.out.seek(SeekFrom::Start(end))?;
+        self.entries.push(ZipEntry {
+            name: name.to_string(),
+            crc,
+            size,
+            offset,
+        });
+        Ok(())
+    }
+
+    /// 写入中央目录，超出 32 位的偏移和条目数使用 ZIP64 记录
+    fn finish(mut self) -> io::Result<W> {
+        let central_offset = self.out.stream_position()?;
+        let mut central = Vec::new();
+        for entry in &self.entries {
+            let zip64 = entry.offset > u64::from(u32::MAX);
+            let version: u16 = if zip64 { 45 } else { 20 };
+            central.extend_from_slice(&0x02014b50u32.to_le_bytes());
+            central.extend_from_slice(&version.to_le_bytes());
+            central.extend_from_slice(&version.to_le_bytes());
+            central.extend_from_slice(&FLAGS.to_le_bytes());
+            central.extend_from_slice(&0u16.to_le_bytes());
+            central.extend_from_slice(&0u16.to_le_bytes());
+            central.extend_from_slice(&DOS_DATE.to_le_bytes());
+            central.extend_from_slice(&entry.crc.to_le_bytes());
+            central.extend_from_slice(&entry.size.to_le_bytes());
+            central.extend_from_slice(&entry.size.to_le_bytes());
+            central.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            central.extend_from_slice(&(if zip64 { 12u16 } else { 0 }).to_le_bytes());
+            central.extend_from_slice(&[0; 10]);
+            central.extend_from_slice(&(if zip64 { u32::MAX } else { entry.offset as u32 }).to_le_bytes());
+            central.extend_from_slice(entry.name.as_bytes());
+            if zip64 {
+                central.extend_from_slice(&1u16.to_le_bytes());
+                central.extend_from_slice(&8u16.to_le_bytes());
+                central.extend_from_slice(&entry.offset.to_le_bytes());
+            }
+        }
+        self.out.write_all(&central)?;
+
+        let count = self.entries.len() as u64;
+        let size = central.len() as u64;
+        let zip64 = central_offset > u64::from(u32::MAX) || size > u64::from(u32::MAX) || count > u64::from(u16::MAX);
+        if zip64 {
+            let record_offset = central_offset + size;
+            self.out.write_all(&0x06064b50u32.to_le_bytes())?;
+            self.out.write_all(&44u64.to_le_bytes())?;
+            self.out.write_all(&45u16.to_le_bytes())?;
+            self.out.write_all(&45u16.to_le_bytes())?;
+            self.out.write_all(&[0; 8])?;
+            self.out.write_all(&count.to_le_bytes())?;
+            self.out.write_all(&count.to_le_bytes())?;
+            self.out.write_all(&size.to_le_bytes())?;
+            self.out.write_all(&central_offset.to_le_bytes())?;
+            self.out.write_all(&0x07064b50u32.to_le_bytes())?;
+            self.out.write_all(&0u32.to_le_bytes())?;
+            self.out.write_all(&record_offset.to_le_bytes())?;
+            self.out.write_all(&1u32.to_le_bytes())?;
+        }
+        let count16 = if zip64 { u16::MAX } else { count as u16 };
+        self.out.write_all(&0x06054b50u32.to_le_bytes())?;
+        self.out.write_all(&[0; 4])?;
+        self.out.write_all(&count16.to_le_bytes())?;
+        self.out.write_all(&count16.to_le_bytes())?;
+        self.out.write_all(&(if zip64 { u32::MAX } else { size as u32 }).to_le_bytes())?;
+        self.out.write_all(&(if zip64 { u32::MAX } else { central_offset as u32 }).to_le_bytes())?;
+        self.out.write_all(&0u16.to_le_bytes())?;
+        Ok(self.out)
+    }
+}
+
+fn local_header(name: &str, crc: u32, size: u32) -> Vec<u8> {
+    let mut header = Vec::with_capacity(30 + name.len());
+    header.extend_from_slice(&0x04034b50u32.to_le_bytes());
+    header.extend_from_slice(&20u16.to_le_bytes());
+    header.extend_from_slice(&FLAGS.to_le_bytes());
+    header.extend_from_slice(&0u16.to_le_bytes());
+    header.extend_from_slice(&0u16.to_le_bytes());
+    header.extend_from_slice(&DOS_DATE.to_le_bytes());
+    header.extend_from_slice(&crc.to_le_bytes());
+    header.extend_from_slice(&size.to_le_bytes());
+    header.extend_from_slice(&size.to_le_bytes());
+    header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    header.extend_from_slice(&0u16.to_le_bytes());
+    header.extend_from_slice(name.as_bytes());
+    header
+}
+
+/// 写入时计算 CRC-32 和长度
+struct Checksum<W: Write> {
+    inner: W,
+    crc: u32,
+    size: u64,
+}
+
+impl<W: Write> Checksum<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, crc: !0, size: 0 }
+    }
+
+    fn finish(self) -> (u32, u64) {
+        (!self.crc, self.size)
+    }
+}
+
+impl<W: Write> Write for Checksum<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        for &byte in &buf[..written] {
+            self.crc = CRC_TABLE[((self.crc ^ u32::from(byte)) & 0xFF) as usize] ^ (self.crc >> 8);
+        }
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksum_matches_crc32() {
+        let mut data = Checksum::new(io::sink());
+        data.write_all(b"123456789").unwrap();
+        assert_eq!(data.finish(), (0xCBF4_3926, 9));
+    }
+
+    #[test]
+    fn entries_are_listed_in_the_central_directory() {
+        let mut zip = ZipWriter::new(io::Cursor::new(Vec::new()));
+        zip.text("a.xml", "<a/>").unwrap();
+        zip.text("b.xml", "").unwrap();
+        let archive = zip.finish().unwrap().into_inner();
+        assert_eq!(&archive[..4], &0x04034b50u32.to_le_bytes());
+        // 本地文件头中补写了校验和与长度
+        assert_eq!(&archive[18..22], &4u32.to_le_bytes());
+        let end = &archive[archive.len() - 22..];
+        assert_eq!(&end[..4], &0x06054b50u32.to_le_bytes());
+        assert_eq!(&end[10..12], &2u16.to_le_bytes());
+    }
+
+    #[test]
+    fn sheets_are_limited_to_the_excel_row_count() {
+        let dir = std::env::temp_dir().join(format!("xlsx-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut workbook = Workbook::create(&dir.join("book.xlsx")).unwrap();
+        let rows = std::iter::repeat_n(Vec::new(), MAX_ROWS - 1);
+        assert!(workbook.stream("full", &["a"], rows.clone()).is_ok());
+        assert!(workbook.stream("over", &["a"], rows.chain([Vec::new()])).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}